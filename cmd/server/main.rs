@@ -1,8 +1,13 @@
 use core::panic;
-use std::fs;
+use std::{ env, fs, process };
 
 use config::{ init_logger, Configuration, CONFIGURATION };
-use internal::{ container::container::new, infra::http::server, migrate };
+use internal::{
+    container::container::{ new, Container },
+    infra::http::server,
+    migrate,
+    tasks::session_cleanup::start_session_cleanup,
+};
 
 #[actix_web::main]
 async fn main() {
@@ -16,11 +21,41 @@ async fn main() {
     }
 
     match new() {
-        Ok(container) =>
+        Ok(container) => {
+            let args: Vec<String> = env::args().skip(1).collect();
+            if !args.is_empty() {
+                return run_command(&container, &args);
+            }
+            start_session_cleanup(container.services.auth_service.clone());
             match server::start_server(container).await {
                 Ok(res) => res,
                 Err(e) => panic!("{}", e.to_string()),
             }
+        }
         Err(e) => panic!("{}", e.to_string()),
     }
 }
+
+/// Maintenance commands, e.g. `server unlock-user user@example.com`.
+fn run_command(container: &Container, args: &[String]) {
+    let services = &container.services;
+    let result = match (args[0].as_str(), args.get(1), args.get(2)) {
+        ("unlock-user", Some(email), None) => {
+            services.auth_service.unlock_user(email).map_err(|e| e.to_string())
+        }
+        ("grant-role", Some(email), Some(role)) => {
+            services.role_service.grant_by_email(email, role).map(|_| ()).map_err(|e| e.to_string())
+        }
+        _ => {
+            eprintln!("Usage:\n  server unlock-user <email>\n  server grant-role <email> <role>");
+            process::exit(2);
+        }
+    };
+    match result {
+        Ok(_) => println!("Done"),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
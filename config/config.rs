@@ -1,3 +1,4 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use rust_commons::config::database_config::DatabaseConfig;
@@ -14,6 +15,262 @@ lazy_static! {
     };
 }
 
+/// Defines what unverified users are not allowed to do.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmailVerificationPolicy {
+    /// Verification emails are sent, but nothing is blocked.
+    Disabled,
+    /// Unverified users can not log in.
+    Login,
+    /// Unverified users can not access routes guarded with `verified_middleware`.
+    Routes,
+}
+
+impl EmailVerificationPolicy {
+    fn from_var(value: &str) -> EmailVerificationPolicy {
+        match value.to_lowercase().as_str() {
+            "disabled" => EmailVerificationPolicy::Disabled,
+            "login" => EmailVerificationPolicy::Login,
+            "routes" => EmailVerificationPolicy::Routes,
+            _ => {
+                log::error!("Unknown email verification policy [{}], using [routes]", value);
+                EmailVerificationPolicy::Routes
+            }
+        }
+    }
+}
+
+/// Defines where outgoing emails are delivered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MailTransport {
+    Smtp,
+    /// Emails are written as files to `mail_storage_location`, useful for local development.
+    File,
+    /// Emails are kept in memory, useful for tests.
+    Memory,
+}
+
+impl MailTransport {
+    fn from_var(value: &str) -> MailTransport {
+        match value.to_lowercase().as_str() {
+            "smtp" => MailTransport::Smtp,
+            "file" => MailTransport::File,
+            "memory" => MailTransport::Memory,
+            _ => {
+                log::error!("Unknown mail transport [{}], using [file]", value);
+                MailTransport::File
+            }
+        }
+    }
+}
+
+/// Algorithm used to sign access tokens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JwtAlgorithm {
+    /// Shared `jwt_secret`, tokens can be verified only by this server. Meant for development.
+    HS256,
+    RS256,
+    EdDSA,
+}
+
+impl JwtAlgorithm {
+    fn from_var(value: &str) -> JwtAlgorithm {
+        match value.to_uppercase().as_str() {
+            "HS256" => JwtAlgorithm::HS256,
+            "RS256" => JwtAlgorithm::RS256,
+            "EDDSA" => JwtAlgorithm::EdDSA,
+            _ => {
+                log::error!("Unknown JWT algorithm [{}], using [HS256]", value);
+                JwtAlgorithm::HS256
+            }
+        }
+    }
+}
+
+/// `SameSite` attribute of auth cookies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    /// Needed only when the front end is served from another site.
+    None,
+}
+
+impl CookieSameSite {
+    fn from_var(value: &str) -> CookieSameSite {
+        match value.to_lowercase().as_str() {
+            "strict" => CookieSameSite::Strict,
+            "lax" => CookieSameSite::Lax,
+            "none" => CookieSameSite::None,
+            _ => {
+                log::error!("Unknown cookie SameSite value [{}], using [lax]", value);
+                CookieSameSite::Lax
+            }
+        }
+    }
+}
+
+/// Algorithm of newly hashed passwords, stored hashes of the other one are still verified.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PasswordHashAlgorithm {
+    Argon2id,
+    /// Kept for hashes created before Argon2 was introduced.
+    Bcrypt,
+}
+
+impl PasswordHashAlgorithm {
+    fn from_var(value: &str) -> PasswordHashAlgorithm {
+        match value.to_lowercase().as_str() {
+            "argon2id" => PasswordHashAlgorithm::Argon2id,
+            "bcrypt" => PasswordHashAlgorithm::Bcrypt,
+            _ => {
+                log::error!("Unknown password hash algorithm [{}], using [argon2id]", value);
+                PasswordHashAlgorithm::Argon2id
+            }
+        }
+    }
+}
+
+/// Character classes a password can be required to contain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PasswordCharacterClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    /// Any character that is not a letter or a digit.
+    Symbol,
+}
+
+/// Parses class names separated with commas, e.g. `lower,upper,digit,symbol`.
+fn parse_password_character_classes(value: &str) -> Vec<PasswordCharacterClass> {
+    return value
+        .split(',')
+        .map(|entry| entry.trim().to_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            match entry.as_str() {
+                "lower" => Some(PasswordCharacterClass::Lowercase),
+                "upper" => Some(PasswordCharacterClass::Uppercase),
+                "digit" => Some(PasswordCharacterClass::Digit),
+                "symbol" => Some(PasswordCharacterClass::Symbol),
+                _ => {
+                    log::error!("Unknown password character class [{}], skipping", entry);
+                    None
+                }
+            }
+        })
+        .collect();
+}
+
+/// Parses `kid:path` pairs separated with commas.
+fn parse_jwt_public_keys(value: &str) -> Vec<(String, String)> {
+    return value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            match entry.split_once(':') {
+                Some((kid, path)) => Some((kid.trim().to_string(), path.trim().to_string())),
+                None => {
+                    log::error!("Invalid JWT public key entry [{}], expected [kid:path]", entry);
+                    None
+                }
+            }
+        })
+        .collect();
+}
+
+/// Proxy allowed to report the client address in `X-Forwarded-For`, an address or a network.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrustedProxy {
+    pub network: IpAddr,
+    pub prefix_len: u8,
+}
+
+impl TrustedProxy {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix_len)).unwrap_or(0);
+                return u32::from(network) & mask == u32::from(ip) & mask;
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix_len)).unwrap_or(0);
+                return u128::from(network) & mask == u128::from(ip) & mask;
+            }
+            _ => false,
+        }
+    }
+
+    fn parse(value: &str) -> Option<TrustedProxy> {
+        let (address, prefix_len) = match value.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len.parse::<u8>().ok()?)),
+            None => (value, None),
+        };
+        let network = address.parse::<IpAddr>().ok()?.to_canonical();
+        let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_prefix_len);
+        if prefix_len > max_prefix_len {
+            return None;
+        }
+        return Some(TrustedProxy { network, prefix_len });
+    }
+}
+
+/// Parses addresses or CIDR networks separated with commas, e.g. `10.0.0.0/8,::1`.
+fn parse_trusted_proxies(value: &str) -> Vec<TrustedProxy> {
+    return value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let proxy = TrustedProxy::parse(entry);
+            if proxy.is_none() {
+                log::error!("Invalid trusted proxy [{}], expected an address or a network", entry);
+            }
+            return proxy;
+        })
+        .collect();
+}
+
+/// External OpenID Connect identity provider.
+#[derive(Clone, Debug)]
+pub struct OidcProviderConfig {
+    /// Used in routes, e.g. `/auth/oidc/{name}/start`.
+    pub name: String,
+    /// Discovery document is loaded from `{issuer}/.well-known/openid-configuration`.
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: String,
+}
+
+/// Reads providers listed in `OIDC_PROVIDERS`, each configured with `OIDC_<NAME>_*` variables.
+fn get_oidc_providers() -> Vec<OidcProviderConfig> {
+    return get_var_or_default("OIDC_PROVIDERS", "")
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| {
+            let prefix = format!("OIDC_{}", name.to_uppercase());
+            let provider = OidcProviderConfig {
+                name: name.to_lowercase(),
+                issuer: get_var_or_default(&format!("{}_ISSUER", prefix), "")
+                    .trim_end_matches('/')
+                    .to_string(),
+                client_id: get_var_or_default(&format!("{}_CLIENT_ID", prefix), ""),
+                client_secret: get_var_or_default(&format!("{}_CLIENT_SECRET", prefix), ""),
+                scopes: get_var_or_default(&format!("{}_SCOPES", prefix), "openid email profile"),
+            };
+            if provider.issuer.is_empty() || provider.client_id.is_empty() {
+                log::error!("OIDC provider [{}] has no issuer or client id, skipping", name);
+                return None;
+            }
+            return Some(provider);
+        })
+        .collect();
+}
+
 pub struct Configuration {
     pub database_name: String,
     pub database_user: String,
@@ -22,8 +279,54 @@ pub struct Configuration {
     pub migration_location: String,
     pub migration_version: String,
     pub file_storage_location: String,
+    pub password_hash_algorithm: PasswordHashAlgorithm,
+    pub argon2_memory_cost: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub bcrypt_cost: u32,
+    pub password_min_length: usize,
+    pub password_required_classes: Vec<PasswordCharacterClass>,
+    pub password_min_entropy: f64,
+    pub password_breached_list_path: String,
     pub jwt_ttl: u64,
     pub jwt_secret: String,
+    pub jwt_algorithm: JwtAlgorithm,
+    pub jwt_private_key_path: String,
+    pub jwt_key_id: String,
+    pub jwt_public_keys: Vec<(String, String)>,
+    pub refresh_token_ttl: u64,
+    pub auth_cookie_mode: bool,
+    pub auth_cookie_same_site: CookieSameSite,
+    pub two_factor_challenge_ttl: u64,
+    pub impersonation_ttl: u64,
+    pub totp_issuer: String,
+    pub mail_transport: MailTransport,
+    pub mail_from: String,
+    pub mail_locale: String,
+    pub mail_storage_location: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    pub smtp_tls: bool,
+    pub app_url: String,
+    pub trusted_proxies: Vec<TrustedProxy>,
+    pub session_prune_interval: u64,
+    pub auth_cache_ttl: u64,
+    pub password_reset_ttl: u64,
+    pub magic_link_ttl: u64,
+    pub email_verification_ttl: u64,
+    pub email_verification_policy: EmailVerificationPolicy,
+    pub login_max_attempts: i32,
+    pub login_max_attempts_per_ip: i32,
+    pub login_attempt_window: u64,
+    pub login_lockout_base: u64,
+    pub login_lockout_max: u64,
+    pub magic_link_max_per_email: i32,
+    pub magic_link_max_per_ip: i32,
+    pub login_event_ttl: u64,
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    pub oidc_state_ttl: u64,
 }
 
 impl DatabaseConfig for Configuration {
@@ -66,7 +369,96 @@ fn get_configuration() -> Configuration {
         // latest - for running migration to last one in migrations folder.
         migration_version: get_var_or_default("MIGRATE_TO", "latest"),
         file_storage_location: get_var_or_default("FILE_STORAGE_LOCATION", "file_storage"),
-        jwt_ttl: 72 * 3600,
+        // argon2id | bcrypt, hashes with another algorithm or parameters are upgraded on login.
+        password_hash_algorithm: PasswordHashAlgorithm::from_var(
+            &get_var_or_default("PASSWORD_HASH_ALGORITHM", "argon2id")
+        ),
+        // Memory in KiB, defaults follow the OWASP recommendation for Argon2id.
+        argon2_memory_cost: get_var_or_default("ARGON2_MEMORY_COST", "19456")
+            .parse()
+            .unwrap_or(19456),
+        argon2_iterations: get_var_or_default("ARGON2_ITERATIONS", "2").parse().unwrap_or(2),
+        argon2_parallelism: get_var_or_default("ARGON2_PARALLELISM", "1").parse().unwrap_or(1),
+        bcrypt_cost: get_var_or_default("BCRYPT_COST", "10").parse().unwrap_or(10),
+        password_min_length: get_var_or_default("PASSWORD_MIN_LENGTH", "8").parse().unwrap_or(8),
+        // lower | upper | digit | symbol, separated with commas.
+        password_required_classes: parse_password_character_classes(
+            &get_var_or_default("PASSWORD_REQUIRED_CLASSES", "")
+        ),
+        // Estimated bits of entropy, see `PasswordPolicy`.
+        password_min_entropy: get_var_or_default("PASSWORD_MIN_ENTROPY", "40")
+            .parse()
+            .unwrap_or(40.0),
+        // File with SHA-1 hashes of breached passwords, one per line, empty disables the check.
+        password_breached_list_path: get_var_or_default("PASSWORD_BREACHED_LIST_PATH", ""),
+        // Access tokens are short-lived, clients renew them with the refresh token.
+        jwt_ttl: 15 * 60,
         jwt_secret: get_var_or_default("JWT_SECRET", "1234567890"),
+        // HS256 | RS256 | EdDSA
+        jwt_algorithm: JwtAlgorithm::from_var(&get_var_or_default("JWT_ALGORITHM", "HS256")),
+        // PEM private key used to sign tokens with RS256 or EdDSA.
+        jwt_private_key_path: get_var_or_default("JWT_PRIVATE_KEY_PATH", ""),
+        // Written into the `kid` header of issued tokens.
+        jwt_key_id: get_var_or_default("JWT_KEY_ID", ""),
+        // Keys accepted for verification and published in JWKS, e.g. `2024-11:keys/2024-11.pem`.
+        // Must contain the public key of `jwt_key_id`, previous keys are kept during rotation.
+        jwt_public_keys: parse_jwt_public_keys(&get_var_or_default("JWT_PUBLIC_KEYS", "")),
+        refresh_token_ttl: 30 * 24 * 3600,
+        // Tokens are set as HttpOnly cookies instead of being returned in response bodies.
+        auth_cookie_mode: get_var_or_default("AUTH_COOKIE_MODE", "false") == "true",
+        // strict | lax | none
+        auth_cookie_same_site: CookieSameSite::from_var(
+            &get_var_or_default("AUTH_COOKIE_SAME_SITE", "lax")
+        ),
+        two_factor_challenge_ttl: 5 * 60,
+        impersonation_ttl: 30 * 60,
+        // Shown in authenticator apps, must not contain ':'.
+        totp_issuer: get_var_or_default("TOTP_ISSUER", "RustBoilerplate"),
+        // smtp | file | memory
+        mail_transport: MailTransport::from_var(&get_var_or_default("MAIL_TRANSPORT", "file")),
+        mail_from: get_var_or_default("MAIL_FROM", "Rust boilerplate <no-reply@localhost>"),
+        mail_locale: get_var_or_default("MAIL_LOCALE", "en"),
+        mail_storage_location: get_var_or_default("MAIL_STORAGE_LOCATION", "mail_storage"),
+        smtp_host: get_var_or_default("SMTP_HOST", "localhost"),
+        smtp_port: get_var_or_default("SMTP_PORT", "1025").parse().unwrap_or(1025),
+        smtp_username: get_var_or_default("SMTP_USERNAME", ""),
+        smtp_password: get_var_or_default("SMTP_PASSWORD", ""),
+        // Plain connection is used only for local SMTP sinks.
+        smtp_tls: get_var_or_default("SMTP_TLS", "false") == "true",
+        // Used to build links in emails.
+        app_url: get_var_or_default("APP_URL", "http://localhost:8080"),
+        // Only these peers may set the client address with `X-Forwarded-For`, empty trusts none.
+        trusted_proxies: parse_trusted_proxies(&get_var_or_default("TRUSTED_PROXIES", "")),
+        session_prune_interval: 3600,
+        // Seconds `auth_middleware` trusts cached sessions and users, 0 disables the cache.
+        auth_cache_ttl: get_var_or_default("AUTH_CACHE_TTL", "30").parse().unwrap_or(30),
+        password_reset_ttl: 3600,
+        magic_link_ttl: 15 * 60,
+        email_verification_ttl: 24 * 3600,
+        // disabled | login | routes
+        email_verification_policy: EmailVerificationPolicy::from_var(
+            &get_var_or_default("EMAIL_VERIFICATION_POLICY", "routes")
+        ),
+        // Failed logins within the window are counted, older ones are forgotten.
+        login_max_attempts: get_var_or_default("LOGIN_MAX_ATTEMPTS", "5").parse().unwrap_or(5),
+        login_max_attempts_per_ip: get_var_or_default("LOGIN_MAX_ATTEMPTS_PER_IP", "20")
+            .parse()
+            .unwrap_or(20),
+        login_attempt_window: 15 * 60,
+        // Lockout doubles with every failed attempt over the limit, up to `login_lockout_max`.
+        login_lockout_base: 60,
+        login_lockout_max: 3600,
+        // Login link requests within `login_attempt_window`, further ones wait for its end.
+        magic_link_max_per_email: get_var_or_default("MAGIC_LINK_MAX_PER_EMAIL", "3")
+            .parse()
+            .unwrap_or(3),
+        magic_link_max_per_ip: get_var_or_default("MAGIC_LINK_MAX_PER_IP", "10")
+            .parse()
+            .unwrap_or(10),
+        // Login history older than this is pruned by the session cleanup task.
+        login_event_ttl: 90 * 24 * 3600,
+        oidc_providers: get_oidc_providers(),
+        // Time the user has to finish login on the provider's side.
+        oidc_state_ttl: 10 * 60,
     };
 }
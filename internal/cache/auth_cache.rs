@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{ atomic::{ AtomicU64, Ordering }, Arc, RwLock },
+    time::{ Duration, Instant },
+};
+
+use rust_commons::uuid::Uuid;
+use serde::Serialize;
+
+use crate::infra::domain::user::UserDTO;
+
+/// Expired entries are swept once a map grows over this size, then the oldest live ones.
+const MAX_ENTRIES: usize = 10_000;
+/// Live entries evicted at once from a full map, so that it is not scanned on every insert.
+const EVICTION_BATCH: usize = MAX_ENTRIES / 10;
+
+/// In-process cache of valid sessions and users loaded by `auth_middleware`, so hot paths
+/// do not query the database on every request.
+///
+/// Entries live for `ttl` and are dropped explicitly when a user or session changes. The cache
+/// is local to the process, with several instances a revoked session stays valid on the others
+/// for up to `ttl`. Zero `ttl` disables caching.
+///
+/// Every invalidation bumps `generation`. Callers read it before loading an entry from the
+/// database and pass it to `put_*`, which skips the entry when an invalidation ran in between,
+/// so a lookup racing with a revocation can not cache the revoked state again.
+pub struct AuthCache {
+    ttl: Duration,
+    generation: AtomicU64,
+    sessions: RwLock<HashMap<(i32, Uuid), Instant>>,
+    users: RwLock<HashMap<i32, (UserDTO, Instant)>>,
+    session_hits: AtomicU64,
+    session_misses: AtomicU64,
+    user_hits: AtomicU64,
+    user_misses: AtomicU64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct AuthCacheStats {
+    pub session_hits: u64,
+    pub session_misses: u64,
+    pub user_hits: u64,
+    pub user_misses: u64,
+}
+
+impl AuthCache {
+    pub fn new(ttl: Duration) -> Arc<AuthCache> {
+        return Arc::new(AuthCache {
+            ttl,
+            generation: AtomicU64::new(0),
+            sessions: RwLock::new(HashMap::new()),
+            users: RwLock::new(HashMap::new()),
+            session_hits: AtomicU64::new(0),
+            session_misses: AtomicU64::new(0),
+            user_hits: AtomicU64::new(0),
+            user_misses: AtomicU64::new(0),
+        });
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return !self.ttl.is_zero();
+    }
+
+    /// Read before loading an entry from the database, to pass to `put_session`/`put_user`.
+    pub fn generation(&self) -> u64 {
+        return self.generation.load(Ordering::SeqCst);
+    }
+
+    /// Returns `true` when the session was recently confirmed to exist.
+    pub fn has_session(&self, user_id: i32, uuid: &Uuid) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let valid = self.sessions
+            .read()
+            .unwrap()
+            .get(&(user_id, *uuid))
+            .is_some_and(|valid_until| *valid_until > Instant::now());
+        count(if valid { &self.session_hits } else { &self.session_misses });
+        return valid;
+    }
+
+    pub fn put_session(&self, user_id: i32, uuid: &Uuid, generation: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+        let now = Instant::now();
+        let mut sessions = self.sessions.write().unwrap();
+        if self.generation() != generation {
+            return;
+        }
+        make_room(&mut sessions, now, |valid_until| *valid_until);
+        sessions.insert((user_id, *uuid), now + self.ttl);
+    }
+
+    pub fn get_user(&self, user_id: i32) -> Option<UserDTO> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let user = self.users
+            .read()
+            .unwrap()
+            .get(&user_id)
+            .filter(|(_, valid_until)| *valid_until > Instant::now())
+            .map(|(user, _)| user.clone());
+        count(if user.is_some() { &self.user_hits } else { &self.user_misses });
+        return user;
+    }
+
+    pub fn put_user(&self, user: &UserDTO, generation: u64) {
+        let Some(user_id) = *user.id else {
+            return;
+        };
+        if !self.is_enabled() {
+            return;
+        }
+        let now = Instant::now();
+        let mut users = self.users.write().unwrap();
+        if self.generation() != generation {
+            return;
+        }
+        make_room(&mut users, now, |(_, valid_until)| *valid_until);
+        users.insert(user_id, (user.clone(), now + self.ttl));
+    }
+
+    pub fn invalidate_session(&self, user_id: i32, uuid: &Uuid) {
+        let mut sessions = self.sessions.write().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        sessions.remove(&(user_id, *uuid));
+    }
+
+    /// Drops every cached session of the user, e.g. after they were revoked.
+    pub fn invalidate_sessions(&self, user_id: i32) {
+        let mut sessions = self.sessions.write().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        sessions.retain(|(session_user_id, _), _| *session_user_id != user_id);
+    }
+
+    /// Drops the user and all of their sessions.
+    pub fn invalidate_user(&self, user_id: i32) {
+        {
+            let mut users = self.users.write().unwrap();
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            users.remove(&user_id);
+        }
+        self.invalidate_sessions(user_id);
+    }
+
+    pub fn stats(&self) -> AuthCacheStats {
+        return AuthCacheStats {
+            session_hits: self.session_hits.load(Ordering::Relaxed),
+            session_misses: self.session_misses.load(Ordering::Relaxed),
+            user_hits: self.user_hits.load(Ordering::Relaxed),
+            user_misses: self.user_misses.load(Ordering::Relaxed),
+        };
+    }
+}
+
+/// Keeps a full map under `MAX_ENTRIES`. Expired entries go first, when all are live the
+/// oldest ones are evicted, which with a single `ttl` are the ones expiring first.
+fn make_room<K: Eq + Hash, V>(
+    entries: &mut HashMap<K, V>,
+    now: Instant,
+    valid_until: impl Fn(&V) -> Instant
+) {
+    if entries.len() < MAX_ENTRIES {
+        return;
+    }
+    entries.retain(|_, value| valid_until(value) > now);
+    if entries.len() < MAX_ENTRIES {
+        return;
+    }
+    let mut expiries: Vec<Instant> = entries.values().map(&valid_until).collect();
+    let (_, cutoff, _) = expiries.select_nth_unstable(EVICTION_BATCH - 1);
+    let cutoff = *cutoff;
+    entries.retain(|_, value| valid_until(value) > cutoff);
+}
+
+fn count(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ sync::Arc, thread, time::Duration };
+
+    use chrono::Utc;
+    use rust_commons::uuid::Uuid;
+
+    use crate::infra::domain::user::UserDTO;
+
+    use super::{ AuthCache, EVICTION_BATCH, MAX_ENTRIES };
+
+    fn user(id: i32) -> UserDTO {
+        let now = Arc::new(Utc::now().naive_utc());
+        return UserDTO {
+            id: Arc::new(Some(id)),
+            name: Arc::from("Jane"),
+            password: Arc::from(""),
+            email: Arc::from("jane@example.com"),
+            avatar: None,
+            created_date: now.clone(),
+            updated_date: now,
+            deleted_date: Arc::new(None),
+            email_verified_at: Arc::new(None),
+            locked_until: Arc::new(None),
+            locale: None,
+        };
+    }
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        let cache = AuthCache::new(Duration::from_millis(50));
+        let uuid = Uuid::new_v4();
+        cache.put_session(1, &uuid, cache.generation());
+        cache.put_user(&user(1), cache.generation());
+        assert!(cache.has_session(1, &uuid));
+        assert!(cache.get_user(1).is_some());
+
+        thread::sleep(Duration::from_millis(60));
+
+        assert!(!cache.has_session(1, &uuid));
+        assert!(cache.get_user(1).is_none());
+    }
+
+    #[test]
+    fn zero_ttl_disables_cache() {
+        let cache = AuthCache::new(Duration::ZERO);
+        let uuid = Uuid::new_v4();
+        cache.put_session(1, &uuid, cache.generation());
+        cache.put_user(&user(1), cache.generation());
+
+        assert!(!cache.has_session(1, &uuid));
+        assert!(cache.get_user(1).is_none());
+        assert_eq!(cache.stats().session_misses, 0);
+    }
+
+    #[test]
+    fn invalidation_drops_entries() {
+        let cache = AuthCache::new(Duration::from_secs(60));
+        let (first, second, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        cache.put_session(1, &first, cache.generation());
+        cache.put_session(1, &second, cache.generation());
+        cache.put_session(2, &other, cache.generation());
+        cache.put_user(&user(1), cache.generation());
+
+        cache.invalidate_session(1, &first);
+        assert!(!cache.has_session(1, &first));
+        assert!(cache.has_session(1, &second));
+
+        cache.invalidate_user(1);
+        assert!(!cache.has_session(1, &second));
+        assert!(cache.get_user(1).is_none());
+        assert!(cache.has_session(2, &other));
+    }
+
+    #[test]
+    fn put_after_invalidation_is_skipped() {
+        let cache = AuthCache::new(Duration::from_secs(60));
+        let uuid = Uuid::new_v4();
+        let generation = cache.generation();
+
+        cache.invalidate_user(1);
+        cache.put_session(1, &uuid, generation);
+        cache.put_user(&user(1), generation);
+
+        assert!(!cache.has_session(1, &uuid));
+        assert!(cache.get_user(1).is_none());
+    }
+
+    #[test]
+    fn hits_and_misses_are_counted() {
+        let cache = AuthCache::new(Duration::from_secs(60));
+        let uuid = Uuid::new_v4();
+        cache.put_session(1, &uuid, cache.generation());
+        cache.put_user(&user(1), cache.generation());
+
+        cache.has_session(1, &uuid);
+        cache.has_session(1, &uuid);
+        cache.has_session(2, &uuid);
+        cache.get_user(1);
+        cache.get_user(2);
+        cache.get_user(3);
+
+        let stats = cache.stats();
+        assert_eq!((stats.session_hits, stats.session_misses), (2, 1));
+        assert_eq!((stats.user_hits, stats.user_misses), (1, 2));
+    }
+
+    #[test]
+    fn full_cache_evicts_oldest_live_entries() {
+        let cache = AuthCache::new(Duration::from_secs(60));
+        let uuids: Vec<Uuid> = (0..MAX_ENTRIES).map(|_| Uuid::new_v4()).collect();
+        for uuid in &uuids {
+            cache.put_session(1, uuid, cache.generation());
+        }
+        let newest = Uuid::new_v4();
+
+        cache.put_session(1, &newest, cache.generation());
+
+        let sessions = cache.sessions.read().unwrap().len();
+        assert!(sessions <= MAX_ENTRIES - EVICTION_BATCH + 1);
+        assert!(cache.has_session(1, &newest));
+        assert!(!cache.has_session(1, &uuids[0]));
+        assert!(cache.has_session(1, &uuids[MAX_ENTRIES - 1]));
+    }
+}
@@ -1,14 +1,58 @@
 use std::sync::{ Arc, RwLock };
-use config::CONFIGURATION;
+use config::{ MailTransport, CONFIGURATION };
 use diesel::{ r2d2::{ ConnectionManager, Pool }, PgConnection };
 
 use crate::{
+    cache::auth_cache::AuthCache,
     filesystem::image_storage_service::ImageStorageService,
     infra::{
-        database::{ session_repository::SessionRepository, user_repository::UserRepository },
-        http::controllers::{ auth_controller::AuthController, user_controller::UserController },
+        database::{
+            api_key_repository::ApiKeyRepository,
+            email_verification_repository::EmailVerificationRepository,
+            impersonation_event_repository::ImpersonationEventRepository,
+            login_attempt_repository::LoginAttemptRepository,
+            login_event_repository::LoginEventRepository,
+            magic_link_repository::MagicLinkRepository,
+            password_reset_repository::PasswordResetRepository,
+            refresh_token_repository::RefreshTokenRepository,
+            role_repository::RoleRepository,
+            session_repository::SessionRepository,
+            two_factor_repository::TwoFactorRepository,
+            user_identity_repository::UserIdentityRepository,
+            user_repository::UserRepository,
+        },
+        http::controllers::{
+            admin_controller::AdminController,
+            api_key_controller::ApiKeyController,
+            auth_controller::AuthController,
+            introspection_controller::IntrospectionController,
+            oidc_controller::OidcController,
+            two_factor_controller::TwoFactorController,
+            user_controller::UserController,
+        },
+    },
+    jwt::JwtKeys,
+    mail::{
+        file_mailer::FileMailer,
+        memory_mailer::MemoryMailer,
+        smtp_mailer::SmtpMailer,
+        templates::MailTemplates,
+        Mailer,
+    },
+    notifications::{ mail_notifier::MailNotifier, Notifier },
+    password::{ policy::PasswordPolicy, PasswordHasher },
+    services::{
+        api_key_service::ApiKeyService,
+        auth_service::AuthService,
+        email_verification_service::EmailVerificationService,
+        introspection_service::IntrospectionService,
+        login_event_service::LoginEventService,
+        login_throttle_service::LoginThrottleService,
+        oidc_service::OidcService,
+        role_service::RoleService,
+        two_factor_service::TwoFactorService,
+        user_service::UserService,
     },
-    services::{ auth_service::AuthService, user_service::UserService },
 };
 
 #[allow(dead_code)]
@@ -22,14 +66,38 @@ pub struct Container {
 pub struct Services {
     pub user_service: Arc<UserService>,
     pub auth_service: Arc<AuthService>,
+    pub email_verification_service: Arc<EmailVerificationService>,
+    pub two_factor_service: Arc<TwoFactorService>,
+    pub login_throttle_service: Arc<LoginThrottleService>,
+    pub login_event_service: Arc<LoginEventService>,
+    pub role_service: Arc<RoleService>,
+    pub api_key_service: Arc<ApiKeyService>,
+    pub oidc_service: Arc<OidcService>,
+    pub introspection_service: Arc<IntrospectionService>,
 }
 #[derive(Clone)]
 pub struct Controllers {
     pub user_controller: UserController,
     pub auth_controller: AuthController,
+    pub two_factor_controller: TwoFactorController,
+    pub admin_controller: AdminController,
+    pub api_key_controller: ApiKeyController,
+    pub oidc_controller: OidcController,
+    pub introspection_controller: IntrospectionController,
 }
 
 pub fn new() -> Result<Container, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mailer = get_mailer()?;
+    let notifier: Arc<dyn Notifier> = Arc::new(
+        MailNotifier::new(Arc::clone(&mailer), MailTemplates::new(&CONFIGURATION.mail_locale))
+    );
+    return with_notifier(notifier);
+}
+
+/// Builds container with custom notifier, e.g. `MemoryNotifier` to read sent tokens in tests.
+pub fn with_notifier(
+    notifier: Arc<dyn Notifier>
+) -> Result<Container, Box<dyn std::error::Error + Send + Sync + 'static>> {
     let manager = get_database_connection();
     let pool = Pool::builder()
         .max_size(5)
@@ -40,27 +108,140 @@ pub fn new() -> Result<Container, Box<dyn std::error::Error + Send + Sync + 'sta
 
     let user_repository = UserRepository::new(Arc::clone(&pool));
     let session_repository = SessionRepository::new(Arc::clone(&pool));
+    let refresh_token_repository = RefreshTokenRepository::new(Arc::clone(&pool));
+    let password_reset_repository = PasswordResetRepository::new(Arc::clone(&pool));
+    let magic_link_repository = MagicLinkRepository::new(Arc::clone(&pool));
+    let impersonation_event_repository = ImpersonationEventRepository::new(Arc::clone(&pool));
+    let email_verification_repository = EmailVerificationRepository::new(Arc::clone(&pool));
+    let two_factor_repository = TwoFactorRepository::new(Arc::clone(&pool));
+    let login_attempt_repository = LoginAttemptRepository::new(Arc::clone(&pool));
+    let login_event_repository = LoginEventRepository::new(Arc::clone(&pool));
+    let role_repository = RoleRepository::new(Arc::clone(&pool));
+    let api_key_repository = ApiKeyRepository::new(Arc::clone(&pool));
+    let user_identity_repository = UserIdentityRepository::new(Arc::clone(&pool));
+    let jwt_keys = JwtKeys::from_configuration()?;
+    let password_hasher = PasswordHasher::from_configuration()?;
+    let password_policy = PasswordPolicy::from_configuration()?;
+    let auth_cache = AuthCache::new(std::time::Duration::from_secs(CONFIGURATION.auth_cache_ttl));
     let file_service = Arc::new(ImageStorageService::new(&CONFIGURATION.file_storage_location));
+    let email_verification_service = EmailVerificationService::new(
+        Arc::clone(&user_repository),
+        Arc::clone(&email_verification_repository),
+        Arc::clone(&notifier),
+        Arc::clone(&auth_cache)
+    );
+    let two_factor_service = TwoFactorService::new(Arc::clone(&two_factor_repository));
+    let login_throttle_service = LoginThrottleService::new(
+        Arc::clone(&user_repository),
+        Arc::clone(&login_attempt_repository)
+    );
+    let login_event_service = LoginEventService::new(
+        Arc::clone(&login_event_repository),
+        Arc::clone(&notifier)
+    );
+    let user_service = UserService::new(
+        Arc::clone(&user_repository),
+        Arc::clone(&session_repository),
+        Arc::clone(&file_service),
+        Arc::clone(&email_verification_service),
+        Arc::clone(&password_hasher),
+        Arc::clone(&password_policy),
+        Arc::clone(&auth_cache)
+    );
+    let auth_service = AuthService::new(
+        Arc::clone(&user_repository),
+        Arc::clone(&session_repository),
+        Arc::clone(&refresh_token_repository),
+        Arc::clone(&password_reset_repository),
+        Arc::clone(&magic_link_repository),
+        Arc::clone(&impersonation_event_repository),
+        Arc::clone(&file_service),
+        Arc::clone(&notifier),
+        Arc::clone(&email_verification_service),
+        Arc::clone(&two_factor_service),
+        Arc::clone(&login_throttle_service),
+        Arc::clone(&login_event_service),
+        Arc::clone(&jwt_keys),
+        Arc::clone(&password_hasher),
+        Arc::clone(&password_policy),
+        Arc::clone(&auth_cache)
+    );
+    let role_service = RoleService::new(Arc::clone(&role_repository), Arc::clone(&user_repository));
+    let api_key_service = ApiKeyService::new(Arc::clone(&api_key_repository));
     let services: Arc<Services> = Arc::new(Services {
-        user_service: UserService::new(
+        user_service: Arc::clone(&user_service),
+        auth_service: Arc::clone(&auth_service),
+        email_verification_service: Arc::clone(&email_verification_service),
+        two_factor_service: Arc::clone(&two_factor_service),
+        login_throttle_service: Arc::clone(&login_throttle_service),
+        login_event_service: Arc::clone(&login_event_service),
+        role_service: Arc::clone(&role_service),
+        api_key_service: Arc::clone(&api_key_service),
+        oidc_service: OidcService::new(
             Arc::clone(&user_repository),
-            Arc::clone(&session_repository),
-            Arc::clone(&file_service)
+            Arc::clone(&user_identity_repository),
+            Arc::clone(&email_verification_service),
+            Arc::clone(&password_hasher)
         ),
-        auth_service: AuthService::new(
-            Arc::clone(&user_repository),
-            Arc::clone(&session_repository),
-            Arc::clone(&file_service)
+        introspection_service: IntrospectionService::new(
+            Arc::clone(&auth_service),
+            Arc::clone(&user_service),
+            Arc::clone(&role_service),
+            Arc::clone(&api_key_service)
         ),
     });
     let controllers: Controllers = Controllers {
-        user_controller: UserController::new(Arc::clone(&services.user_service)),
-        auth_controller: AuthController::new(Arc::clone(&services.auth_service)),
+        user_controller: UserController::new(
+            Arc::clone(&services.user_service),
+            Arc::clone(&services.login_event_service)
+        ),
+        auth_controller: AuthController::new(
+            Arc::clone(&services.auth_service),
+            Arc::clone(&services.email_verification_service)
+        ),
+        two_factor_controller: TwoFactorController::new(
+            Arc::clone(&services.two_factor_service)
+        ),
+        admin_controller: AdminController::new(
+            Arc::clone(&services.role_service),
+            Arc::clone(&services.login_throttle_service),
+            Arc::clone(&services.auth_service)
+        ),
+        api_key_controller: ApiKeyController::new(Arc::clone(&services.api_key_service)),
+        oidc_controller: OidcController::new(
+            Arc::clone(&services.oidc_service),
+            Arc::clone(&services.auth_service)
+        ),
+        introspection_controller: IntrospectionController::new(
+            Arc::clone(&services.introspection_service)
+        ),
     };
     let container = Container { services: services, controllers: controllers };
     return Ok(container);
 }
 
+fn get_mailer() -> Result<Arc<dyn Mailer>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mailer: Arc<dyn Mailer> = match CONFIGURATION.mail_transport {
+        MailTransport::Smtp =>
+            Arc::new(
+                SmtpMailer::new(
+                    &CONFIGURATION.smtp_host,
+                    CONFIGURATION.smtp_port,
+                    &CONFIGURATION.smtp_username,
+                    &CONFIGURATION.smtp_password,
+                    CONFIGURATION.smtp_tls,
+                    &CONFIGURATION.mail_from
+                )?
+            ),
+        MailTransport::File =>
+            Arc::new(
+                FileMailer::new(&CONFIGURATION.mail_storage_location, &CONFIGURATION.mail_from)?
+            ),
+        MailTransport::Memory => Arc::new(MemoryMailer::new()),
+    };
+    return Ok(mailer);
+}
+
 fn get_database_connection() -> ConnectionManager<PgConnection> {
     let connection = ConnectionManager::<PgConnection>::new(
         &format!(
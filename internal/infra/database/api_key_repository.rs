@@ -0,0 +1,138 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::NaiveDateTime;
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::{ FilterDsl, OrderDsl },
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    BoolExpressionMethods,
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+
+use crate::infra::domain::api_key::ApiKeyDTO;
+
+rust_commons::diesel::table! {
+    api_keys (id) {
+        id -> Int4,
+        user_id -> Integer,
+        name -> Text,
+        prefix -> Text,
+        secret_hash -> Text,
+        scopes -> Array<Text>,
+        last_used_at -> Nullable<Timestamp>,
+        expires_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = api_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub secret_hash: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = api_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct ApiKeyInsertable {
+    user_id: i32,
+    name: String,
+    prefix: String,
+    secret_hash: String,
+    scopes: Vec<String>,
+    expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl ApiKeyRepository {
+    pub fn new(pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>) -> Arc<ApiKeyRepository> {
+        return Arc::new(ApiKeyRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn save(&self, api_key: ApiKeyDTO) -> Result<ApiKey, diesel::result::Error> {
+        use self::api_keys::dsl::*;
+        let api_key_model = ApiKeyInsertable {
+            user_id: *api_key.user_id,
+            name: api_key.name.to_string(),
+            prefix: api_key.prefix.to_string(),
+            secret_hash: api_key.secret_hash.to_string(),
+            scopes: api_key.scopes.iter().map(|scope| scope.to_string()).collect(),
+            expires_at: api_key.expires_at,
+        };
+        return diesel
+            ::insert_into(api_keys)
+            .values(&api_key_model)
+            .get_result::<ApiKey>(&mut self.get_connection());
+    }
+
+    pub fn find_by_hash(&self, hash: &str) -> Result<ApiKey, diesel::result::Error> {
+        use self::api_keys::dsl::*;
+        return api_keys
+            .filter(secret_hash.eq(hash))
+            .first::<ApiKey>(&mut self.get_connection());
+    }
+
+    pub fn find_by_user_id(
+        &self,
+        api_key_user_id: Arc<i32>
+    ) -> Result<Vec<ApiKey>, diesel::result::Error> {
+        use self::api_keys::dsl::*;
+        return api_keys
+            .filter(user_id.eq(*api_key_user_id))
+            .order(created_at.desc())
+            .load::<ApiKey>(&mut self.get_connection());
+    }
+
+    /// Updates `last_used_at` at most once per `precision_start`, so every request
+    /// made with a key does not end up as a write.
+    pub fn touch(
+        &self,
+        api_key_id: i32,
+        at: NaiveDateTime,
+        precision_start: NaiveDateTime
+    ) -> Result<usize, diesel::result::Error> {
+        use self::api_keys::dsl::*;
+        return diesel
+            ::update(
+                api_keys
+                    .filter(id.eq(api_key_id))
+                    .filter(last_used_at.is_null().or(last_used_at.lt(precision_start)))
+            )
+            .set(last_used_at.eq(Some(at)))
+            .execute(&mut self.get_connection());
+    }
+
+    /// Returns `false` when the user has no key with provided id.
+    pub fn delete(
+        &self,
+        api_key_user_id: Arc<i32>,
+        api_key_id: i32
+    ) -> Result<bool, diesel::result::Error> {
+        use self::api_keys::dsl::*;
+        let deleted = diesel
+            ::delete(api_keys.filter(user_id.eq(*api_key_user_id)).filter(id.eq(api_key_id)))
+            .execute(&mut self.get_connection())?;
+        return Ok(deleted > 0);
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::NaiveDateTime;
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::FilterDsl,
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+
+use crate::infra::domain::email_verification::EmailVerificationDTO;
+
+rust_commons::diesel::table! {
+    email_verifications (id) {
+        id -> Int4,
+        user_id -> Integer,
+        email -> Text,
+        token_hash -> Text,
+        used_at -> Nullable<Timestamp>,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = email_verifications)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EmailVerification {
+    pub id: i32,
+    pub user_id: i32,
+    pub email: String,
+    pub token_hash: String,
+    pub used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = email_verifications)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct EmailVerificationInsertable {
+    user_id: i32,
+    email: String,
+    token_hash: String,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct EmailVerificationRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl EmailVerificationRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<EmailVerificationRepository> {
+        return Arc::new(EmailVerificationRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn save(
+        &self,
+        verification: EmailVerificationDTO
+    ) -> Result<EmailVerification, diesel::result::Error> {
+        use self::email_verifications::dsl::*;
+        let verification_model = EmailVerificationInsertable {
+            user_id: *verification.user_id,
+            email: verification.email.to_string(),
+            token_hash: verification.token_hash.to_string(),
+            expires_at: verification.expires_at,
+        };
+        let result = diesel
+            ::insert_into(email_verifications)
+            .values(&verification_model)
+            .get_result::<EmailVerification>(&mut self.get_connection())?;
+        return Ok(result);
+    }
+
+    pub fn find_by_hash(&self, hash: &str) -> Result<EmailVerification, diesel::result::Error> {
+        use self::email_verifications::dsl::*;
+        return email_verifications
+            .filter(token_hash.eq(hash))
+            .first::<EmailVerification>(&mut self.get_connection());
+    }
+
+    /// Marks token as used. Returns `false` when it was already used by a concurrent request.
+    pub fn mark_used(
+        &self,
+        verification_id: i32,
+        at: NaiveDateTime
+    ) -> Result<bool, diesel::result::Error> {
+        use self::email_verifications::dsl::*;
+        let updated = diesel
+            ::update(
+                email_verifications.filter(id.eq(verification_id)).filter(used_at.is_null())
+            )
+            .set(used_at.eq(Some(at)))
+            .execute(&mut self.get_connection())?;
+        return Ok(updated == 1);
+    }
+
+    pub fn delete_by_user_id(
+        &self,
+        verification_user_id: Arc<i32>
+    ) -> Result<usize, diesel::result::Error> {
+        use self::email_verifications::dsl::*;
+        return diesel
+            ::delete(email_verifications.filter(user_id.eq(*verification_user_id)))
+            .execute(&mut self.get_connection());
+    }
+}
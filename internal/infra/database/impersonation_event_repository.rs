@@ -0,0 +1,106 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::NaiveDateTime;
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::{ FilterDsl, OrderDsl },
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+use rust_commons::uuid::Uuid;
+
+use crate::infra::domain::impersonation::ImpersonationEventDTO;
+
+rust_commons::diesel::table! {
+    impersonation_events (id) {
+        id -> Int4,
+        actor_id -> Integer,
+        user_id -> Integer,
+        session_uuid -> Uuid,
+        event -> Text,
+        reason -> Nullable<Text>,
+        client_ip -> Nullable<Text>,
+        expires_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = impersonation_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ImpersonationEvent {
+    pub id: i32,
+    pub actor_id: i32,
+    pub user_id: i32,
+    pub session_uuid: Uuid,
+    pub event: String,
+    pub reason: Option<String>,
+    pub client_ip: Option<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = impersonation_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct ImpersonationEventInsertable {
+    actor_id: i32,
+    user_id: i32,
+    session_uuid: Uuid,
+    event: String,
+    reason: Option<String>,
+    client_ip: Option<String>,
+    expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone)]
+pub struct ImpersonationEventRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl ImpersonationEventRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<ImpersonationEventRepository> {
+        return Arc::new(ImpersonationEventRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn save(
+        &self,
+        impersonation_event: ImpersonationEventDTO
+    ) -> Result<ImpersonationEvent, diesel::result::Error> {
+        use self::impersonation_events::dsl::*;
+        let event_model = ImpersonationEventInsertable {
+            actor_id: *impersonation_event.actor_id,
+            user_id: *impersonation_event.user_id,
+            session_uuid: impersonation_event.session_uuid,
+            event: impersonation_event.event.to_string(),
+            reason: impersonation_event.reason.as_deref().map(str::to_string),
+            client_ip: impersonation_event.client_ip.as_deref().map(str::to_string),
+            expires_at: impersonation_event.expires_at,
+        };
+        return diesel
+            ::insert_into(impersonation_events)
+            .values(&event_model)
+            .get_result::<ImpersonationEvent>(&mut self.get_connection());
+    }
+
+    pub fn find_by_user_id(
+        &self,
+        event_user_id: Arc<i32>
+    ) -> Result<Vec<ImpersonationEvent>, diesel::result::Error> {
+        use self::impersonation_events::dsl::*;
+        return impersonation_events
+            .filter(user_id.eq(*event_user_id))
+            .order(created_at.desc())
+            .load::<ImpersonationEvent>(&mut self.get_connection());
+    }
+}
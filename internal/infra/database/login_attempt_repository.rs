@@ -0,0 +1,173 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::{ NaiveDateTime, Utc };
+use rust_commons::diesel::{
+    self,
+    dsl::case_when,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::FilterDsl,
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    BoolExpressionMethods,
+    ExpressionMethods,
+    OptionalExtension,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+
+rust_commons::diesel::table! {
+    login_attempts (scope, identifier) {
+        scope -> Text,
+        identifier -> Text,
+        failed_attempts -> Integer,
+        last_failed_at -> Timestamp,
+        locked_until -> Nullable<Timestamp>,
+    }
+}
+
+/// What failed login attempts are counted against. Login link requests are counted the same
+/// way, every request is an attempt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoginAttemptScope {
+    Account,
+    Ip,
+    MagicLinkEmail,
+    MagicLinkIp,
+}
+
+impl LoginAttemptScope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LoginAttemptScope::Account => "account",
+            LoginAttemptScope::Ip => "ip",
+            LoginAttemptScope::MagicLinkEmail => "magic_link_email",
+            LoginAttemptScope::MagicLinkIp => "magic_link_ip",
+        }
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = login_attempts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoginAttempt {
+    pub scope: String,
+    pub identifier: String,
+    pub failed_attempts: i32,
+    pub last_failed_at: NaiveDateTime,
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = login_attempts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct LoginAttemptInsertable {
+    scope: String,
+    identifier: String,
+    failed_attempts: i32,
+    last_failed_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct LoginAttemptRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl LoginAttemptRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<LoginAttemptRepository> {
+        return Arc::new(LoginAttemptRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn find(
+        &self,
+        attempt_scope: LoginAttemptScope,
+        attempt_identifier: &str
+    ) -> Result<Option<LoginAttempt>, diesel::result::Error> {
+        use self::login_attempts::dsl::*;
+        return login_attempts
+            .filter(scope.eq(attempt_scope.as_str()))
+            .filter(identifier.eq(attempt_identifier))
+            .first::<LoginAttempt>(&mut self.get_connection())
+            .optional();
+    }
+
+    /// Counts a failed attempt. The counter starts over when the previous failure happened
+    /// before `window_start`. The increment is done by the upsert itself, so concurrent failures
+    /// are all counted, also for a row which does not exist yet.
+    pub fn register_failure(
+        &self,
+        attempt_scope: LoginAttemptScope,
+        attempt_identifier: &str,
+        window_start: NaiveDateTime
+    ) -> Result<LoginAttempt, diesel::result::Error> {
+        use self::login_attempts::dsl::*;
+        let now = Utc::now().naive_utc();
+        let attempt_model = LoginAttemptInsertable {
+            scope: attempt_scope.as_str().to_owned(),
+            identifier: attempt_identifier.to_owned(),
+            failed_attempts: 1,
+            last_failed_at: now,
+        };
+        let attempts = case_when(last_failed_at.ge(window_start), failed_attempts + 1).otherwise(1);
+        return diesel
+            ::insert_into(login_attempts)
+            .values(&attempt_model)
+            .on_conflict((scope, identifier))
+            .do_update()
+            .set((failed_attempts.eq(attempts), last_failed_at.eq(now)))
+            .get_result::<LoginAttempt>(&mut self.get_connection());
+    }
+
+    pub fn lock(
+        &self,
+        attempt_scope: LoginAttemptScope,
+        attempt_identifier: &str,
+        until: NaiveDateTime
+    ) -> Result<usize, diesel::result::Error> {
+        use self::login_attempts::dsl::*;
+        return diesel
+            ::update(
+                login_attempts
+                    .filter(scope.eq(attempt_scope.as_str()))
+                    .filter(identifier.eq(attempt_identifier))
+            )
+            .set(locked_until.eq(Some(until)))
+            .execute(&mut self.get_connection());
+    }
+
+    pub fn delete(
+        &self,
+        attempt_scope: LoginAttemptScope,
+        attempt_identifier: &str
+    ) -> Result<usize, diesel::result::Error> {
+        use self::login_attempts::dsl::*;
+        return diesel
+            ::delete(
+                login_attempts
+                    .filter(scope.eq(attempt_scope.as_str()))
+                    .filter(identifier.eq(attempt_identifier))
+            )
+            .execute(&mut self.get_connection());
+    }
+
+    /// Deletes counters which are outside of the window and not locked anymore.
+    pub fn delete_stale(
+        &self,
+        window_start: NaiveDateTime
+    ) -> Result<usize, diesel::result::Error> {
+        use self::login_attempts::dsl::*;
+        let now = Utc::now().naive_utc();
+        return diesel
+            ::delete(
+                login_attempts
+                    .filter(last_failed_at.lt(window_start))
+                    .filter(locked_until.is_null().or(locked_until.lt(now)))
+            )
+            .execute(&mut self.get_connection());
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::NaiveDateTime;
+use rust_commons::diesel::{
+    self,
+    dsl::exists,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::{ FilterDsl, LimitDsl, OrderDsl },
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+
+use crate::infra::domain::login_event::{ LoginEventDTO, LOGIN_SUCCEEDED };
+
+rust_commons::diesel::table! {
+    login_events (id) {
+        id -> Int4,
+        user_id -> Integer,
+        outcome -> Text,
+        reason -> Nullable<Text>,
+        client_ip -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        device_fingerprint -> Text,
+        new_device -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = login_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoginEvent {
+    pub id: i32,
+    pub user_id: i32,
+    pub outcome: String,
+    pub reason: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub device_fingerprint: String,
+    pub new_device: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = login_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct LoginEventInsertable {
+    user_id: i32,
+    outcome: String,
+    reason: Option<String>,
+    client_ip: Option<String>,
+    user_agent: Option<String>,
+    device_fingerprint: String,
+    new_device: bool,
+}
+
+#[derive(Clone)]
+pub struct LoginEventRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl LoginEventRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<LoginEventRepository> {
+        return Arc::new(LoginEventRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn save(&self, login_event: LoginEventDTO) -> Result<LoginEvent, diesel::result::Error> {
+        use self::login_events::dsl::*;
+        let event_model = LoginEventInsertable {
+            user_id: *login_event.user_id,
+            outcome: login_event.outcome.to_string(),
+            reason: login_event.reason.map(str::to_string),
+            client_ip: login_event.client_ip.as_deref().map(str::to_string),
+            user_agent: login_event.user_agent.as_deref().map(str::to_string),
+            device_fingerprint: login_event.device_fingerprint.to_string(),
+            new_device: login_event.new_device,
+        };
+        return diesel
+            ::insert_into(login_events)
+            .values(&event_model)
+            .get_result::<LoginEvent>(&mut self.get_connection());
+    }
+
+    /// Latest events first.
+    pub fn find_by_user_id(
+        &self,
+        event_user_id: Arc<i32>,
+        max_events: i64
+    ) -> Result<Vec<LoginEvent>, diesel::result::Error> {
+        use self::login_events::dsl::*;
+        return login_events
+            .filter(user_id.eq(*event_user_id))
+            .order(created_at.desc())
+            .limit(max_events)
+            .load::<LoginEvent>(&mut self.get_connection());
+    }
+
+    pub fn has_successful_login(
+        &self,
+        event_user_id: Arc<i32>
+    ) -> Result<bool, diesel::result::Error> {
+        use self::login_events::dsl::*;
+        return diesel
+            ::select(
+                exists(
+                    login_events
+                        .filter(user_id.eq(*event_user_id))
+                        .filter(outcome.eq(LOGIN_SUCCEEDED))
+                )
+            )
+            .get_result::<bool>(&mut self.get_connection());
+    }
+
+    pub fn has_successful_login_from(
+        &self,
+        event_user_id: Arc<i32>,
+        fingerprint: &str
+    ) -> Result<bool, diesel::result::Error> {
+        use self::login_events::dsl::*;
+        return diesel
+            ::select(
+                exists(
+                    login_events
+                        .filter(user_id.eq(*event_user_id))
+                        .filter(outcome.eq(LOGIN_SUCCEEDED))
+                        .filter(device_fingerprint.eq(fingerprint))
+                )
+            )
+            .get_result::<bool>(&mut self.get_connection());
+    }
+
+    pub fn delete_older_than(&self, before: NaiveDateTime) -> Result<usize, diesel::result::Error> {
+        use self::login_events::dsl::*;
+        return diesel
+            ::delete(login_events.filter(created_at.lt(before)))
+            .execute(&mut self.get_connection());
+    }
+}
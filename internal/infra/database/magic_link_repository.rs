@@ -0,0 +1,102 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::NaiveDateTime;
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::FilterDsl,
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+
+use crate::infra::domain::magic_link::MagicLinkDTO;
+
+rust_commons::diesel::table! {
+    magic_links (id) {
+        id -> Int4,
+        user_id -> Integer,
+        email -> Text,
+        token_hash -> Text,
+        used_at -> Nullable<Timestamp>,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = magic_links)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MagicLink {
+    pub id: i32,
+    pub user_id: i32,
+    pub email: String,
+    pub token_hash: String,
+    pub used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = magic_links)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct MagicLinkInsertable {
+    user_id: i32,
+    email: String,
+    token_hash: String,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct MagicLinkRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl MagicLinkRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<MagicLinkRepository> {
+        return Arc::new(MagicLinkRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn save(&self, link: MagicLinkDTO) -> Result<MagicLink, diesel::result::Error> {
+        use self::magic_links::dsl::*;
+        let link_model = MagicLinkInsertable {
+            user_id: *link.user_id,
+            email: link.email.to_string(),
+            token_hash: link.token_hash.to_string(),
+            expires_at: link.expires_at,
+        };
+        return diesel
+            ::insert_into(magic_links)
+            .values(&link_model)
+            .get_result::<MagicLink>(&mut self.get_connection());
+    }
+
+    pub fn find_by_hash(&self, hash: &str) -> Result<MagicLink, diesel::result::Error> {
+        use self::magic_links::dsl::*;
+        return magic_links
+            .filter(token_hash.eq(hash))
+            .first::<MagicLink>(&mut self.get_connection());
+    }
+
+    /// Marks link as used. Returns `false` when it was already used by a concurrent request.
+    pub fn mark_used(
+        &self,
+        link_id: i32,
+        at: NaiveDateTime
+    ) -> Result<bool, diesel::result::Error> {
+        use self::magic_links::dsl::*;
+        let updated = diesel
+            ::update(magic_links.filter(id.eq(link_id)).filter(used_at.is_null()))
+            .set(used_at.eq(Some(at)))
+            .execute(&mut self.get_connection())?;
+        return Ok(updated == 1);
+    }
+}
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS refresh_tokens
+(
+    id           SERIAL PRIMARY KEY,
+    user_id      INTEGER   NOT NULL,
+    session_uuid UUID      NOT NULL,
+    token_hash   TEXT      NOT NULL,
+    used_at      TIMESTAMP NULL,
+    expires_at   TIMESTAMP NOT NULL,
+    created_at   TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT refresh_tokens_token_hash_key UNIQUE (token_hash),
+    CONSTRAINT refresh_tokens_session_fkey FOREIGN KEY (user_id, session_uuid)
+        REFERENCES sessions (user_id, uuid) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+ALTER TABLE sessions
+    ADD COLUMN IF NOT EXISTS created_at   TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN IF NOT EXISTS user_agent   TEXT NULL,
+    ADD COLUMN IF NOT EXISTS client_ip    TEXT NULL,
+    ADD COLUMN IF NOT EXISTS expires_at   TIMESTAMP DEFAULT (CURRENT_TIMESTAMP + INTERVAL '30 days') NOT NULL;
+
+-- Default is only used to backfill existing sessions.
+ALTER TABLE sessions ALTER COLUMN expires_at DROP DEFAULT;
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS password_resets
+(
+    id         SERIAL PRIMARY KEY,
+    user_id    INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    token_hash TEXT      NOT NULL,
+    used_at    TIMESTAMP NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT password_resets_token_hash_key UNIQUE (token_hash)
+);
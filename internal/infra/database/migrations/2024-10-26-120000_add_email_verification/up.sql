@@ -0,0 +1,16 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified_at TIMESTAMP NULL;
+
+-- Accounts created before verification was introduced are trusted.
+UPDATE users SET email_verified_at = created_date WHERE email_verified_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS email_verifications
+(
+    id         SERIAL PRIMARY KEY,
+    user_id    INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    email      TEXT      NOT NULL,
+    token_hash TEXT      NOT NULL,
+    used_at    TIMESTAMP NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT email_verifications_token_hash_key UNIQUE (token_hash)
+);
@@ -0,0 +1,13 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMP NULL;
+
+-- Failed login counters, kept per account (identifier is user id) and per client IP.
+-- Account lockout is stored in users.locked_until, so only IP rows use locked_until here.
+CREATE TABLE IF NOT EXISTS login_attempts
+(
+    scope           TEXT      NOT NULL,
+    identifier      TEXT      NOT NULL,
+    failed_attempts INTEGER   NOT NULL DEFAULT 0,
+    last_failed_at  TIMESTAMP NOT NULL,
+    locked_until    TIMESTAMP NULL,
+    PRIMARY KEY (scope, identifier)
+);
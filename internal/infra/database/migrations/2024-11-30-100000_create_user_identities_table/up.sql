@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS user_identities
+(
+    id            SERIAL PRIMARY KEY,
+    user_id       INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    provider      TEXT      NOT NULL,
+    subject       TEXT      NOT NULL,
+    email         TEXT      NULL,
+    last_login_at TIMESTAMP NULL,
+    created_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT user_identities_provider_subject_key UNIQUE (provider, subject)
+);
+
+CREATE INDEX IF NOT EXISTS user_identities_user_id_idx ON user_identities (user_id);
+
+-- Pending authorization requests, consumed by the callback.
+CREATE TABLE IF NOT EXISTS oidc_login_states
+(
+    state_hash    TEXT      PRIMARY KEY,
+    provider      TEXT      NOT NULL,
+    code_verifier TEXT      NOT NULL,
+    nonce         TEXT      NOT NULL,
+    expires_at    TIMESTAMP NOT NULL,
+    created_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
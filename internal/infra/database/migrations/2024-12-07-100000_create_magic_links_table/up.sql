@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS magic_links
+(
+    id         SERIAL PRIMARY KEY,
+    user_id    INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    email      TEXT      NOT NULL,
+    token_hash TEXT      NOT NULL,
+    used_at    TIMESTAMP NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT magic_links_token_hash_key UNIQUE (token_hash)
+);
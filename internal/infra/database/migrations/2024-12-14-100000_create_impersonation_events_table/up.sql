@@ -0,0 +1,25 @@
+-- Users are not referenced with foreign keys, so the trail outlives deleted accounts.
+CREATE TABLE IF NOT EXISTS impersonation_events
+(
+    id           SERIAL PRIMARY KEY,
+    actor_id     INTEGER   NOT NULL,
+    user_id      INTEGER   NOT NULL,
+    session_uuid UUID      NOT NULL,
+    event        TEXT      NOT NULL,
+    reason       TEXT      NULL,
+    client_ip    TEXT      NULL,
+    expires_at   TIMESTAMP NULL,
+    created_at   TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS impersonation_events_user_id_idx ON impersonation_events (user_id);
+
+INSERT INTO permissions (name) VALUES ('users.impersonate') ON CONFLICT DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id
+FROM roles,
+     permissions
+WHERE roles.name = 'admin'
+  AND permissions.name = 'users.impersonate'
+ON CONFLICT DO NOTHING;
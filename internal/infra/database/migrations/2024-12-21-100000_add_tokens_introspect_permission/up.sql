@@ -0,0 +1,9 @@
+INSERT INTO permissions (name) VALUES ('tokens.introspect') ON CONFLICT DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id
+FROM roles,
+     permissions
+WHERE roles.name = 'admin'
+  AND permissions.name = 'tokens.introspect'
+ON CONFLICT DO NOTHING;
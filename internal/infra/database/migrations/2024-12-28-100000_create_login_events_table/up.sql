@@ -0,0 +1,16 @@
+-- Logins of existing accounts, failed attempts for unknown emails are only counted per IP.
+CREATE TABLE IF NOT EXISTS login_events
+(
+    id                 SERIAL PRIMARY KEY,
+    user_id            INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    outcome            TEXT      NOT NULL,
+    reason             TEXT      NULL,
+    client_ip          TEXT      NULL,
+    user_agent         TEXT      NULL,
+    device_fingerprint TEXT      NOT NULL,
+    new_device         BOOLEAN   NOT NULL DEFAULT FALSE,
+    created_at         TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS login_events_user_id_idx ON login_events (user_id, created_at);
+CREATE INDEX IF NOT EXISTS login_events_fingerprint_idx ON login_events (user_id, device_fingerprint);
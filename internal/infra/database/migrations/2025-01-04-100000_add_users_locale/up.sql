@@ -0,0 +1,2 @@
+-- Language of emails sent to the user, default mail locale is used when it is not set.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locale TEXT NULL;
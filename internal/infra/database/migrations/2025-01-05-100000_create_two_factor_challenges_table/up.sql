@@ -0,0 +1,7 @@
+-- Issued two-factor challenge tokens, a row is deleted once its token is exchanged for a session.
+CREATE TABLE IF NOT EXISTS two_factor_challenges
+(
+    jti        UUID PRIMARY KEY,
+    user_id    INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL
+);
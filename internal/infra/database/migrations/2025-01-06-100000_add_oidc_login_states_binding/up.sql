@@ -0,0 +1,4 @@
+-- Hash of the value kept in the browser which started the login, pending states can not be
+-- bound, so they are dropped.
+DELETE FROM oidc_login_states;
+ALTER TABLE oidc_login_states ADD COLUMN IF NOT EXISTS binding_hash TEXT NOT NULL;
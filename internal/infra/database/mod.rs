@@ -1,2 +1,13 @@
 pub mod user_repository;
 pub mod session_repository;
+pub mod refresh_token_repository;
+pub mod password_reset_repository;
+pub mod magic_link_repository;
+pub mod email_verification_repository;
+pub mod two_factor_repository;
+pub mod login_attempt_repository;
+pub mod role_repository;
+pub mod api_key_repository;
+pub mod user_identity_repository;
+pub mod impersonation_event_repository;
+pub mod login_event_repository;
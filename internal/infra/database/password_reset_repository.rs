@@ -0,0 +1,109 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::NaiveDateTime;
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::FilterDsl,
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+
+use crate::infra::domain::password_reset::PasswordResetDTO;
+
+rust_commons::diesel::table! {
+    password_resets (id) {
+        id -> Int4,
+        user_id -> Integer,
+        token_hash -> Text,
+        used_at -> Nullable<Timestamp>,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = password_resets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PasswordReset {
+    pub id: i32,
+    pub user_id: i32,
+    pub token_hash: String,
+    pub used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = password_resets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct PasswordResetInsertable {
+    user_id: i32,
+    token_hash: String,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct PasswordResetRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl PasswordResetRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<PasswordResetRepository> {
+        return Arc::new(PasswordResetRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn save(&self, reset: PasswordResetDTO) -> Result<PasswordReset, diesel::result::Error> {
+        use self::password_resets::dsl::*;
+        let reset_model = PasswordResetInsertable {
+            user_id: *reset.user_id,
+            token_hash: reset.token_hash.to_string(),
+            expires_at: reset.expires_at,
+        };
+        let result = diesel
+            ::insert_into(password_resets)
+            .values(&reset_model)
+            .get_result::<PasswordReset>(&mut self.get_connection())?;
+        return Ok(result);
+    }
+
+    pub fn find_by_hash(&self, hash: &str) -> Result<PasswordReset, diesel::result::Error> {
+        use self::password_resets::dsl::*;
+        return password_resets
+            .filter(token_hash.eq(hash))
+            .first::<PasswordReset>(&mut self.get_connection());
+    }
+
+    /// Marks token as used. Returns `false` when it was already used by a concurrent request.
+    pub fn mark_used(
+        &self,
+        reset_id: i32,
+        at: NaiveDateTime
+    ) -> Result<bool, diesel::result::Error> {
+        use self::password_resets::dsl::*;
+        let updated = diesel
+            ::update(password_resets.filter(id.eq(reset_id)).filter(used_at.is_null()))
+            .set(used_at.eq(Some(at)))
+            .execute(&mut self.get_connection())?;
+        return Ok(updated == 1);
+    }
+
+    pub fn delete_by_user_id(
+        &self,
+        reset_user_id: Arc<i32>
+    ) -> Result<usize, diesel::result::Error> {
+        use self::password_resets::dsl::*;
+        return diesel
+            ::delete(password_resets.filter(user_id.eq(*reset_user_id)))
+            .execute(&mut self.get_connection());
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::NaiveDateTime;
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::FilterDsl,
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+use rust_commons::uuid::Uuid;
+
+use crate::infra::domain::refresh_token::RefreshTokenDTO;
+
+rust_commons::diesel::table! {
+    refresh_tokens (id) {
+        id -> Int4,
+        user_id -> Integer,
+        session_uuid -> Uuid,
+        token_hash -> Text,
+        used_at -> Nullable<Timestamp>,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = refresh_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RefreshToken {
+    pub id: i32,
+    pub user_id: i32,
+    pub session_uuid: Uuid,
+    pub token_hash: String,
+    pub used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = refresh_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct RefreshTokenInsertable {
+    user_id: i32,
+    session_uuid: Uuid,
+    token_hash: String,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct RefreshTokenRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl RefreshTokenRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<RefreshTokenRepository> {
+        return Arc::new(RefreshTokenRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn save(&self, token: RefreshTokenDTO) -> Result<RefreshToken, diesel::result::Error> {
+        use self::refresh_tokens::dsl::*;
+        let token_model = RefreshTokenInsertable {
+            user_id: *token.user_id,
+            session_uuid: token.session_uuid,
+            token_hash: token.token_hash.to_string(),
+            expires_at: token.expires_at,
+        };
+        let result = diesel
+            ::insert_into(refresh_tokens)
+            .values(&token_model)
+            .get_result::<RefreshToken>(&mut self.get_connection())?;
+        return Ok(result);
+    }
+
+    pub fn find_by_hash(&self, hash: &str) -> Result<RefreshToken, diesel::result::Error> {
+        use self::refresh_tokens::dsl::*;
+        return refresh_tokens
+            .filter(token_hash.eq(hash))
+            .first::<RefreshToken>(&mut self.get_connection());
+    }
+
+    /// Marks token as used. Returns `false` when it was already used by a concurrent request.
+    pub fn mark_used(
+        &self,
+        token_id: i32,
+        at: NaiveDateTime
+    ) -> Result<bool, diesel::result::Error> {
+        use self::refresh_tokens::dsl::*;
+        let updated = diesel
+            ::update(refresh_tokens.filter(id.eq(token_id)).filter(used_at.is_null()))
+            .set(used_at.eq(Some(at)))
+            .execute(&mut self.get_connection())?;
+        return Ok(updated == 1);
+    }
+}
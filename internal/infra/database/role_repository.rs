@@ -0,0 +1,120 @@
+use std::sync::{ Arc, RwLock };
+
+use rust_commons::diesel::{
+    self,
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    JoinOnDsl,
+    PgConnection,
+    QueryDsl,
+    RunQueryDsl,
+};
+
+rust_commons::diesel::table! {
+    roles (id) {
+        id -> Int4,
+        name -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+rust_commons::diesel::table! {
+    permissions (id) {
+        id -> Int4,
+        name -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+rust_commons::diesel::table! {
+    role_permissions (role_id, permission_id) {
+        role_id -> Integer,
+        permission_id -> Integer,
+    }
+}
+
+rust_commons::diesel::table! {
+    user_roles (user_id, role_id) {
+        user_id -> Integer,
+        role_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+rust_commons::diesel::allow_tables_to_appear_in_same_query!(
+    roles,
+    permissions,
+    role_permissions,
+    user_roles
+);
+
+#[derive(Clone)]
+pub struct RoleRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl RoleRepository {
+    pub fn new(pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>) -> Arc<RoleRepository> {
+        return Arc::new(RoleRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn find_role_names_by_user_id(
+        &self,
+        user_id: Arc<i32>
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        return roles::table
+            .inner_join(user_roles::table.on(user_roles::role_id.eq(roles::id)))
+            .filter(user_roles::user_id.eq(*user_id))
+            .select(roles::name)
+            .order(roles::name.asc())
+            .load::<String>(&mut self.get_connection());
+    }
+
+    pub fn find_permission_names_by_user_id(
+        &self,
+        user_id: Arc<i32>
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        return permissions::table
+            .inner_join(
+                role_permissions::table.on(role_permissions::permission_id.eq(permissions::id))
+            )
+            .inner_join(user_roles::table.on(user_roles::role_id.eq(role_permissions::role_id)))
+            .filter(user_roles::user_id.eq(*user_id))
+            .select(permissions::name)
+            .distinct()
+            .load::<String>(&mut self.get_connection());
+    }
+
+    pub fn find_role_id(&self, role_name: &str) -> Result<i32, diesel::result::Error> {
+        return roles::table
+            .filter(roles::name.eq(role_name))
+            .select(roles::id)
+            .first::<i32>(&mut self.get_connection());
+    }
+
+    /// Returns `false` when the user already had the role.
+    pub fn assign(&self, user_id: Arc<i32>, role_id: i32) -> Result<bool, diesel::result::Error> {
+        let inserted = diesel
+            ::insert_into(user_roles::table)
+            .values((user_roles::user_id.eq(*user_id), user_roles::role_id.eq(role_id)))
+            .on_conflict_do_nothing()
+            .execute(&mut self.get_connection())?;
+        return Ok(inserted > 0);
+    }
+
+    /// Returns `false` when the user did not have the role.
+    pub fn unassign(&self, user_id: Arc<i32>, role_id: i32) -> Result<bool, diesel::result::Error> {
+        let deleted = diesel
+            ::delete(
+                user_roles::table
+                    .filter(user_roles::user_id.eq(*user_id))
+                    .filter(user_roles::role_id.eq(role_id))
+            )
+            .execute(&mut self.get_connection())?;
+        return Ok(deleted > 0);
+    }
+}
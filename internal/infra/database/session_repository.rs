@@ -1,9 +1,10 @@
 use std::sync::{ Arc, RwLock };
 
+use chrono::{ NaiveDateTime, Utc };
 use rust_commons::diesel::{
     self,
     prelude::{ Insertable, Queryable },
-    query_dsl::methods::FilterDsl,
+    query_dsl::methods::{ FilterDsl, OrderDsl },
     r2d2::{ ConnectionManager, Pool, PooledConnection },
     ExpressionMethods,
     PgConnection,
@@ -12,21 +13,44 @@ use rust_commons::diesel::{
 };
 use rust_commons::uuid::Uuid;
 
-use crate::infra::domain::session::SessionDTO;
+use crate::infra::domain::session::{ ClientInfoDTO, SessionDTO };
 
 rust_commons::diesel::table! {
     sessions (user_id, uuid) {
         user_id -> Integer,
         uuid -> Uuid,
+        created_at -> Timestamp,
+        last_seen_at -> Timestamp,
+        user_agent -> Nullable<Text>,
+        client_ip -> Nullable<Text>,
+        expires_at -> Timestamp,
     }
 }
 
-#[derive(Selectable, Insertable, Queryable, Debug)]
+#[derive(Selectable, Queryable, Debug)]
 #[diesel(table_name = sessions)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Session {
     pub user_id: i32,
     pub uuid: Uuid,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub client_ip: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = sessions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct SessionInsertable {
+    user_id: i32,
+    uuid: Uuid,
+    created_at: NaiveDateTime,
+    last_seen_at: NaiveDateTime,
+    user_agent: Option<String>,
+    client_ip: Option<String>,
+    expires_at: NaiveDateTime,
 }
 
 #[derive(Clone)]
@@ -43,9 +67,23 @@ impl SessionRepository {
         self.pool.write().unwrap().get().expect("Failed to get a connection")
     }
 
-    pub fn save(&self, session: SessionDTO) -> Result<Session, diesel::result::Error> {
+    pub fn save(
+        &self,
+        session: SessionDTO,
+        client_info: &ClientInfoDTO,
+        session_expires_at: NaiveDateTime
+    ) -> Result<Session, diesel::result::Error> {
         use self::sessions::dsl::*;
-        let session_model = Session { user_id: *session.user_id, uuid: session.uuid };
+        let now = Utc::now().naive_utc();
+        let session_model = SessionInsertable {
+            user_id: *session.user_id,
+            uuid: session.uuid,
+            created_at: now,
+            last_seen_at: now,
+            user_agent: client_info.user_agent.as_deref().map(str::to_string),
+            client_ip: client_info.client_ip.as_deref().map(str::to_string),
+            expires_at: session_expires_at,
+        };
         let result = diesel
             ::insert_into(sessions)
             .values(&session_model)
@@ -53,12 +91,46 @@ impl SessionRepository {
         return Ok(result);
     }
 
+    pub fn find(&self, session: &SessionDTO) -> Result<Session, diesel::result::Error> {
+        use self::sessions::dsl::*;
+        return sessions
+            .filter(user_id.eq(*session.user_id))
+            .filter(uuid.eq(&session.uuid))
+            .first::<Session>(&mut self.get_connection());
+    }
+
+    pub fn find_by_user_id(&self, id: Arc<i32>) -> Result<Vec<Session>, diesel::result::Error> {
+        use self::sessions::dsl::*;
+        return sessions
+            .filter(user_id.eq(*id))
+            .order(last_seen_at.desc())
+            .load::<Session>(&mut self.get_connection());
+    }
+
+    /// Updates last activity of the session and prolongs its expiration.
+    pub fn touch(
+        &self,
+        session: &SessionDTO,
+        session_expires_at: NaiveDateTime
+    ) -> Result<Session, diesel::result::Error> {
+        use self::sessions::dsl::*;
+        return diesel
+            ::update(sessions.filter(user_id.eq(*session.user_id)).filter(uuid.eq(&session.uuid)))
+            .set((last_seen_at.eq(Utc::now().naive_utc()), expires_at.eq(session_expires_at)))
+            .get_result::<Session>(&mut self.get_connection());
+    }
+
     pub fn exists(&self, session: SessionDTO) -> Result<bool, diesel::result::Error> {
         use self::sessions::dsl::*;
         use diesel::dsl::exists;
         let exists = diesel
             ::select(
-                exists(sessions.filter(user_id.eq(*session.user_id)).filter(uuid.eq(&session.uuid)))
+                exists(
+                    sessions
+                        .filter(user_id.eq(*session.user_id))
+                        .filter(uuid.eq(&session.uuid))
+                        .filter(expires_at.gt(Utc::now().naive_utc()))
+                )
             )
             .get_result::<bool>(&mut self.get_connection())?;
         return Ok(exists);
@@ -72,11 +144,30 @@ impl SessionRepository {
         return result;
     }
 
-    pub fn delete_by_user_id(&self, id: Arc<i32>) -> Result<usize, diesel::result::Error> {
+    pub fn delete_expired(&self) -> Result<usize, diesel::result::Error> {
         use self::sessions::dsl::*;
-        let result = diesel
-            ::delete(sessions.filter(user_id.eq(*id)))
+        return diesel
+            ::delete(sessions.filter(expires_at.le(Utc::now().naive_utc())))
             .execute(&mut self.get_connection());
+    }
+
+    /// Deletes all sessions of the user, optionally keeping the one with `except_uuid`.
+    pub fn delete_by_user_id(
+        &self,
+        id: Arc<i32>,
+        except_uuid: Option<Uuid>
+    ) -> Result<usize, diesel::result::Error> {
+        use self::sessions::dsl::*;
+        let result = match except_uuid {
+            Some(except_uuid) =>
+                diesel
+                    ::delete(sessions.filter(user_id.eq(*id)).filter(uuid.ne(except_uuid)))
+                    .execute(&mut self.get_connection()),
+            None =>
+                diesel
+                    ::delete(sessions.filter(user_id.eq(*id)))
+                    .execute(&mut self.get_connection()),
+        };
         return result;
     }
 }
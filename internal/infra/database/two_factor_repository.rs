@@ -0,0 +1,279 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::{ NaiveDateTime, Utc };
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::{ FilterDsl, SelectDsl },
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    BoolExpressionMethods,
+    Connection,
+    ExpressionMethods,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+use rust_commons::uuid::Uuid;
+
+rust_commons::diesel::table! {
+    two_factor_secrets (user_id) {
+        user_id -> Integer,
+        secret -> Text,
+        enabled_at -> Nullable<Timestamp>,
+        last_used_step -> Nullable<BigInt>,
+        created_at -> Timestamp,
+    }
+}
+
+rust_commons::diesel::table! {
+    two_factor_recovery_codes (id) {
+        id -> Int4,
+        user_id -> Integer,
+        code_hash -> Text,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+rust_commons::diesel::table! {
+    two_factor_challenges (jti) {
+        jti -> Uuid,
+        user_id -> Integer,
+        expires_at -> Timestamp,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = two_factor_secrets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TwoFactorSecret {
+    pub user_id: i32,
+    pub secret: String,
+    pub enabled_at: Option<NaiveDateTime>,
+    pub last_used_step: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = two_factor_secrets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct TwoFactorSecretInsertable {
+    user_id: i32,
+    secret: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = two_factor_recovery_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct RecoveryCodeInsertable {
+    user_id: i32,
+    code_hash: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = two_factor_challenges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct ChallengeInsertable {
+    jti: Uuid,
+    user_id: i32,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct TwoFactorRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl TwoFactorRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<TwoFactorRepository> {
+        return Arc::new(TwoFactorRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn find_by_user_id(
+        &self,
+        secret_user_id: Arc<i32>
+    ) -> Result<TwoFactorSecret, diesel::result::Error> {
+        use self::two_factor_secrets::dsl::*;
+        return two_factor_secrets
+            .filter(user_id.eq(*secret_user_id))
+            .first::<TwoFactorSecret>(&mut self.get_connection());
+    }
+
+    /// Stores a new not yet confirmed secret, replacing the previous one.
+    pub fn save_pending(
+        &self,
+        secret_user_id: Arc<i32>,
+        new_secret: &str
+    ) -> Result<TwoFactorSecret, diesel::result::Error> {
+        use self::two_factor_secrets::dsl::*;
+        let secret_model = TwoFactorSecretInsertable {
+            user_id: *secret_user_id,
+            secret: new_secret.to_owned(),
+        };
+        return diesel
+            ::insert_into(two_factor_secrets)
+            .values(&secret_model)
+            .on_conflict(user_id)
+            .do_update()
+            .set((
+                secret.eq(new_secret),
+                enabled_at.eq(None::<NaiveDateTime>),
+                last_used_step.eq(None::<i64>),
+                created_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<TwoFactorSecret>(&mut self.get_connection());
+    }
+
+    /// Enables two-factor authentication and replaces recovery codes in one transaction.
+    pub fn enable(
+        &self,
+        secret_user_id: Arc<i32>,
+        recovery_code_hashes: Vec<String>
+    ) -> Result<(), diesel::result::Error> {
+        use self::two_factor_secrets::dsl::*;
+        use self::two_factor_recovery_codes::dsl as codes;
+        let recovery_codes: Vec<RecoveryCodeInsertable> = recovery_code_hashes
+            .into_iter()
+            .map(|hash| RecoveryCodeInsertable { user_id: *secret_user_id, code_hash: hash })
+            .collect();
+        return self.get_connection().transaction(|connection| {
+            diesel
+                ::update(two_factor_secrets.filter(user_id.eq(*secret_user_id)))
+                .set(enabled_at.eq(Some(Utc::now().naive_utc())))
+                .execute(connection)?;
+            diesel
+                ::delete(
+                    codes::two_factor_recovery_codes.filter(codes::user_id.eq(*secret_user_id))
+                )
+                .execute(connection)?;
+            diesel
+                ::insert_into(codes::two_factor_recovery_codes)
+                .values(&recovery_codes)
+                .execute(connection)?;
+            return Ok(());
+        });
+    }
+
+    /// Remembers the last accepted time step, so a code can not be used twice.
+    /// Returns `false` when the step (or a later one) was already used.
+    pub fn use_step(
+        &self,
+        secret_user_id: Arc<i32>,
+        step: i64
+    ) -> Result<bool, diesel::result::Error> {
+        use self::two_factor_secrets::dsl::*;
+        let updated = diesel
+            ::update(
+                two_factor_secrets
+                    .filter(user_id.eq(*secret_user_id))
+                    .filter(last_used_step.is_null().or(last_used_step.lt(step)))
+            )
+            .set(last_used_step.eq(Some(step)))
+            .execute(&mut self.get_connection())?;
+        return Ok(updated == 1);
+    }
+
+    /// Returns `false` when there is no unused recovery code with provided hash.
+    pub fn use_recovery_code(
+        &self,
+        code_user_id: Arc<i32>,
+        hash: &str
+    ) -> Result<bool, diesel::result::Error> {
+        use self::two_factor_recovery_codes::dsl::*;
+        let updated = diesel
+            ::update(
+                two_factor_recovery_codes
+                    .filter(user_id.eq(*code_user_id))
+                    .filter(code_hash.eq(hash))
+                    .filter(used_at.is_null())
+            )
+            .set(used_at.eq(Some(Utc::now().naive_utc())))
+            .execute(&mut self.get_connection())?;
+        return Ok(updated > 0);
+    }
+
+    pub fn save_challenge(
+        &self,
+        challenge_jti: Uuid,
+        challenge_user_id: Arc<i32>,
+        challenge_expires_at: NaiveDateTime
+    ) -> Result<(), diesel::result::Error> {
+        use self::two_factor_challenges::dsl::*;
+        let challenge_model = ChallengeInsertable {
+            jti: challenge_jti,
+            user_id: *challenge_user_id,
+            expires_at: challenge_expires_at,
+        };
+        diesel
+            ::insert_into(two_factor_challenges)
+            .values(&challenge_model)
+            .execute(&mut self.get_connection())?;
+        return Ok(());
+    }
+
+    pub fn is_challenge_active(
+        &self,
+        challenge_jti: Uuid,
+        challenge_user_id: Arc<i32>
+    ) -> Result<bool, diesel::result::Error> {
+        use self::two_factor_challenges::dsl::*;
+        let found = two_factor_challenges
+            .filter(jti.eq(challenge_jti))
+            .filter(user_id.eq(*challenge_user_id))
+            .filter(expires_at.gt(Utc::now().naive_utc()))
+            .select(jti)
+            .first::<Uuid>(&mut self.get_connection());
+        match found {
+            Ok(_) => Ok(true),
+            Err(diesel::result::Error::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deletes the challenge, returns `false` when it was already used or has expired.
+    pub fn consume_challenge(
+        &self,
+        challenge_jti: Uuid,
+        challenge_user_id: Arc<i32>
+    ) -> Result<bool, diesel::result::Error> {
+        use self::two_factor_challenges::dsl::*;
+        let deleted = diesel
+            ::delete(
+                two_factor_challenges
+                    .filter(jti.eq(challenge_jti))
+                    .filter(user_id.eq(*challenge_user_id))
+                    .filter(expires_at.gt(Utc::now().naive_utc()))
+            )
+            .execute(&mut self.get_connection())?;
+        return Ok(deleted == 1);
+    }
+
+    pub fn delete_expired_challenges(&self) -> Result<usize, diesel::result::Error> {
+        use self::two_factor_challenges::dsl::*;
+        return diesel
+            ::delete(two_factor_challenges.filter(expires_at.le(Utc::now().naive_utc())))
+            .execute(&mut self.get_connection());
+    }
+
+    pub fn delete(&self, secret_user_id: Arc<i32>) -> Result<(), diesel::result::Error> {
+        use self::two_factor_secrets::dsl::*;
+        use self::two_factor_recovery_codes::dsl as codes;
+        return self.get_connection().transaction(|connection| {
+            diesel
+                ::delete(
+                    codes::two_factor_recovery_codes.filter(codes::user_id.eq(*secret_user_id))
+                )
+                .execute(connection)?;
+            diesel
+                ::delete(two_factor_secrets.filter(user_id.eq(*secret_user_id)))
+                .execute(connection)?;
+            return Ok(());
+        });
+    }
+}
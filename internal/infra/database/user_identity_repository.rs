@@ -0,0 +1,191 @@
+use std::sync::{ Arc, RwLock };
+
+use chrono::{ NaiveDateTime, Utc };
+use rust_commons::diesel::{
+    self,
+    prelude::{ Insertable, Queryable },
+    query_dsl::methods::FilterDsl,
+    r2d2::{ ConnectionManager, Pool, PooledConnection },
+    ExpressionMethods,
+    OptionalExtension,
+    PgConnection,
+    RunQueryDsl,
+    Selectable,
+};
+
+use crate::infra::domain::user_identity::{ OidcLoginStateDTO, UserIdentityDTO };
+
+rust_commons::diesel::table! {
+    user_identities (id) {
+        id -> Int4,
+        user_id -> Integer,
+        provider -> Text,
+        subject -> Text,
+        email -> Nullable<Text>,
+        last_login_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+rust_commons::diesel::table! {
+    oidc_login_states (state_hash) {
+        state_hash -> Text,
+        provider -> Text,
+        code_verifier -> Text,
+        nonce -> Text,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+        binding_hash -> Text,
+    }
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = user_identities)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserIdentity {
+    pub id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = user_identities)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct UserIdentityInsertable {
+    user_id: i32,
+    provider: String,
+    subject: String,
+    email: Option<String>,
+}
+
+#[derive(Selectable, Queryable, Debug)]
+#[diesel(table_name = oidc_login_states)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OidcLoginState {
+    pub state_hash: String,
+    pub provider: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub binding_hash: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = oidc_login_states)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct OidcLoginStateInsertable {
+    state_hash: String,
+    provider: String,
+    code_verifier: String,
+    nonce: String,
+    expires_at: NaiveDateTime,
+    binding_hash: String,
+}
+
+/// Stores identities of users at external OpenID Connect providers
+/// and pending authorization requests.
+#[derive(Clone)]
+pub struct UserIdentityRepository {
+    pub pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>,
+}
+
+impl UserIdentityRepository {
+    pub fn new(
+        pool: Arc<RwLock<Pool<ConnectionManager<PgConnection>>>>
+    ) -> Arc<UserIdentityRepository> {
+        return Arc::new(UserIdentityRepository { pool });
+    }
+
+    fn get_connection(&self) -> PooledConnection<ConnectionManager<PgConnection>> {
+        self.pool.write().unwrap().get().expect("Failed to get a connection")
+    }
+
+    pub fn find(
+        &self,
+        identity_provider: &str,
+        identity_subject: &str
+    ) -> Result<Option<UserIdentity>, diesel::result::Error> {
+        use self::user_identities::dsl::*;
+        return user_identities
+            .filter(provider.eq(identity_provider))
+            .filter(subject.eq(identity_subject))
+            .first::<UserIdentity>(&mut self.get_connection())
+            .optional();
+    }
+
+    pub fn save(&self, identity: UserIdentityDTO) -> Result<UserIdentity, diesel::result::Error> {
+        use self::user_identities::dsl::*;
+        let identity_model = UserIdentityInsertable {
+            user_id: *identity.user_id,
+            provider: identity.provider.to_string(),
+            subject: identity.subject.to_string(),
+            email: identity.email.as_deref().map(str::to_string),
+        };
+        return diesel
+            ::insert_into(user_identities)
+            .values(&identity_model)
+            .get_result::<UserIdentity>(&mut self.get_connection());
+    }
+
+    pub fn touch(
+        &self,
+        identity_id: i32,
+        identity_email: Option<&str>
+    ) -> Result<usize, diesel::result::Error> {
+        use self::user_identities::dsl::*;
+        return diesel
+            ::update(user_identities.filter(id.eq(identity_id)))
+            .set((last_login_at.eq(Some(Utc::now().naive_utc())), email.eq(identity_email)))
+            .execute(&mut self.get_connection());
+    }
+
+    pub fn delete(&self, identity_id: i32) -> Result<usize, diesel::result::Error> {
+        use self::user_identities::dsl::*;
+        return diesel
+            ::delete(user_identities.filter(id.eq(identity_id)))
+            .execute(&mut self.get_connection());
+    }
+
+    pub fn save_state(
+        &self,
+        state: OidcLoginStateDTO
+    ) -> Result<OidcLoginState, diesel::result::Error> {
+        use self::oidc_login_states::dsl::*;
+        let state_model = OidcLoginStateInsertable {
+            state_hash: state.state_hash.to_string(),
+            provider: state.provider.to_string(),
+            code_verifier: state.code_verifier.to_string(),
+            nonce: state.nonce.to_string(),
+            expires_at: state.expires_at,
+            binding_hash: state.binding_hash.to_string(),
+        };
+        return diesel
+            ::insert_into(oidc_login_states)
+            .values(&state_model)
+            .get_result::<OidcLoginState>(&mut self.get_connection());
+    }
+
+    /// Deletes and returns the state, so every state can be used only once.
+    pub fn take_state(
+        &self,
+        hash: &str
+    ) -> Result<Option<OidcLoginState>, diesel::result::Error> {
+        use self::oidc_login_states::dsl::*;
+        return diesel
+            ::delete(oidc_login_states.filter(state_hash.eq(hash)))
+            .get_result::<OidcLoginState>(&mut self.get_connection())
+            .optional();
+    }
+
+    pub fn delete_expired_states(&self) -> Result<usize, diesel::result::Error> {
+        use self::oidc_login_states::dsl::*;
+        return diesel
+            ::delete(oidc_login_states.filter(expires_at.lt(Utc::now().naive_utc())))
+            .execute(&mut self.get_connection());
+    }
+}
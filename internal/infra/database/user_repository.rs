@@ -2,17 +2,27 @@ use std::sync::{ Arc, RwLock };
 
 use chrono::{ NaiveDateTime, Utc };
 use rust_commons::diesel::{
+    dsl::count_star,
+    BoolExpressionMethods,
+    pg::Pg,
     prelude::{ AsChangeset, Insertable, Queryable },
-    query_dsl::methods::FilterDsl,
     r2d2::{ ConnectionManager, Pool, PooledConnection },
     ExpressionMethods,
     PgConnection,
+    PgTextExpressionMethods,
+    QueryDsl,
     RunQueryDsl,
     Selectable,
     SelectableHelper,
 };
 
-use crate::infra::{ domain::user::UserDTO, http::requests::user_request::UserRequest };
+use crate::infra::{
+    domain::{
+        pagination::{ CursorPageRequestDTO, PageRequestDTO, SortDTO },
+        user::{ UserDTO, UserFilterDTO, UserSortField },
+    },
+    http::requests::user_request::UserRequest,
+};
 
 diesel::table! {
     users (id) {
@@ -24,6 +34,9 @@ diesel::table! {
         created_date -> Timestamp,
         updated_date -> Timestamp,
         deleted_date -> Nullable<Timestamp>,
+        email_verified_at -> Nullable<Timestamp>,
+        locked_until -> Nullable<Timestamp>,
+        locale -> Nullable<Text>,
     }
 }
 
@@ -39,6 +52,12 @@ pub struct User {
     pub created_date: NaiveDateTime,
     pub updated_date: NaiveDateTime,
     pub deleted_date: Option<NaiveDateTime>,
+    pub email_verified_at: Option<NaiveDateTime>,
+    /// Changed only by the login lockout, never by a profile update.
+    #[diesel(skip_update)]
+    pub locked_until: Option<NaiveDateTime>,
+    /// Language of emails, `None` means the default mail locale.
+    pub locale: Option<String>,
 }
 
 #[derive(Insertable, Clone, Queryable)]
@@ -103,12 +122,87 @@ impl UserRepository {
         return Ok(new_user);
     }
 
-    pub fn find_all(&self) -> Result<Vec<User>, diesel::result::Error> {
-        use self::users::dsl::{ users, deleted_date };
-        let users_list = users
-            .filter(deleted_date.is_null())
+    /// Returns requested page of not deleted users and number of users matching the filter.
+    pub fn find_page(
+        &self,
+        filter: &UserFilterDTO,
+        sort: &[SortDTO<UserSortField>],
+        page: PageRequestDTO
+    ) -> Result<(Vec<User>, i64), diesel::result::Error> {
+        let mut connection = self.get_connection();
+        let total = filtered_users(filter)
+            .select(count_star())
+            .first::<i64>(&mut connection)?;
+        let mut query = filtered_users(filter);
+        for item in sort {
+            query = match (item.field, item.descending) {
+                (UserSortField::Id, false) => query.then_order_by(users::id.asc()),
+                (UserSortField::Id, true) => query.then_order_by(users::id.desc()),
+                (UserSortField::Name, false) => query.then_order_by(users::name.asc()),
+                (UserSortField::Name, true) => query.then_order_by(users::name.desc()),
+                (UserSortField::Email, false) => query.then_order_by(users::email.asc()),
+                (UserSortField::Email, true) => query.then_order_by(users::email.desc()),
+                (UserSortField::CreatedDate, false) =>
+                    query.then_order_by(users::created_date.asc()),
+                (UserSortField::CreatedDate, true) =>
+                    query.then_order_by(users::created_date.desc()),
+                (UserSortField::UpdatedDate, false) =>
+                    query.then_order_by(users::updated_date.asc()),
+                (UserSortField::UpdatedDate, true) =>
+                    query.then_order_by(users::updated_date.desc()),
+            };
+        }
+        // Rows with equal sort values would otherwise move between pages.
+        let users_list = query
+            .then_order_by(users::id.asc())
+            .offset(page.offset())
+            .limit(i64::from(page.per_page))
+            .load::<User>(&mut connection)?;
+        return Ok((users_list, total));
+    }
+
+    /// Returns up to `per_page` not deleted users next to the cursor in `(created_date, id)`
+    /// order and whether there are more of them in the read direction. Matching users are not
+    /// counted, that would scan the whole filter on every page. Rows are returned in list order
+    /// also when reading backward.
+    pub fn find_cursor_page(
+        &self,
+        filter: &UserFilterDTO,
+        page: CursorPageRequestDTO
+    ) -> Result<(Vec<User>, bool), diesel::result::Error> {
+        // Reading forward through ascending list or backward through descending one.
+        let ascending = page.backward == page.descending;
+        let mut query = filtered_users(filter);
+        if let Some(key) = page.key {
+            if ascending {
+                query = query.filter(
+                    users::created_date
+                        .gt(key.created_date)
+                        .or(users::created_date.eq(key.created_date).and(users::id.gt(key.id)))
+                );
+            } else {
+                query = query.filter(
+                    users::created_date
+                        .lt(key.created_date)
+                        .or(users::created_date.eq(key.created_date).and(users::id.lt(key.id)))
+                );
+            }
+        }
+        query = if ascending {
+            query.order_by((users::created_date.asc(), users::id.asc()))
+        } else {
+            query.order_by((users::created_date.desc(), users::id.desc()))
+        };
+        // One extra row tells whether there is another page.
+        let mut users_list = query
+            .limit(i64::from(page.per_page) + 1)
             .load::<User>(&mut self.get_connection())?;
-        return Ok(users_list);
+        let has_more = users_list.len() > (page.per_page as usize);
+        users_list.truncate(page.per_page as usize);
+        if page.backward {
+            users_list.reverse();
+        }
+        return Ok((users_list, has_more));
     }
 
     pub fn find_by_id(&self, user_id: Arc<i32>) -> Result<User, diesel::result::Error> {
@@ -152,6 +246,44 @@ impl UserRepository {
             .get_result(&mut self.get_connection());
     }
 
+    pub fn update_password(
+        &self,
+        user_id: Arc<i32>,
+        hashed_password: &str
+    ) -> Result<User, diesel::result::Error> {
+        use self::users::dsl::*;
+        let query = diesel::update(users.filter(id.eq(*user_id)).filter(deleted_date.is_null()));
+        return query
+            .set((password.eq(hashed_password), updated_date.eq(Utc::now().naive_local())))
+            .returning(User::as_returning())
+            .get_result(&mut self.get_connection());
+    }
+
+    pub fn set_email_verified(
+        &self,
+        user_id: Arc<i32>,
+        verified_at: Option<NaiveDateTime>
+    ) -> Result<User, diesel::result::Error> {
+        use self::users::dsl::*;
+        let query = diesel::update(users.filter(id.eq(*user_id)));
+        return query
+            .set(email_verified_at.eq(verified_at))
+            .returning(User::as_returning())
+            .get_result(&mut self.get_connection());
+    }
+
+    pub fn set_locked_until(
+        &self,
+        user_id: Arc<i32>,
+        until: Option<NaiveDateTime>
+    ) -> Result<usize, diesel::result::Error> {
+        use self::users::dsl::*;
+        return diesel
+            ::update(users.filter(id.eq(*user_id)))
+            .set(locked_until.eq(until))
+            .execute(&mut self.get_connection());
+    }
+
     pub fn delete(&self, user_id: Arc<i32>) -> Result<usize, diesel::result::Error> {
         use self::users::dsl::*;
         return diesel
@@ -161,3 +293,25 @@ impl UserRepository {
             .execute(&mut self.get_connection());
     }
 }
+
+fn filtered_users(filter: &UserFilterDTO) -> users::BoxedQuery<'static, Pg> {
+    let mut query = users::table.filter(users::deleted_date.is_null()).into_boxed();
+    if let Some(name) = &filter.name {
+        query = query.filter(users::name.ilike(format!("%{}%", escape_like(name))));
+    }
+    if let Some(email) = &filter.email {
+        query = query.filter(users::email.eq(email.clone()));
+    }
+    if let Some(created_from) = filter.created_from {
+        query = query.filter(users::created_date.ge(created_from));
+    }
+    if let Some(created_before) = filter.created_before {
+        query = query.filter(users::created_date.lt(created_before));
+    }
+    return query;
+}
+
+/// Escapes `LIKE` wildcards, so that they match literally.
+fn escape_like(value: &str) -> String {
+    return value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+}
@@ -0,0 +1,12 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+
+pub struct ApiKeyDTO {
+    pub user_id: Arc<i32>,
+    pub name: Arc<str>,
+    pub prefix: Arc<str>,
+    pub secret_hash: Arc<str>,
+    pub scopes: Vec<Arc<str>>,
+    pub expires_at: Option<NaiveDateTime>,
+}
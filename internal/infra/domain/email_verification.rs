@@ -0,0 +1,10 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+
+pub struct EmailVerificationDTO {
+    pub user_id: Arc<i32>,
+    pub email: Arc<str>,
+    pub token_hash: Arc<str>,
+    pub expires_at: NaiveDateTime,
+}
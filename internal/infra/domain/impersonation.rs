@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use rust_commons::uuid::Uuid;
+use serde::Serialize;
+
+use crate::infra::http::resources::user_resource::UserResponse;
+
+/// Values of `impersonation_events.event`.
+pub const IMPERSONATION_STARTED: &str = "start";
+pub const IMPERSONATION_STOPPED: &str = "stop";
+
+/// Inserted into request extensions by `auth_middleware` next to the impersonated `UserDTO`
+/// when the request is made with an impersonation token.
+#[derive(Clone)]
+pub struct ImpersonationDTO {
+    /// Admin who started the impersonation.
+    pub actor_id: Arc<i32>,
+    pub user_id: Arc<i32>,
+}
+
+pub struct ImpersonationEventDTO {
+    pub actor_id: Arc<i32>,
+    pub user_id: Arc<i32>,
+    pub session_uuid: Uuid,
+    pub event: &'static str,
+    pub reason: Option<Arc<str>>,
+    pub client_ip: Option<Arc<str>>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// Impersonation tokens have no refresh token, a new impersonation is started once it expires.
+#[derive(Clone, Serialize)]
+pub struct ImpersonationTokenDTO {
+    pub user: UserResponse,
+    pub token: Arc<str>,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use rust_commons::uuid::Uuid;
+use serde::Serialize;
+
+/// Values of `token_type`, also accepted as `token_type_hint`.
+pub const ACCESS_TOKEN_TYPE: &str = "access_token";
+pub const API_KEY_TYPE: &str = "api_key";
+
+/// Introspection result in the shape of RFC 7662, inactive tokens carry only `active: false`
+/// so the response does not reveal why the token was rejected.
+#[derive(Clone, Default, Serialize)]
+pub struct IntrospectionDTO {
+    pub active: bool,
+    /// Id of the user the token acts as.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<Arc<str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<Arc<str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<&'static str>,
+    /// Space separated permissions available to the token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<Arc<str>>,
+    /// Unix timestamp, missing for API keys without expiry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    /// Session uuid, only for access tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    /// Real user behind an impersonation token, as the `act` claim of RFC 8693.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub act: Option<IntrospectionActorDTO>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct IntrospectionActorDTO {
+    pub sub: Arc<str>,
+}
+
+impl IntrospectionDTO {
+    pub fn inactive() -> IntrospectionDTO {
+        return IntrospectionDTO::default();
+    }
+}
@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+/// Values of `login_events.outcome`.
+pub const LOGIN_SUCCEEDED: &str = "success";
+pub const LOGIN_FAILED: &str = "failure";
+
+/// Values of `login_events.reason` for failed logins.
+pub const INVALID_PASSWORD: &str = "invalid_password";
+pub const INVALID_TWO_FACTOR_CODE: &str = "invalid_two_factor_code";
+pub const ACCOUNT_LOCKED: &str = "account_locked";
+
+pub struct LoginEventDTO {
+    pub user_id: Arc<i32>,
+    pub outcome: &'static str,
+    pub reason: Option<&'static str>,
+    pub client_ip: Option<Arc<str>>,
+    pub user_agent: Option<Arc<str>>,
+    pub device_fingerprint: Arc<str>,
+    pub new_device: bool,
+}
@@ -0,0 +1,11 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+
+pub struct MagicLinkDTO {
+    pub user_id: Arc<i32>,
+    /// Email the link was sent to, the link stops working if the user changes it.
+    pub email: Arc<str>,
+    pub token_hash: Arc<str>,
+    pub expires_at: NaiveDateTime,
+}
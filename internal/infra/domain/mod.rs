@@ -1,2 +1,14 @@
 pub mod user;
 pub mod session;
+pub mod refresh_token;
+pub mod password_reset;
+pub mod magic_link;
+pub mod email_verification;
+pub mod two_factor;
+pub mod role;
+pub mod api_key;
+pub mod user_identity;
+pub mod impersonation;
+pub mod introspection;
+pub mod login_event;
+pub mod pagination;
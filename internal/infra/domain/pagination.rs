@@ -0,0 +1,111 @@
+use chrono::NaiveDateTime;
+
+/// Page size used when the client does not ask for one.
+pub const DEFAULT_PER_PAGE: u32 = 20;
+/// Bigger pages are rejected, so that a single request can not load a whole table.
+pub const MAX_PER_PAGE: u32 = 100;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PageRequestDTO {
+    /// Starts from 1.
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl PageRequestDTO {
+    pub fn offset(&self) -> i64 {
+        return (i64::from(self.page) - 1) * i64::from(self.per_page);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PageDTO<T> {
+    pub data: Vec<T>,
+    /// Number of rows matching the filters across all pages.
+    pub total: u64,
+}
+
+/// Position of a row in `(created_date, id)` order, used by keyset pagination.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeysetDTO {
+    pub created_date: NaiveDateTime,
+    pub id: i32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CursorPageRequestDTO {
+    /// Rows after this position, or before it when `backward`. The list starts from the
+    /// beginning when not set.
+    pub key: Option<KeysetDTO>,
+    pub backward: bool,
+    /// Newest rows first.
+    pub descending: bool,
+    pub per_page: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct CursorPageDTO<T> {
+    pub data: Vec<T>,
+    /// Position of the last row when there are rows after the page.
+    pub next: Option<KeysetDTO>,
+    /// Position of the first row when there are rows before the page.
+    pub prev: Option<KeysetDTO>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortDTO<F> {
+    pub field: F,
+    pub descending: bool,
+}
+
+impl<F> SortDTO<F> {
+    /// Parses comma separated field names, `-` prefix sorts descending, e.g. `-created_date,name`.
+    pub fn parse_list(
+        value: &str,
+        parse_field: impl Fn(&str) -> Option<F>
+    ) -> Result<Vec<SortDTO<F>>, String> {
+        let mut sort = Vec::new();
+        for item in value.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (name, descending) = match item.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (item, false),
+            };
+            let Some(field) = parse_field(name) else {
+                return Err(format!("Can not sort by {}", name));
+            };
+            sort.push(SortDTO { field, descending });
+        }
+        return Ok(sort);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SortDTO;
+
+    fn parse_field(name: &str) -> Option<&'static str> {
+        return ["name", "created_date"].into_iter().find(|field| *field == name);
+    }
+
+    #[test]
+    fn sort_list_is_parsed_in_order() {
+        assert_eq!(
+            SortDTO::parse_list(" -created_date, name ,", parse_field),
+            Ok(
+                vec![
+                    SortDTO { field: "created_date", descending: true },
+                    SortDTO { field: "name", descending: false }
+                ]
+            )
+        );
+        assert_eq!(SortDTO::parse_list("", parse_field), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn unknown_sort_field_is_rejected() {
+        assert_eq!(
+            SortDTO::parse_list("name,-password", parse_field),
+            Err("Can not sort by password".to_string())
+        );
+    }
+}
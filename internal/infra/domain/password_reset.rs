@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+
+pub struct PasswordResetDTO {
+    pub user_id: Arc<i32>,
+    pub token_hash: Arc<str>,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,11 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use rust_commons::uuid::Uuid;
+
+pub struct RefreshTokenDTO {
+    pub user_id: Arc<i32>,
+    pub session_uuid: Uuid,
+    pub token_hash: Arc<str>,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,38 @@
+use std::{ collections::HashSet, sync::Arc };
+
+use serde::Serialize;
+
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Permission names seeded by migrations.
+pub mod permissions {
+    pub const USERS_READ: &str = "users.read";
+    pub const USERS_UNLOCK: &str = "users.unlock";
+    pub const ROLES_MANAGE: &str = "roles.manage";
+    pub const USERS_IMPERSONATE: &str = "users.impersonate";
+    pub const TOKENS_INTROSPECT: &str = "tokens.introspect";
+}
+
+/// Roles of the authenticated user, loaded by `auth_middleware` alongside `UserDTO`.
+#[derive(Clone, Default, Serialize)]
+pub struct UserRolesDTO {
+    pub roles: Vec<Arc<str>>,
+    pub permissions: HashSet<Arc<str>>,
+}
+
+impl UserRolesDTO {
+    pub fn has_permission(&self, permission: &str) -> bool {
+        return self.permissions.contains(permission);
+    }
+
+    /// Keeps only permissions listed in scopes, used for requests made with an API key.
+    pub fn scoped(self, scopes: &[String]) -> UserRolesDTO {
+        return UserRolesDTO {
+            roles: self.roles,
+            permissions: self.permissions
+                .into_iter()
+                .filter(|permission| scopes.iter().any(|scope| scope.as_str() == &**permission))
+                .collect(),
+        };
+    }
+}
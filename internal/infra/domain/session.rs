@@ -1,7 +1,10 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 
+use actix_web::{ http::header::{ USER_AGENT, X_FORWARDED_FOR }, HttpRequest };
 use serde::{ Deserialize, Serialize };
 use rust_commons::uuid::Uuid;
+use config::{ TrustedProxy, CONFIGURATION };
 
 #[derive(Serialize, Deserialize)]
 pub struct SessionDTO {
@@ -14,3 +17,112 @@ impl SessionDTO {
         return SessionDTO { user_id, uuid };
     }
 }
+
+#[derive(Clone, Serialize)]
+pub struct AuthTokensDTO {
+    pub token: Arc<str>,
+    pub refresh_token: Arc<str>,
+}
+
+/// Information about the client which opened a session.
+#[derive(Clone, Default)]
+pub struct ClientInfoDTO {
+    pub user_agent: Option<Arc<str>>,
+    pub client_ip: Option<Arc<str>>,
+}
+
+impl ClientInfoDTO {
+    pub fn from_request(request: &HttpRequest) -> ClientInfoDTO {
+        return ClientInfoDTO {
+            user_agent: request.headers()
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(Arc::from),
+            client_ip: client_ip(request, &CONFIGURATION.trusted_proxies)
+                .map(|ip| Arc::from(ip.to_string())),
+        };
+    }
+}
+
+/// Address of the connected peer, or the one reported in `X-Forwarded-For` when the peer is a
+/// trusted proxy. The header is read from the right, since anything left of the last trusted
+/// proxy may have been written by the client itself.
+fn client_ip(request: &HttpRequest, trusted_proxies: &[TrustedProxy]) -> Option<IpAddr> {
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+    let mut client_ip = request.peer_addr()?.ip().to_canonical();
+    if !is_trusted(client_ip) {
+        return Some(client_ip);
+    }
+    let forwarded: Vec<&str> = request.headers()
+        .get_all(X_FORWARDED_FOR)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    for entry in forwarded.into_iter().rev() {
+        match entry.parse::<IpAddr>() {
+            Ok(ip) => {
+                client_ip = ip.to_canonical();
+                if !is_trusted(client_ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    return Some(client_ip);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{ IpAddr, SocketAddr };
+
+    use actix_web::test::TestRequest;
+    use config::TrustedProxy;
+
+    use super::client_ip;
+
+    fn proxies() -> Vec<TrustedProxy> {
+        return vec![TrustedProxy { network: "10.0.0.0".parse().unwrap(), prefix_len: 8 }];
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        return Some(value.parse().unwrap());
+    }
+
+    fn request(peer: &str, forwarded_for: Option<&str>) -> TestRequest {
+        let peer = SocketAddr::new(peer.parse().unwrap(), 4000);
+        let request = TestRequest::default().peer_addr(peer);
+        return match forwarded_for {
+            Some(value) => request.insert_header(("X-Forwarded-For", value)),
+            None => request,
+        };
+    }
+
+    #[test]
+    fn ignores_forwarded_header_from_untrusted_peer() {
+        let request = request("203.0.113.7", Some("198.51.100.1")).to_http_request();
+        assert_eq!(client_ip(&request, &proxies()), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn takes_rightmost_untrusted_forwarded_address() {
+        let request = request("10.0.0.2", Some("1.2.3.4, 198.51.100.1, 10.0.0.1"))
+            .to_http_request();
+        assert_eq!(client_ip(&request, &proxies()), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn stops_at_invalid_forwarded_entry() {
+        let request = request("10.0.0.2", Some("198.51.100.1, unknown, 10.0.0.1"))
+            .to_http_request();
+        assert_eq!(client_ip(&request, &proxies()), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn uses_peer_when_nothing_is_forwarded() {
+        let request = request("10.0.0.2", None).to_http_request();
+        assert_eq!(client_ip(&request, &proxies()), ip("10.0.0.2"));
+        assert_eq!(client_ip(&request, &[]), ip("10.0.0.2"));
+    }
+}
@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+#[derive(Clone, Serialize)]
+pub struct TwoFactorEnrollmentDTO {
+    pub secret: Arc<str>,
+    pub otpauth_uri: Arc<str>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RecoveryCodesDTO {
+    pub recovery_codes: Vec<Arc<str>>,
+}
+
+/// Returned by login instead of tokens when the user has two-factor authentication enabled.
+#[derive(Clone, Serialize)]
+pub struct TwoFactorChallengeDTO {
+    pub two_factor_required: bool,
+    pub challenge_token: Arc<str>,
+}
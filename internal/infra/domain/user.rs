@@ -5,6 +5,7 @@ use serde::Serialize;
 
 use crate::infra::{
     database::user_repository::User,
+    domain::two_factor::TwoFactorChallengeDTO,
     http::{ middlewares::Userable, resources::user_resource::UserResponse },
 };
 
@@ -18,12 +19,59 @@ pub struct UserDTO {
     pub created_date: Arc<NaiveDateTime>,
     pub updated_date: Arc<NaiveDateTime>,
     pub deleted_date: Arc<Option<NaiveDateTime>>,
+    pub email_verified_at: Arc<Option<NaiveDateTime>>,
+    pub locked_until: Arc<Option<NaiveDateTime>>,
+    pub locale: Option<Arc<str>>,
 }
 
 #[derive(Clone, Serialize)]
 pub struct AuthenticatedUserDTO {
     pub user: UserResponse,
-    pub token: Arc<str>,
+    /// Tokens are not issued when the user has to verify email before logging in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<Arc<str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<Arc<str>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum LoginDTO {
+    Authenticated(AuthenticatedUserDTO),
+    TwoFactorRequired(TwoFactorChallengeDTO),
+}
+
+/// Fields `GET /user/all` can be sorted by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UserSortField {
+    Id,
+    Name,
+    Email,
+    CreatedDate,
+    UpdatedDate,
+}
+
+impl UserSortField {
+    pub fn from_name(name: &str) -> Option<UserSortField> {
+        match name {
+            "id" => Some(UserSortField::Id),
+            "name" => Some(UserSortField::Name),
+            "email" => Some(UserSortField::Email),
+            "created_date" => Some(UserSortField::CreatedDate),
+            "updated_date" => Some(UserSortField::UpdatedDate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UserFilterDTO {
+    /// Case insensitive part of the name.
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub created_from: Option<NaiveDateTime>,
+    /// Exclusive.
+    pub created_before: Option<NaiveDateTime>,
 }
 
 impl UserDTO {
@@ -37,6 +85,9 @@ impl UserDTO {
             created_date: Arc::new(user.created_date),
             updated_date: Arc::new(user.updated_date),
             deleted_date: Arc::new(user.deleted_date),
+            email_verified_at: Arc::new(user.email_verified_at),
+            locked_until: Arc::new(user.locked_until),
+            locale: user.locale.map(Arc::from),
         }
     }
 
@@ -57,8 +108,15 @@ impl UserDTO {
             created_date: *self.created_date,
             updated_date: *self.updated_date,
             deleted_date: self.deleted_date.as_ref().and_then(|date| Some(date)),
+            email_verified_at: *self.email_verified_at,
+            locked_until: *self.locked_until,
+            locale: self.locale.as_ref().map(|locale| locale.to_string()),
         }
     }
+
+    pub fn is_email_verified(&self) -> bool {
+        return self.email_verified_at.is_some();
+    }
 }
 
 impl Userable for UserDTO {
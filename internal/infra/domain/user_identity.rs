@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+
+pub struct UserIdentityDTO {
+    pub user_id: Arc<i32>,
+    pub provider: Arc<str>,
+    pub subject: Arc<str>,
+    pub email: Option<Arc<str>>,
+}
+
+pub struct OidcLoginStateDTO {
+    pub state_hash: Arc<str>,
+    pub provider: Arc<str>,
+    pub code_verifier: Arc<str>,
+    pub nonce: Arc<str>,
+    pub expires_at: NaiveDateTime,
+    /// Hash of the value set in a cookie of the browser which started the login.
+    pub binding_hash: Arc<str>,
+}
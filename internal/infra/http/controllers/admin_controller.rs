@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use actix_web::{ web, HttpMessage, HttpRequest, HttpResponse, Responder };
+
+use crate::{
+    infra::{
+        domain::{ role::permissions::USERS_IMPERSONATE, session::ClientInfoDTO, user::UserDTO },
+        http::{
+            requests::{
+                admin_request::{
+                    ImpersonationRequest,
+                    RoleRequest,
+                    UserPathRequest,
+                    UserRolePathRequest,
+                },
+                JsonValidator,
+                PathValidator,
+            },
+            resources::{ impersonation_resource::ImpersonationEventResponse, ErrorResponse },
+        },
+    },
+    services::{
+        auth_service::AuthService,
+        login_throttle_service::LoginThrottleService,
+        role_service::RoleService,
+    },
+};
+
+#[derive(Clone)]
+pub struct AdminController {
+    role_service: Arc<RoleService>,
+    login_throttle_service: Arc<LoginThrottleService>,
+    auth_service: Arc<AuthService>,
+}
+
+impl AdminController {
+    pub fn new(
+        role_service: Arc<RoleService>,
+        login_throttle_service: Arc<LoginThrottleService>,
+        auth_service: Arc<AuthService>
+    ) -> AdminController {
+        return AdminController { role_service, login_throttle_service, auth_service };
+    }
+
+    async fn unlock_user(&self, user_id: i32) -> impl Responder {
+        match self.login_throttle_service.unlock(Arc::new(user_id)) {
+            Ok(_) => {
+                return HttpResponse::Ok().finish();
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn find_user_roles(&self, user_id: i32) -> impl Responder {
+        match self.role_service.find_user_roles(Arc::new(user_id)) {
+            Ok(roles) => {
+                return HttpResponse::Ok().json(roles);
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn grant_role(&self, user_id: i32, role: JsonValidator<RoleRequest>) -> impl Responder {
+        match self.role_service.grant(Arc::new(user_id), &role.role) {
+            Ok(_) => {
+                return HttpResponse::Ok().finish();
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn revoke_role(&self, user_id: i32, role: &str) -> impl Responder {
+        match self.role_service.revoke(Arc::new(user_id), role) {
+            Ok(true) => {
+                return HttpResponse::Ok().finish();
+            }
+            Ok(false) => {
+                return HttpResponse::NotFound().json(
+                    ErrorResponse::new_error(Some("User does not have the role".to_string()))
+                );
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn impersonate_user(
+        &self,
+        request: HttpRequest,
+        user_id: i32,
+        impersonation: JsonValidator<ImpersonationRequest>
+    ) -> impl Responder {
+        let Some(actor) = request.extensions().get::<UserDTO>().cloned() else {
+            return HttpResponse::Forbidden().json("Not authenticated");
+        };
+        // Impersonating someone who can impersonate as well would escalate privileges.
+        match self.role_service.find_user_roles(Arc::new(user_id)) {
+            Ok(roles) if roles.has_permission(USERS_IMPERSONATE) => {
+                return HttpResponse::Forbidden().json(
+                    ErrorResponse::new_error(
+                        Some("User with impersonation permission can not be impersonated".into())
+                    )
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+        let client_info = ClientInfoDTO::from_request(&request);
+        match
+            self.auth_service.impersonate(
+                Arc::new(actor.id.unwrap()),
+                Arc::new(user_id),
+                &impersonation.reason,
+                &client_info
+            )
+        {
+            Ok(token) => {
+                return HttpResponse::Ok().json(token);
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn find_impersonations(&self, user_id: i32) -> impl Responder {
+        match self.auth_service.find_impersonation_events(Arc::new(user_id)) {
+            Ok(events) => {
+                return HttpResponse::Ok().json(
+                    ImpersonationEventResponse::models_to_response(&events)
+                );
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+}
+
+pub async fn unlock_user(
+    admin_controller: web::Data<AdminController>,
+    path: PathValidator<UserPathRequest>
+) -> impl Responder {
+    return admin_controller.unlock_user(path.id).await;
+}
+
+pub async fn find_user_roles(
+    admin_controller: web::Data<AdminController>,
+    path: PathValidator<UserPathRequest>
+) -> impl Responder {
+    return admin_controller.find_user_roles(path.id).await;
+}
+
+pub async fn grant_role(
+    admin_controller: web::Data<AdminController>,
+    path: PathValidator<UserPathRequest>,
+    role: JsonValidator<RoleRequest>
+) -> impl Responder {
+    return admin_controller.grant_role(path.id, role).await;
+}
+
+pub async fn revoke_role(
+    admin_controller: web::Data<AdminController>,
+    path: PathValidator<UserRolePathRequest>
+) -> impl Responder {
+    return admin_controller.revoke_role(path.id, &path.role).await;
+}
+
+pub async fn impersonate_user(
+    admin_controller: web::Data<AdminController>,
+    request: HttpRequest,
+    path: PathValidator<UserPathRequest>,
+    impersonation: JsonValidator<ImpersonationRequest>
+) -> impl Responder {
+    return admin_controller.impersonate_user(request, path.id, impersonation).await;
+}
+
+pub async fn find_impersonations(
+    admin_controller: web::Data<AdminController>,
+    path: PathValidator<UserPathRequest>
+) -> impl Responder {
+    return admin_controller.find_impersonations(path.id).await;
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use actix_web::{ web, HttpMessage, HttpRequest, HttpResponse, Responder };
+
+use crate::{
+    infra::{
+        domain::{ role::UserRolesDTO, user::UserDTO },
+        http::{
+            requests::{
+                api_key_request::{ ApiKeyPathRequest, CreateApiKeyRequest },
+                JsonValidator,
+                PathValidator,
+            },
+            resources::{
+                api_key_resource::{ ApiKeyResponse, CreatedApiKeyResponse },
+                ErrorResponse,
+            },
+        },
+    },
+    services::{ api_key_service::ApiKeyService, auth_service::Claims },
+};
+
+#[derive(Clone)]
+pub struct ApiKeyController {
+    api_key_service: Arc<ApiKeyService>,
+}
+
+impl ApiKeyController {
+    pub fn new(api_key_service: Arc<ApiKeyService>) -> ApiKeyController {
+        return ApiKeyController { api_key_service };
+    }
+
+    async fn create(
+        &self,
+        request: HttpRequest,
+        create: JsonValidator<CreateApiKeyRequest>
+    ) -> impl Responder {
+        let extensions = request.extensions();
+        // Keys are created only from a login session, so a leaked key can not mint new ones.
+        if extensions.get::<Claims>().is_none() {
+            return HttpResponse::Forbidden().json(
+                ErrorResponse::new_error(
+                    Some("API keys can be created only with a login session".to_string())
+                )
+            );
+        }
+        let user = extensions.get::<UserDTO>();
+        let roles = extensions.get::<UserRolesDTO>();
+        if let (Some(user), Some(roles)) = (user, roles) {
+            let user_id = Arc::new(user.id.unwrap());
+            match self.api_key_service.create(user_id, roles, create.into_inner()) {
+                Ok((api_key, key)) => {
+                    return HttpResponse::Created().json(CreatedApiKeyResponse {
+                        api_key: ApiKeyResponse::model_to_response(&api_key),
+                        key: Arc::from(key),
+                    });
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    async fn find_all(&self, request: HttpRequest) -> impl Responder {
+        if let Some(user) = request.extensions().get::<UserDTO>() {
+            match self.api_key_service.find_by_user_id(Arc::new(user.id.unwrap())) {
+                Ok(api_keys) => {
+                    return HttpResponse::Ok().json(ApiKeyResponse::models_to_response(&api_keys));
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    async fn revoke(&self, request: HttpRequest, api_key_id: i32) -> impl Responder {
+        if let Some(user) = request.extensions().get::<UserDTO>() {
+            match self.api_key_service.revoke(Arc::new(user.id.unwrap()), api_key_id) {
+                Ok(true) => {
+                    return HttpResponse::Ok().finish();
+                }
+                Ok(false) => {
+                    return HttpResponse::NotFound().json(
+                        ErrorResponse::new_error(Some("API key not found".to_string()))
+                    );
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Unauthorized().finish();
+    }
+}
+
+pub async fn create_api_key(
+    api_key_controller: web::Data<ApiKeyController>,
+    request: HttpRequest,
+    create: JsonValidator<CreateApiKeyRequest>
+) -> impl Responder {
+    return api_key_controller.create(request, create).await;
+}
+
+pub async fn find_api_keys(
+    api_key_controller: web::Data<ApiKeyController>,
+    request: HttpRequest
+) -> impl Responder {
+    return api_key_controller.find_all(request).await;
+}
+
+pub async fn revoke_api_key(
+    api_key_controller: web::Data<ApiKeyController>,
+    request: HttpRequest,
+    path: PathValidator<ApiKeyPathRequest>
+) -> impl Responder {
+    return api_key_controller.revoke(request, path.id).await;
+}
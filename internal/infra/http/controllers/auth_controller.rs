@@ -1,32 +1,233 @@
 use std::sync::Arc;
 
-use actix_web::{ web, HttpMessage, HttpRequest, HttpResponse, Responder };
+use actix_web::{
+    http::header::RETRY_AFTER,
+    web,
+    HttpMessage,
+    HttpRequest,
+    HttpResponse,
+    Responder,
+};
+use rust_commons::uuid::Uuid;
 
 use crate::{
     infra::{
-        domain::session::SessionDTO,
+        domain::session::{ ClientInfoDTO, SessionDTO },
         http::{
-            requests::{ user_request::{ AuthRequest, UserRequest }, JsonValidator },
-            resources::ErrorResponse,
+            cookies::{ self, ACCESS_TOKEN_COOKIE, REFRESH_TOKEN_COOKIE },
+            requests::{
+                auth_request::{
+                    ConsumeMagicLinkRequest,
+                    ForgotPasswordRequest,
+                    MagicLinkRequest,
+                    RefreshTokenRequest,
+                    ResendVerificationRequest,
+                    ResetPasswordRequest,
+                    SessionPathRequest,
+                    TwoFactorVerifyRequest,
+                    VerifyEmailRequest,
+                },
+                user_request::{ AuthRequest, UserRequest },
+                JsonValidator,
+                PathValidator,
+            },
+            resources::{
+                session_resource::SessionResponse,
+                user_resource::UserResponse,
+                ErrorResponse,
+            },
         },
     },
-    services::auth_service::{ AuthService, Claims },
+    services::{
+        auth_service::{ AuthService, AuthServiceError, Claims },
+        email_verification_service::EmailVerificationService,
+    },
 };
 
 #[derive(Clone)]
 pub struct AuthController {
     auth_service: Arc<AuthService>,
+    email_verification_service: Arc<EmailVerificationService>,
 }
 
 impl AuthController {
-    pub fn new(auth_service: Arc<AuthService>) -> AuthController {
-        return AuthController { auth_service };
+    pub fn new(
+        auth_service: Arc<AuthService>,
+        email_verification_service: Arc<EmailVerificationService>
+    ) -> AuthController {
+        return AuthController { auth_service, email_verification_service };
     }
 
-    async fn register(&self, user: JsonValidator<UserRequest>) -> impl Responder {
-        match self.auth_service.register(user.into_inner()).await {
+    async fn register(
+        &self,
+        request: HttpRequest,
+        user: JsonValidator<UserRequest>
+    ) -> impl Responder {
+        let client_info = ClientInfoDTO::from_request(&request);
+        match self.auth_service.register(user.into_inner(), client_info).await {
             Ok(user) => {
-                return HttpResponse::Created().json(user);
+                return cookies::authenticated_response(HttpResponse::Created(), user);
+            }
+            Err(AuthServiceError::PasswordPolicyError(errors)) => {
+                return HttpResponse::BadRequest().json(ErrorResponse::from(errors));
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn login(
+        &self,
+        request: HttpRequest,
+        user_credentials: JsonValidator<AuthRequest>
+    ) -> impl Responder {
+        let client_info = ClientInfoDTO::from_request(&request);
+        match self.auth_service.login(user_credentials.into_inner(), client_info) {
+            Ok(login) => {
+                return cookies::login_response(login);
+            }
+            Err(AuthServiceError::LockedError(seconds)) => {
+                return too_many_attempts(seconds);
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn verify_two_factor(
+        &self,
+        request: HttpRequest,
+        verify: JsonValidator<TwoFactorVerifyRequest>
+    ) -> impl Responder {
+        let client_info = ClientInfoDTO::from_request(&request);
+        match
+            self.auth_service.verify_two_factor(&verify.challenge_token, &verify.code, client_info)
+        {
+            Ok(user) => {
+                return cookies::authenticated_response(HttpResponse::Ok(), user);
+            }
+            Err(AuthServiceError::LockedError(seconds)) => {
+                return too_many_attempts(seconds);
+            }
+            Err(e) => {
+                return HttpResponse::Unauthorized().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    /// Takes the refresh token from the body, or from the cookie in cookie mode.
+    async fn refresh(
+        &self,
+        request: HttpRequest,
+        refresh_request: Option<JsonValidator<RefreshTokenRequest>>
+    ) -> impl Responder {
+        let refresh_token = match refresh_request {
+            Some(refresh_request) => refresh_request.into_inner().refresh_token,
+            None => {
+                let cookie = request
+                    .cookie(REFRESH_TOKEN_COOKIE)
+                    .filter(|_| cookies::is_enabled())
+                    .map(|cookie| cookie.value().to_owned());
+                let Some(refresh_token) = cookie else {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some("Refresh token is missing".to_string()))
+                    );
+                };
+                if !cookies::is_csrf_valid(&request) {
+                    return HttpResponse::Forbidden().json(
+                        ErrorResponse::new_error(Some("Invalid CSRF token".to_string()))
+                    );
+                }
+                refresh_token
+            }
+        };
+        match self.auth_service.refresh(&refresh_token) {
+            Ok(tokens) => {
+                return cookies::tokens_response(tokens);
+            }
+            Err(e) => {
+                return HttpResponse::Unauthorized().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn forgot_password(
+        &self,
+        forgot: JsonValidator<ForgotPasswordRequest>
+    ) -> impl Responder {
+        match self.auth_service.forgot_password(&forgot.email) {
+            Ok(_) => {
+                return HttpResponse::Ok().finish();
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn reset_password(&self, reset: JsonValidator<ResetPasswordRequest>) -> impl Responder {
+        match self.auth_service.reset_password(reset.into_inner()) {
+            Ok(_) => {
+                return HttpResponse::Ok().finish();
+            }
+            Err(AuthServiceError::PasswordPolicyError(errors)) => {
+                return HttpResponse::BadRequest().json(ErrorResponse::from(errors));
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn send_magic_link(
+        &self,
+        request: HttpRequest,
+        magic_link: JsonValidator<MagicLinkRequest>
+    ) -> impl Responder {
+        let client_info = ClientInfoDTO::from_request(&request);
+        match
+            self.auth_service.send_magic_link(&magic_link.email, client_info.client_ip.as_deref())
+        {
+            Ok(_) => {
+                return HttpResponse::Ok().finish();
+            }
+            Err(AuthServiceError::LockedError(seconds)) => {
+                return too_many_attempts(seconds);
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn consume_magic_link(
+        &self,
+        request: HttpRequest,
+        consume: JsonValidator<ConsumeMagicLinkRequest>
+    ) -> impl Responder {
+        let client_info = ClientInfoDTO::from_request(&request);
+        match self.auth_service.consume_magic_link(&consume.token, client_info) {
+            Ok(login) => {
+                return cookies::login_response(login);
+            }
+            Err(AuthServiceError::LockedError(seconds)) => {
+                return too_many_attempts(seconds);
             }
             Err(e) => {
                 return HttpResponse::BadRequest().json(
@@ -36,10 +237,118 @@ impl AuthController {
         }
     }
 
-    async fn login(&self, user_credentials: web::Json<AuthRequest>) -> impl Responder {
-        match self.auth_service.login(user_credentials.into_inner()) {
+    async fn verify_email(&self, verify: JsonValidator<VerifyEmailRequest>) -> impl Responder {
+        match self.email_verification_service.verify(&verify.token) {
             Ok(user) => {
-                return HttpResponse::Ok().json(user);
+                return HttpResponse::Ok().json(UserResponse::dto_to_response(&user));
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn resend_verification(
+        &self,
+        resend: JsonValidator<ResendVerificationRequest>
+    ) -> impl Responder {
+        match self.email_verification_service.resend(&resend.email) {
+            Ok(_) => {
+                return HttpResponse::Ok().finish();
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    async fn jwks(&self) -> impl Responder {
+        return HttpResponse::Ok().json(self.auth_service.jwks());
+    }
+
+    /// Ends an impersonation through `stop_impersonation`, so it is recorded in the audit trail.
+    /// Works without a valid access token, so that a client holding an expired one can still
+    /// log out: the session is then found by the refresh token from the body, or from the cookie
+    /// in cookie mode. Cookies are cleared even when no session is found.
+    async fn logout(
+        &self,
+        request: HttpRequest,
+        logout_request: Option<JsonValidator<RefreshTokenRequest>>
+    ) -> impl Responder {
+        let bearer_token = request
+            .headers()
+            .get("Authorization")
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map(str::to_owned);
+        let cookie = |name| {
+            return request
+                .cookie(name)
+                .filter(|_| cookies::is_enabled())
+                .map(|cookie| cookie.value().to_owned());
+        };
+        let access_cookie = bearer_token.is_none().then(|| cookie(ACCESS_TOKEN_COOKIE)).flatten();
+        let (refresh_token, refresh_cookie) = match logout_request {
+            Some(logout_request) => (Some(logout_request.into_inner().refresh_token), None),
+            None => (None, cookie(REFRESH_TOKEN_COOKIE)),
+        };
+        // Tokens read from cookies need the CSRF header, like on the other routes.
+        let uses_cookies = access_cookie.is_some() || refresh_cookie.is_some();
+        if uses_cookies && !cookies::is_csrf_valid(&request) {
+            return HttpResponse::Forbidden().json(
+                ErrorResponse::new_error(Some("Invalid CSRF token".to_string()))
+            );
+        }
+
+        let claims = bearer_token
+            .or(access_cookie)
+            .and_then(|token| self.auth_service.decode_claims(&token).ok())
+            .filter(|claims| self.auth_service.check(claims.clone()));
+        let result = match (claims, refresh_token.or(refresh_cookie)) {
+            (Some(claims), _) if claims.actor_id.is_some() => {
+                let client_info = ClientInfoDTO::from_request(&request);
+                self.auth_service.stop_impersonation(&claims, &client_info)
+            }
+            (Some(claims), _) => {
+                self.auth_service.logout(SessionDTO {
+                    user_id: claims.user_id.clone(),
+                    uuid: claims.uuid,
+                })
+            }
+            (None, Some(refresh_token)) => {
+                self.auth_service.logout_by_refresh_token(&refresh_token)
+            }
+            (None, None) => Err(AuthServiceError::ServiceError(Box::from("Not authenticated"))),
+        };
+        let mut response = match &result {
+            Ok(_) => HttpResponse::Ok(),
+            Err(_) => HttpResponse::Unauthorized(),
+        };
+        if cookies::is_enabled() {
+            cookies::clear_auth_cookies(&mut response);
+        }
+        match result {
+            Ok(_) => {
+                return response.finish();
+            }
+            Err(e) => {
+                return response.json(ErrorResponse::new_error(Some(e.to_string())));
+            }
+        }
+    }
+
+    async fn stop_impersonation(&self, request: HttpRequest) -> impl Responder {
+        let Some(claims) = request.extensions().get::<Claims>().cloned() else {
+            return HttpResponse::Unauthorized().finish();
+        };
+        let client_info = ClientInfoDTO::from_request(&request);
+        match self.auth_service.stop_impersonation(&claims, &client_info) {
+            Ok(_) => {
+                return HttpResponse::Ok().finish();
             }
             Err(e) => {
                 return HttpResponse::BadRequest().json(
@@ -49,15 +358,68 @@ impl AuthController {
         }
     }
 
-    async fn logout(&self, request: HttpRequest) -> impl Responder {
-        if let Some(claims) = request.extensions_mut().get::<Claims>() {
-            let session = SessionDTO {
-                user_id: claims.user_id.clone(),
-                uuid: claims.uuid.clone(),
-            };
-            match self.auth_service.logout(session) {
+    async fn find_sessions(&self, request: HttpRequest) -> impl Responder {
+        if let Some(claims) = request.extensions().get::<Claims>() {
+            match self.auth_service.find_sessions(claims.user_id.clone()) {
+                Ok(sessions) => {
+                    return HttpResponse::Ok().json(
+                        SessionResponse::models_to_response(&sessions, &claims.uuid)
+                    );
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    async fn find_session(&self, request: HttpRequest, uuid: Uuid) -> impl Responder {
+        if let Some(claims) = request.extensions().get::<Claims>() {
+            match self.auth_service.find_session(SessionDTO::new(claims.user_id.clone(), uuid)) {
+                Ok(session) => {
+                    return HttpResponse::Ok().json(
+                        SessionResponse::model_to_response(&session, &claims.uuid)
+                    );
+                }
+                Err(e) => {
+                    return HttpResponse::NotFound().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    async fn revoke_session(&self, request: HttpRequest, uuid: Uuid) -> impl Responder {
+        if let Some(claims) = request.extensions().get::<Claims>() {
+            match self.auth_service.revoke_session(SessionDTO::new(claims.user_id.clone(), uuid)) {
+                Ok(true) => {
+                    return HttpResponse::Ok().finish();
+                }
+                Ok(false) => {
+                    return HttpResponse::NotFound().json(
+                        ErrorResponse::new_error(Some("Session not found".to_string()))
+                    );
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    async fn revoke_other_sessions(&self, request: HttpRequest) -> impl Responder {
+        if let Some(claims) = request.extensions().get::<Claims>() {
+            match self.auth_service.revoke_other_sessions(claims) {
                 Ok(_) => {
-                    return HttpResponse::Ok().finish().map_into_boxed_body();
+                    return HttpResponse::Ok().finish();
                 }
                 Err(e) => {
                     return HttpResponse::BadRequest().json(
@@ -65,29 +427,142 @@ impl AuthController {
                     );
                 }
             }
-        } else {
-            return HttpResponse::Unauthorized().finish();
         }
+        return HttpResponse::Unauthorized().finish();
     }
 }
 
+fn too_many_attempts(retry_after: u64) -> HttpResponse {
+    return HttpResponse::TooManyRequests()
+        .insert_header((RETRY_AFTER, retry_after.to_string()))
+        .json(
+            ErrorResponse::new_error(
+                Some(AuthServiceError::LockedError(retry_after).to_string())
+            )
+        );
+}
+
+pub async fn jwks(auth_controller: web::Data<AuthController>) -> impl Responder {
+    return auth_controller.jwks().await;
+}
+
 pub async fn logout(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
+    logout_request: Option<JsonValidator<RefreshTokenRequest>>
+) -> impl Responder {
+    return auth_controller.logout(request, logout_request).await;
+}
+
+pub async fn stop_impersonation(
     auth_controller: web::Data<AuthController>,
     request: HttpRequest
 ) -> impl Responder {
-    return auth_controller.logout(request).await;
+    return auth_controller.stop_impersonation(request).await;
 }
 
 pub async fn register(
     auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
     user: JsonValidator<UserRequest>
 ) -> impl Responder {
-    return auth_controller.register(user).await;
+    return auth_controller.register(request, user).await;
 }
 
 pub async fn login(
     auth_controller: web::Data<AuthController>,
-    user: web::Json<AuthRequest>
+    request: HttpRequest,
+    user: JsonValidator<AuthRequest>
+) -> impl Responder {
+    return auth_controller.login(request, user).await;
+}
+
+pub async fn verify_two_factor(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
+    verify: JsonValidator<TwoFactorVerifyRequest>
+) -> impl Responder {
+    return auth_controller.verify_two_factor(request, verify).await;
+}
+
+pub async fn refresh(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
+    refresh_request: Option<JsonValidator<RefreshTokenRequest>>
+) -> impl Responder {
+    return auth_controller.refresh(request, refresh_request).await;
+}
+
+pub async fn forgot_password(
+    auth_controller: web::Data<AuthController>,
+    forgot: JsonValidator<ForgotPasswordRequest>
+) -> impl Responder {
+    return auth_controller.forgot_password(forgot).await;
+}
+
+pub async fn send_magic_link(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
+    magic_link: JsonValidator<MagicLinkRequest>
+) -> impl Responder {
+    return auth_controller.send_magic_link(request, magic_link).await;
+}
+
+pub async fn consume_magic_link(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
+    consume: JsonValidator<ConsumeMagicLinkRequest>
+) -> impl Responder {
+    return auth_controller.consume_magic_link(request, consume).await;
+}
+
+pub async fn reset_password(
+    auth_controller: web::Data<AuthController>,
+    reset: JsonValidator<ResetPasswordRequest>
+) -> impl Responder {
+    return auth_controller.reset_password(reset).await;
+}
+
+pub async fn verify_email(
+    auth_controller: web::Data<AuthController>,
+    verify: JsonValidator<VerifyEmailRequest>
+) -> impl Responder {
+    return auth_controller.verify_email(verify).await;
+}
+
+pub async fn resend_verification(
+    auth_controller: web::Data<AuthController>,
+    resend: JsonValidator<ResendVerificationRequest>
+) -> impl Responder {
+    return auth_controller.resend_verification(resend).await;
+}
+
+pub async fn find_sessions(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest
+) -> impl Responder {
+    return auth_controller.find_sessions(request).await;
+}
+
+pub async fn find_session(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
+    path: PathValidator<SessionPathRequest>
+) -> impl Responder {
+    return auth_controller.find_session(request, path.uuid).await;
+}
+
+pub async fn revoke_session(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest,
+    path: PathValidator<SessionPathRequest>
+) -> impl Responder {
+    return auth_controller.revoke_session(request, path.uuid).await;
+}
+
+pub async fn revoke_other_sessions(
+    auth_controller: web::Data<AuthController>,
+    request: HttpRequest
 ) -> impl Responder {
-    return auth_controller.login(user).await;
+    return auth_controller.revoke_other_sessions(request).await;
 }
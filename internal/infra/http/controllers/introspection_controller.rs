@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use actix_web::{ web, HttpResponse, Responder };
+
+use crate::{
+    infra::http::{
+        requests::{ auth_request::IntrospectionRequest, FormValidator },
+        resources::ErrorResponse,
+    },
+    services::introspection_service::IntrospectionService,
+};
+
+#[derive(Clone)]
+pub struct IntrospectionController {
+    introspection_service: Arc<IntrospectionService>,
+}
+
+impl IntrospectionController {
+    pub fn new(introspection_service: Arc<IntrospectionService>) -> IntrospectionController {
+        return IntrospectionController { introspection_service };
+    }
+
+    async fn introspect(&self, request: IntrospectionRequest) -> impl Responder {
+        match self.introspection_service.introspect(&request.token) {
+            Ok(introspection) => {
+                return HttpResponse::Ok().json(introspection);
+            }
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+}
+
+/// RFC 7662 token introspection, the token is sent form encoded.
+pub async fn introspect(
+    introspection_controller: web::Data<IntrospectionController>,
+    request: FormValidator<IntrospectionRequest>
+) -> impl Responder {
+    return introspection_controller.introspect(request.into_inner()).await;
+}
@@ -1,2 +1,7 @@
 pub mod user_controller;
 pub mod auth_controller;
+pub mod two_factor_controller;
+pub mod admin_controller;
+pub mod api_key_controller;
+pub mod oidc_controller;
+pub mod introspection_controller;
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use actix_web::{ http::header::LOCATION, web, HttpRequest, HttpResponse, Responder };
+
+use crate::{
+    infra::{
+        domain::session::ClientInfoDTO,
+        http::{
+            cookies::{ self, OIDC_BINDING_COOKIE },
+            requests::{
+                auth_request::{ OidcCallbackRequest, OidcProviderPathRequest },
+                PathValidator,
+                QueryValidator,
+            },
+            resources::ErrorResponse,
+        },
+    },
+    services::{ auth_service::AuthService, oidc_service::OidcService },
+};
+
+#[derive(Clone)]
+pub struct OidcController {
+    oidc_service: Arc<OidcService>,
+    auth_service: Arc<AuthService>,
+}
+
+impl OidcController {
+    pub fn new(oidc_service: Arc<OidcService>, auth_service: Arc<AuthService>) -> OidcController {
+        return OidcController { oidc_service, auth_service };
+    }
+
+    async fn start(&self, provider: &str) -> impl Responder {
+        match self.oidc_service.authorization_url(provider).await {
+            Ok((url, binding)) => {
+                let mut response = HttpResponse::Found();
+                cookies::set_oidc_binding_cookie(&mut response, &binding);
+                return response.insert_header((LOCATION, url)).finish();
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
+    /// The binding cookie is cleared whatever the outcome, every login starts with a new one.
+    async fn callback(
+        &self,
+        request: HttpRequest,
+        provider: &str,
+        callback: OidcCallbackRequest
+    ) -> impl Responder {
+        let mut response = self.finish_login(&request, provider, callback).await;
+        cookies::clear_oidc_binding_cookie(&mut response);
+        return response;
+    }
+
+    async fn finish_login(
+        &self,
+        request: &HttpRequest,
+        provider: &str,
+        callback: OidcCallbackRequest
+    ) -> HttpResponse {
+        if let Some(error) = callback.error {
+            let message = match callback.error_description {
+                Some(description) => format!("{}: {}", error, description),
+                None => error,
+            };
+            return HttpResponse::BadRequest().json(ErrorResponse::new_error(Some(message)));
+        }
+        let (Some(code), Some(state)) = (callback.code, callback.state) else {
+            return HttpResponse::BadRequest().json(
+                ErrorResponse::new_error(Some("Missing code or state".to_string()))
+            );
+        };
+
+        let Some(binding) = request.cookie(OIDC_BINDING_COOKIE) else {
+            return HttpResponse::BadRequest().json(
+                ErrorResponse::new_error(
+                    Some("Login was not started in this browser".to_string())
+                )
+            );
+        };
+
+        let user = match
+            self.oidc_service.authenticate(provider, &code, &state, binding.value()).await
+        {
+            Ok(user) => user,
+            Err(e) => {
+                return HttpResponse::Unauthorized().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        };
+        let client_info = ClientInfoDTO::from_request(request);
+        match self.auth_service.complete_login(&user, &client_info) {
+            Ok(login) => {
+                return cookies::login_response(login);
+            }
+            Err(e) => {
+                return HttpResponse::Unauthorized().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+}
+
+pub async fn start_oidc_login(
+    oidc_controller: web::Data<OidcController>,
+    path: PathValidator<OidcProviderPathRequest>
+) -> impl Responder {
+    return oidc_controller.start(&path.provider).await;
+}
+
+pub async fn oidc_callback(
+    oidc_controller: web::Data<OidcController>,
+    request: HttpRequest,
+    path: PathValidator<OidcProviderPathRequest>,
+    callback: QueryValidator<OidcCallbackRequest>
+) -> impl Responder {
+    return oidc_controller.callback(request, &path.provider, callback.into_inner()).await;
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use actix_web::{ web, HttpMessage, HttpRequest, HttpResponse, Responder };
+
+use crate::{
+    infra::{
+        domain::user::UserDTO,
+        http::{
+            requests::{ auth_request::TwoFactorCodeRequest, JsonValidator },
+            resources::ErrorResponse,
+        },
+    },
+    services::two_factor_service::TwoFactorService,
+};
+
+#[derive(Clone)]
+pub struct TwoFactorController {
+    two_factor_service: Arc<TwoFactorService>,
+}
+
+impl TwoFactorController {
+    pub fn new(two_factor_service: Arc<TwoFactorService>) -> TwoFactorController {
+        return TwoFactorController { two_factor_service };
+    }
+
+    async fn enroll(&self, request: HttpRequest) -> impl Responder {
+        if let Some(user) = request.extensions().get::<UserDTO>() {
+            match self.two_factor_service.enroll(user) {
+                Ok(enrollment) => {
+                    return HttpResponse::Ok().json(enrollment);
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Forbidden().json("Not authenticated");
+    }
+
+    async fn confirm(
+        &self,
+        request: HttpRequest,
+        confirm: JsonValidator<TwoFactorCodeRequest>
+    ) -> impl Responder {
+        if let Some(user) = request.extensions().get::<UserDTO>() {
+            match self.two_factor_service.confirm(user, &confirm.code) {
+                Ok(recovery_codes) => {
+                    return HttpResponse::Ok().json(recovery_codes);
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Forbidden().json("Not authenticated");
+    }
+
+    async fn disable(
+        &self,
+        request: HttpRequest,
+        disable: JsonValidator<TwoFactorCodeRequest>
+    ) -> impl Responder {
+        if let Some(user) = request.extensions().get::<UserDTO>() {
+            match self.two_factor_service.disable(user, &disable.code) {
+                Ok(_) => {
+                    return HttpResponse::Ok().finish();
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Forbidden().json("Not authenticated");
+    }
+}
+
+pub async fn enroll(
+    two_factor_controller: web::Data<TwoFactorController>,
+    request: HttpRequest
+) -> impl Responder {
+    return two_factor_controller.enroll(request).await;
+}
+
+pub async fn confirm(
+    two_factor_controller: web::Data<TwoFactorController>,
+    request: HttpRequest,
+    confirm: JsonValidator<TwoFactorCodeRequest>
+) -> impl Responder {
+    return two_factor_controller.confirm(request, confirm).await;
+}
+
+pub async fn disable(
+    two_factor_controller: web::Data<TwoFactorController>,
+    request: HttpRequest,
+    disable: JsonValidator<TwoFactorCodeRequest>
+) -> impl Responder {
+    return two_factor_controller.disable(request, disable).await;
+}
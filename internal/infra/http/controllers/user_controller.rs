@@ -1,37 +1,80 @@
 use std::sync::Arc;
 
-use actix_web::{ web, HttpMessage, HttpRequest, HttpResponse, Responder };
+use actix_web::{ http::header, web, HttpMessage, HttpRequest, HttpResponse, Responder };
 
 use crate::{
     infra::{
         domain::user::UserDTO,
         http::{
-            requests::{ user_request::UserUpdateRequest, JsonValidator },
-            resources::{ user_resource::UserResponse, BasedListResponse, ErrorResponse },
+            requests::{
+                user_request::{ ChangePasswordRequest, UserListRequest, UserUpdateRequest },
+                JsonValidator,
+                QueryValidator,
+            },
+            resources::{
+                login_event_resource::LoginEventResponse,
+                user_resource::UserResponse,
+                BasedListResponse,
+                ErrorResponse,
+            },
         },
     },
-    services::user_service::UserService,
+    services::{
+        auth_service::Claims,
+        login_event_service::LoginEventService,
+        user_service::{ UserService, UserServiceError },
+    },
 };
 
 #[derive(Clone)]
 pub struct UserController {
     user_service: Arc<UserService>,
+    login_event_service: Arc<LoginEventService>,
 }
 
 impl UserController {
-    pub fn new(user_service: Arc<UserService>) -> UserController {
-        return UserController { user_service };
+    pub fn new(
+        user_service: Arc<UserService>,
+        login_event_service: Arc<LoginEventService>
+    ) -> UserController {
+        return UserController { user_service, login_event_service };
     }
 
-    async fn find_all(&self) -> impl Responder {
-        match self.user_service.find_all() {
-            Ok(users) => {
-                let response = BasedListResponse {
-                    data: UserResponse::dtos_to_response(users),
-                    total: 0,
-                    page: 0,
-                };
-                return HttpResponse::Ok().json(response);
+    async fn find_all(
+        &self,
+        request: HttpRequest,
+        query: QueryValidator<UserListRequest>
+    ) -> impl Responder {
+        let response = match query.to_cursor_page() {
+            Some(page) =>
+                self.user_service
+                    .find_cursor_page(&query.to_filter(), page)
+                    .map(|users| {
+                        return BasedListResponse::from_cursor_page(
+                            UserResponse::dtos_to_response(users.data),
+                            page,
+                            users.next,
+                            users.prev
+                        );
+                    }),
+            None => {
+                let page = query.to_page();
+                self.user_service
+                    .find_page(&query.to_filter(), &query.to_sort(), page)
+                    .map(|users| {
+                        return BasedListResponse::from_page(
+                            UserResponse::dtos_to_response(users.data),
+                            users.total,
+                            page
+                        );
+                    })
+            }
+        };
+        match response {
+            Ok(response) => {
+                return HttpResponse::Ok()
+                    .insert_header((header::LINK, response.link_header(&request)))
+                    .json(response);
             }
             Err(e) => {
                 return HttpResponse::BadRequest().json(
@@ -68,6 +111,47 @@ impl UserController {
         return HttpResponse::Forbidden().json("Not authenticated");
     }
 
+    async fn change_password(
+        &self,
+        request: HttpRequest,
+        change: JsonValidator<ChangePasswordRequest>
+    ) -> impl Responder {
+        let claims = request.extensions().get::<Claims>().cloned();
+        let mut extensions = request.extensions_mut();
+        if let (Some(user), Some(claims)) = (extensions.get_mut::<UserDTO>(), claims) {
+            match self.user_service.change_password(user, claims.uuid, change.into_inner()) {
+                Ok(_) => {
+                    return HttpResponse::Ok().finish().map_into_boxed_body();
+                }
+                Err(UserServiceError::PasswordPolicyError(errors)) => {
+                    return HttpResponse::BadRequest().json(ErrorResponse::from(errors));
+                }
+                Err(e) => {
+                    return HttpResponse::BadRequest().json(
+                        ErrorResponse::new_error(Some(e.to_string()))
+                    );
+                }
+            }
+        }
+        return HttpResponse::Forbidden().json("Not authenticated");
+    }
+
+    async fn find_logins(&self, request: HttpRequest) -> impl Responder {
+        let Some(user) = request.extensions().get::<UserDTO>().cloned() else {
+            return HttpResponse::Forbidden().json("Not authenticated");
+        };
+        match self.login_event_service.find_by_user_id(Arc::new(user.id.unwrap())) {
+            Ok(events) => {
+                return HttpResponse::Ok().json(LoginEventResponse::models_to_response(&events));
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().json(
+                    ErrorResponse::new_error(Some(e.to_string()))
+                );
+            }
+        }
+    }
+
     async fn delete(&self, request: HttpRequest) -> impl Responder {
         if let Some(user) = request.extensions_mut().get::<UserDTO>() {
             match self.user_service.delete(user) {
@@ -86,8 +170,12 @@ impl UserController {
 }
 
 // HANDLERS USER ROUTE
-pub async fn find_all(user_controller: web::Data<UserController>) -> impl Responder {
-    return user_controller.find_all().await;
+pub async fn find_all(
+    user_controller: web::Data<UserController>,
+    request: HttpRequest,
+    query: QueryValidator<UserListRequest>
+) -> impl Responder {
+    return user_controller.find_all(request, query).await;
 }
 
 pub async fn find_me(
@@ -105,6 +193,21 @@ pub async fn update(
     return user_controller.update(request, update_data).await;
 }
 
+pub async fn change_password(
+    user_controller: web::Data<UserController>,
+    request: HttpRequest,
+    change: JsonValidator<ChangePasswordRequest>
+) -> impl Responder {
+    return user_controller.change_password(request, change).await;
+}
+
+pub async fn find_logins(
+    user_controller: web::Data<UserController>,
+    request: HttpRequest
+) -> impl Responder {
+    return user_controller.find_logins(request).await;
+}
+
 pub async fn delete(
     user_controller: web::Data<UserController>,
     request: HttpRequest
@@ -0,0 +1,170 @@
+//! Auth cookies for browser clients, enabled with `auth_cookie_mode`.
+//!
+//! Access and refresh tokens are kept in HttpOnly cookies, so scripts can not read them.
+//! Requests authenticated with the cookie are protected with double-submit CSRF validation:
+//! unsafe methods must repeat the value of the readable `csrf_token` cookie in `X-CSRF-Token`.
+use actix_web::{
+    cookie::{ time::Duration, Cookie, SameSite },
+    http::Method,
+    HttpRequest,
+    HttpResponse,
+    HttpResponseBuilder,
+};
+use config::{ CookieSameSite, CONFIGURATION };
+
+use crate::{
+    infra::domain::{ session::AuthTokensDTO, user::{ AuthenticatedUserDTO, LoginDTO } },
+    services::generate_token,
+};
+
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+pub const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+/// Ties an OpenID Connect login to the browser which started it, set in every mode.
+pub const OIDC_BINDING_COOKIE: &str = "oidc_binding";
+
+const OIDC_PATH: &str = "/api/v1/auth/oidc";
+
+/// Refresh token is sent only to the auth endpoints, which need it for refresh and logout.
+const REFRESH_TOKEN_PATH: &str = "/api/v1/auth";
+
+pub fn is_enabled() -> bool {
+    return CONFIGURATION.auth_cookie_mode;
+}
+
+/// Responds with the authenticated user, moving tokens from the body into cookies
+/// when cookie mode is enabled.
+pub fn authenticated_response(
+    mut builder: HttpResponseBuilder,
+    mut user: AuthenticatedUserDTO
+) -> HttpResponse {
+    if is_enabled() {
+        if let (Some(token), Some(refresh_token)) = (user.token.take(), user.refresh_token.take()) {
+            set_auth_cookies(&mut builder, &token, &refresh_token);
+        }
+    }
+    return builder.json(user);
+}
+
+pub fn login_response(login: LoginDTO) -> HttpResponse {
+    match login {
+        LoginDTO::Authenticated(user) => authenticated_response(HttpResponse::Ok(), user),
+        LoginDTO::TwoFactorRequired(challenge) => HttpResponse::Ok().json(challenge),
+    }
+}
+
+pub fn tokens_response(tokens: AuthTokensDTO) -> HttpResponse {
+    if is_enabled() {
+        let mut builder = HttpResponse::Ok();
+        set_auth_cookies(&mut builder, &tokens.token, &tokens.refresh_token);
+        return builder.finish();
+    }
+    return HttpResponse::Ok().json(tokens);
+}
+
+/// Expires every auth cookie on the client.
+pub fn clear_auth_cookies(builder: &mut HttpResponseBuilder) {
+    for (name, path, http_only) in [
+        (ACCESS_TOKEN_COOKIE, "/", true),
+        (REFRESH_TOKEN_COOKIE, REFRESH_TOKEN_PATH, true),
+        (CSRF_COOKIE, "/", false),
+    ] {
+        let mut cookie = build_cookie(name, String::new(), path, http_only, 0);
+        cookie.make_removal();
+        builder.cookie(cookie);
+    }
+}
+
+/// `Lax` lets the cookie come along with the top level redirect back from the provider.
+pub fn set_oidc_binding_cookie(builder: &mut HttpResponseBuilder, binding: &str) {
+    let mut cookie = build_cookie(
+        OIDC_BINDING_COOKIE,
+        binding.to_string(),
+        OIDC_PATH,
+        true,
+        CONFIGURATION.oidc_state_ttl as i64
+    );
+    cookie.set_same_site(SameSite::Lax);
+    builder.cookie(cookie);
+}
+
+pub fn clear_oidc_binding_cookie(response: &mut HttpResponse) {
+    let mut cookie = build_cookie(OIDC_BINDING_COOKIE, String::new(), OIDC_PATH, true, 0);
+    cookie.set_same_site(SameSite::Lax);
+    cookie.make_removal();
+    // Adding a well formed cookie can not fail.
+    let _ = response.add_cookie(&cookie);
+}
+
+/// Requests with safe methods pass, others need `X-CSRF-Token` equal to the `csrf_token` cookie.
+pub fn is_csrf_valid(request: &HttpRequest) -> bool {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return true;
+    }
+    let Some(cookie) = request.cookie(CSRF_COOKIE) else {
+        return false;
+    };
+    let Some(header) = request.headers().get(CSRF_HEADER) else {
+        return false;
+    };
+    if cookie.value().is_empty() {
+        return false;
+    }
+    return constant_time_eq(cookie.value().as_bytes(), header.as_bytes());
+}
+
+fn set_auth_cookies(builder: &mut HttpResponseBuilder, token: &str, refresh_token: &str) {
+    let refresh_ttl = CONFIGURATION.refresh_token_ttl as i64;
+    builder
+        .cookie(
+            build_cookie(
+                ACCESS_TOKEN_COOKIE,
+                token.to_string(),
+                "/",
+                true,
+                CONFIGURATION.jwt_ttl as i64
+            )
+        )
+        .cookie(
+            build_cookie(
+                REFRESH_TOKEN_COOKIE,
+                refresh_token.to_string(),
+                REFRESH_TOKEN_PATH,
+                true,
+                refresh_ttl
+            )
+        )
+        .cookie(build_cookie(CSRF_COOKIE, generate_token(), "/", false, refresh_ttl));
+}
+
+fn build_cookie(
+    name: &'static str,
+    value: String,
+    path: &'static str,
+    http_only: bool,
+    max_age: i64
+) -> Cookie<'static> {
+    let same_site = match CONFIGURATION.auth_cookie_same_site {
+        CookieSameSite::Strict => SameSite::Strict,
+        CookieSameSite::Lax => SameSite::Lax,
+        CookieSameSite::None => SameSite::None,
+    };
+    return Cookie::build(name, value)
+        .path(path)
+        .http_only(http_only)
+        .secure(true)
+        .same_site(same_site)
+        .max_age(Duration::seconds(max_age))
+        .finish();
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    return left
+        .iter()
+        .zip(right)
+        .fold(0u8, |difference, (a, b)| difference | (a ^ b)) == 0;
+}
@@ -8,65 +8,160 @@ use actix_web::{
     HttpMessage,
     HttpResponse,
 };
-use config::CONFIGURATION;
-use jsonwebtoken::{ decode, DecodingKey, Validation };
 
-use crate::services::{ auth_service::{ AuthService, Claims }, user_service::UserService };
+use crate::{
+    infra::{
+        domain::impersonation::ImpersonationDTO,
+        http::{ cookies::{ self, ACCESS_TOKEN_COOKIE }, resources::ErrorResponse },
+    },
+    services::{
+        api_key_service::ApiKeyService,
+        auth_service::AuthService,
+        role_service::RoleService,
+        user_service::UserService,
+    },
+};
+
+const API_KEY_HEADER: &str = "X-API-Key";
 
+/// Authenticates requests with `Authorization: Bearer <jwt>`, `Authorization: ApiKey <key>`
+/// or `X-API-Key: <key>`. Inserts `UserDTO` and `UserRolesDTO` into request extensions,
+/// `Claims` are inserted only for JWT. API keys are rejected unless `allow_api_keys` is set,
+/// which is done only for routes guarded by a permission the key can be scoped to.
+/// In cookie mode the JWT is also read from the `access_token` cookie, requests authenticated
+/// this way must pass CSRF validation. Impersonation tokens additionally insert `ImpersonationDTO`
+/// with the id of the real actor.
 pub async fn auth_middleware<B>(
     user_service: Arc<UserService>,
     auth_service: Arc<AuthService>,
+    role_service: Arc<RoleService>,
+    api_key_service: Arc<ApiKeyService>,
+    allow_api_keys: bool,
     req: ServiceRequest,
     next: Next<B>
 ) -> Result<ServiceResponse<BoxBody>, Error>
     where B: MessageBody + 'static
 {
-    let auth_header = req.headers().get("Authorization");
-    if let Some(auth_header) = auth_header {
-        let token_str = auth_header.to_str().unwrap_or("").replace("Bearer ", "");
-        let validation = Validation::default();
-        let token_data = decode::<Claims>(
-            &token_str,
-            &DecodingKey::from_secret(CONFIGURATION.jwt_secret.as_ref()),
-            &validation
-        );
+    let auth_header = req
+        .headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .map(str::to_owned);
+    let api_key = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|header| header.to_str().ok())
+        .map(str::to_owned)
+        .or_else(|| {
+            auth_header
+                .as_deref()
+                .and_then(|header| header.strip_prefix("ApiKey "))
+                .map(str::to_owned)
+        });
 
-        match token_data {
-            Ok(data) => {
-                let claims = data.claims;
-                if auth_service.check(claims.clone()) {
-                    match user_service.find_by_id(claims.user_id.clone()) {
-                        Ok(user) => {
-                            req.extensions_mut().insert(user);
-                            req.extensions_mut().insert(claims.clone());
-                            let res = next.call(req).await?;
-                            return Ok(res.map_into_boxed_body());
-                        }
-                        Err(e) => {
-                            return Ok(
-                                req.into_response(
-                                    HttpResponse::BadRequest()
-                                        .json(e.to_string())
-                                        .map_into_boxed_body()
-                                )
-                            );
-                        }
-                    }
-                } else {
+    let (user_id, claims, scopes) = if let Some(api_key) = api_key {
+        if !allow_api_keys {
+            return Ok(
+                req.into_response(
+                    HttpResponse::Forbidden()
+                        .json(
+                            ErrorResponse::new_error(
+                                Some("API keys are not allowed on this route".to_string())
+                            )
+                        )
+                        .map_into_boxed_body()
+                )
+            );
+        }
+        match api_key_service.authenticate(&api_key) {
+            Ok(api_key) => (Arc::new(api_key.user_id), None, Some(api_key.scopes)),
+            Err(_) => {
+                return Ok(
+                    req.into_response(HttpResponse::Unauthorized().finish().map_into_boxed_body())
+                );
+            }
+        }
+    } else {
+        let token = match auth_header {
+            Some(auth_header) => auth_header.replace("Bearer ", ""),
+            None => {
+                let cookie = req
+                    .cookie(ACCESS_TOKEN_COOKIE)
+                    .filter(|_| cookies::is_enabled())
+                    .map(|cookie| cookie.value().to_owned());
+                let Some(token) = cookie else {
                     return Ok(
                         req.into_response(
                             HttpResponse::Unauthorized().finish().map_into_boxed_body()
                         )
                     );
+                };
+                if !cookies::is_csrf_valid(req.request()) {
+                    return Ok(
+                        req.into_response(
+                            HttpResponse::Forbidden()
+                                .json(
+                                    ErrorResponse::new_error(
+                                        Some("Invalid CSRF token".to_string())
+                                    )
+                                )
+                                .map_into_boxed_body()
+                        )
+                    );
                 }
+                token
             }
-            Err(_) => {
+        };
+        match auth_service.decode_claims(&token) {
+            Ok(claims) if auth_service.check(claims.clone()) => {
+                (claims.user_id.clone(), Some(claims), None)
+            }
+            _ => {
                 return Ok(
                     req.into_response(HttpResponse::Unauthorized().finish().map_into_boxed_body())
                 );
             }
         }
-    } else {
-        return Ok(req.into_response(HttpResponse::Unauthorized().finish().map_into_boxed_body()));
+    };
+
+    match user_service.find_by_id(user_id.clone()) {
+        Ok(user) => {
+            let roles = match role_service.find_user_roles(user_id) {
+                Ok(roles) => roles,
+                Err(e) => {
+                    return Ok(
+                        req.into_response(
+                            HttpResponse::InternalServerError()
+                                .json(e.to_string())
+                                .map_into_boxed_body()
+                        )
+                    );
+                }
+            };
+            let roles = match scopes {
+                Some(scopes) => roles.scoped(&scopes),
+                None => roles,
+            };
+            req.extensions_mut().insert(user);
+            req.extensions_mut().insert(roles);
+            if let Some(claims) = claims {
+                if let Some(actor_id) = claims.actor_id.clone() {
+                    req.extensions_mut().insert(ImpersonationDTO {
+                        actor_id,
+                        user_id: claims.user_id.clone(),
+                    });
+                }
+                req.extensions_mut().insert(claims);
+            }
+            let res = next.call(req).await?;
+            return Ok(res.map_into_boxed_body());
+        }
+        Err(e) => {
+            return Ok(
+                req.into_response(
+                    HttpResponse::BadRequest().json(e.to_string()).map_into_boxed_body()
+                )
+            );
+        }
     }
 }
@@ -0,0 +1,31 @@
+use actix_web::{
+    body::{ BoxBody, MessageBody },
+    dev::{ ServiceRequest, ServiceResponse },
+    middleware::Next,
+    Error,
+    HttpMessage,
+    HttpResponse,
+};
+
+use crate::infra::{ domain::impersonation::ImpersonationDTO, http::resources::ErrorResponse };
+
+/// Rejects requests made with an impersonation token, guards destructive operations
+/// like deleting the account. Must be wrapped inside of `auth_middleware`.
+pub async fn not_impersonating_middleware<B>(
+    req: ServiceRequest,
+    next: Next<B>
+) -> Result<ServiceResponse<BoxBody>, Error>
+    where B: MessageBody + 'static
+{
+    if req.extensions().get::<ImpersonationDTO>().is_none() {
+        let res = next.call(req).await?;
+        return Ok(res.map_into_boxed_body());
+    }
+    return Ok(
+        req.into_response(
+            HttpResponse::Forbidden().json(
+                ErrorResponse::new_error(Some("Not allowed while impersonating".to_string()))
+            )
+        )
+    );
+}
@@ -3,8 +3,11 @@ use std::sync::Arc;
 use serde::Serialize;
 
 pub mod auth_middleware;
+pub mod impersonation_middleware;
 pub mod is_owner_middleware;
 pub mod path_object_middleware;
+pub mod permission_middleware;
+pub mod verified_middleware;
 
 pub trait Userable {
     fn get_user_id(&self) -> Arc<i32>;
@@ -0,0 +1,36 @@
+use actix_web::{
+    body::{ BoxBody, MessageBody },
+    dev::{ ServiceRequest, ServiceResponse },
+    middleware::Next,
+    Error,
+    HttpMessage,
+    HttpResponse,
+};
+
+use crate::infra::{ domain::role::UserRolesDTO, http::resources::ErrorResponse };
+
+/// Rejects users without the permission. Must be wrapped inside of `auth_middleware`.
+pub async fn permission_middleware<B>(
+    permission: &'static str,
+    req: ServiceRequest,
+    next: Next<B>
+) -> Result<ServiceResponse<BoxBody>, Error>
+    where B: MessageBody + 'static
+{
+    let has_permission = req
+        .extensions()
+        .get::<UserRolesDTO>()
+        .map(|roles| roles.has_permission(permission))
+        .unwrap_or(false);
+    if has_permission {
+        let res = next.call(req).await?;
+        return Ok(res.map_into_boxed_body());
+    }
+    return Ok(
+        req.into_response(
+            HttpResponse::Forbidden().json(
+                ErrorResponse::new_error(Some(format!("Missing permission [{}]", permission)))
+            )
+        )
+    );
+}
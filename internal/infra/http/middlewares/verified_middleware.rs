@@ -0,0 +1,36 @@
+use actix_web::{
+    body::{ BoxBody, MessageBody },
+    dev::{ ServiceRequest, ServiceResponse },
+    middleware::Next,
+    Error,
+    HttpMessage,
+    HttpResponse,
+};
+use config::{ EmailVerificationPolicy, CONFIGURATION };
+
+use crate::infra::{ domain::user::UserDTO, http::resources::ErrorResponse };
+
+/// Rejects users with unverified email. Must be wrapped inside of `auth_middleware`.
+pub async fn verified_middleware<B>(
+    req: ServiceRequest,
+    next: Next<B>
+) -> Result<ServiceResponse<BoxBody>, Error>
+    where B: MessageBody + 'static
+{
+    let is_verified = req
+        .extensions()
+        .get::<UserDTO>()
+        .map(|user| user.is_email_verified())
+        .unwrap_or(false);
+    if CONFIGURATION.email_verification_policy == EmailVerificationPolicy::Disabled || is_verified {
+        let res = next.call(req).await?;
+        return Ok(res.map_into_boxed_body());
+    }
+    return Ok(
+        req.into_response(
+            HttpResponse::Forbidden().json(
+                ErrorResponse::new_error(Some("Email is not verified".to_string()))
+            )
+        )
+    );
+}
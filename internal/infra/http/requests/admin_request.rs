@@ -0,0 +1,29 @@
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RoleRequest {
+    #[validate(length(min = 1, message = "Role must not be empty"))]
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImpersonationRequest {
+    /// Stored in the audit trail, e.g. a support ticket reference.
+    #[validate(length(min = 1, max = 500, message = "Reason must be 1 to 500 characters long"))]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserPathRequest {
+    #[validate(range(min = 1, message = "User id must be positive"))]
+    pub id: i32,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserRolePathRequest {
+    #[validate(range(min = 1, message = "User id must be positive"))]
+    pub id: i32,
+    #[validate(length(min = 1, message = "Role must not be empty"))]
+    pub role: String,
+}
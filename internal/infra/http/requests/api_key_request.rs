@@ -0,0 +1,20 @@
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters long"))]
+    pub name: String,
+    /// Permissions the key is limited to, they must be a subset of the user's permissions.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Key never expires when not set.
+    #[validate(range(min = 1, max = 3650, message = "Expiry must be between 1 and 3650 days"))]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApiKeyPathRequest {
+    #[validate(range(min = 1, message = "API key id must be positive"))]
+    pub id: i32,
+}
@@ -0,0 +1,90 @@
+use rust_commons::uuid::Uuid;
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RefreshTokenRequest {
+    #[validate(length(min = 1, message = "Refresh token must not be empty"))]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Email must be a valid email address"))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, message = "Reset token must not be empty"))]
+    pub token: String,
+    /// Checked by `PasswordPolicy`.
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MagicLinkRequest {
+    #[validate(email(message = "Email must be a valid email address"))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConsumeMagicLinkRequest {
+    #[validate(length(min = 1, message = "Login token must not be empty"))]
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyEmailRequest {
+    #[validate(length(min = 1, message = "Verification token must not be empty"))]
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResendVerificationRequest {
+    #[validate(email(message = "Email must be a valid email address"))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCodeRequest {
+    #[validate(length(min = 1, message = "Code must not be empty"))]
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorVerifyRequest {
+    #[validate(length(min = 1, message = "Challenge token must not be empty"))]
+    pub challenge_token: String,
+    #[validate(length(min = 1, message = "Code must not be empty"))]
+    pub code: String,
+}
+
+/// Query parameters the identity provider redirects back with.
+#[derive(Debug, Deserialize, Validate)]
+pub struct OidcCallbackRequest {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Form parameters of RFC 7662 token introspection.
+#[derive(Debug, Deserialize, Validate)]
+pub struct IntrospectionRequest {
+    #[validate(length(min = 1, message = "Token must not be empty"))]
+    pub token: String,
+    /// Accepted for compatibility, the token type is detected from the token itself.
+    pub token_type_hint: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SessionPathRequest {
+    pub uuid: Uuid,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct OidcProviderPathRequest {
+    #[validate(length(min = 1, message = "Provider must not be empty"))]
+    pub provider: String,
+}
@@ -2,11 +2,10 @@ use std::collections::HashMap;
 
 use actix_web::http::StatusCode;
 use actix_web::{ HttpResponse, ResponseError };
-use config::log::debug;
 use thiserror::Error;
 use validator::{ ValidationError, ValidationErrors, ValidationErrorsKind };
 
-use crate::infra::http::resources::ErrorResponse;
+use crate::infra::http::resources::{ ErrorResponse, FieldErrorResponse };
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -51,64 +50,196 @@ impl ResponseError for Error {
     }
 }
 
-#[inline]
-fn flatten_errors(errors: &ValidationErrors) -> HashMap<String, Vec<String>> {
-    let mut mapped_errors: HashMap<String, Vec<String>> = HashMap::new();
-    for error in errors.errors() {
-        match error.1 {
-            ValidationErrorsKind::Field(field_errors) => {
-                mapped_errors.insert(
-                    error.0.to_string(),
-                    field_errors
-                        .iter()
-                        .map(|val_error| val_error.message.as_deref().unwrap().to_string())
-                        .collect()
-                );
-            }
-            ValidationErrorsKind::List(list_error) => {
-                debug!("{:?}", list_error);
-            }
-            ValidationErrorsKind::Struct(struct_errors) => {
-                debug!("{:?}", struct_errors);
-            }
-        }
+/// Groups errors by path of the field, nested structs are joined with `.` and list items are
+/// indexed, e.g. `addresses[1].zip`.
+fn flatten_errors(errors: &ValidationErrors) -> HashMap<String, Vec<FieldErrorResponse>> {
+    let mut mapped_errors: HashMap<String, Vec<FieldErrorResponse>> = HashMap::new();
+    for (path, error) in collect_errors(errors, None) {
+        mapped_errors.entry(path).or_default().push(field_error_response(error));
     }
     return mapped_errors;
 }
 
-#[inline]
-fn _flatten_errors(
-    errors: &ValidationErrors,
-    path: Option<String>,
-    indent: Option<u16>
-) -> Vec<(u16, String, &ValidationError)> {
-    errors
+fn collect_errors<'a>(
+    errors: &'a ValidationErrors,
+    path: Option<&str>
+) -> Vec<(String, &'a ValidationError)> {
+    return errors
         .errors()
         .iter()
-        .flat_map(|(&field, err)| {
-            let indent = indent.unwrap_or(0);
-            let actual_path = path
-                .as_ref()
-                .map(|path| [path.as_str(), field].join("."))
-                .unwrap_or_else(|| field.to_owned());
-            match err {
-                ValidationErrorsKind::Field(field_errors) =>
-                    field_errors
+        .flat_map(|(&field, kind)| {
+            let field_path = match path {
+                Some(path) => format!("{}.{}", path, field),
+                None => field.to_string(),
+            };
+            match kind {
+                ValidationErrorsKind::Field(field_errors) => {
+                    return field_errors
                         .iter()
-                        .map(|error| (indent, actual_path.clone(), error))
-                        .collect::<Vec<_>>(),
-                ValidationErrorsKind::List(list_error) =>
-                    list_error
+                        .map(|error| (field_path.clone(), error))
+                        .collect::<Vec<_>>();
+                }
+                ValidationErrorsKind::List(list_errors) => {
+                    return list_errors
                         .iter()
                         .flat_map(|(index, errors)| {
-                            let actual_path = format!("{}[{}]", actual_path.as_str(), index);
-                            _flatten_errors(errors, Some(actual_path), Some(indent + 1))
+                            let item_path = format!("{}[{}]", field_path, index);
+                            return collect_errors(errors, Some(&item_path));
                         })
-                        .collect::<Vec<_>>(),
+                        .collect::<Vec<_>>();
+                }
                 ValidationErrorsKind::Struct(struct_errors) => {
-                    _flatten_errors(struct_errors, Some(actual_path), Some(indent + 1))
+                    return collect_errors(struct_errors, Some(&field_path));
                 }
             }
         })
-        .collect::<Vec<_>>()
+        .collect();
+}
+
+fn field_error_response(error: &ValidationError) -> FieldErrorResponse {
+    return FieldErrorResponse {
+        code: error.code.to_string(),
+        message: error.message
+            .as_ref()
+            .map(|message| message.to_string())
+            .unwrap_or_else(|| default_message(error)),
+        // The rejected value is left out, so that passwords are not sent back.
+        params: error.params
+            .iter()
+            .filter(|(name, _)| name.as_ref() != "value")
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect(),
+    };
+}
+
+/// Message for validators declared without one.
+fn default_message(error: &ValidationError) -> String {
+    let param = |name: &str| {
+        return error.params.get(name).map(|value| {
+            match value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            }
+        });
+    };
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("length", _, _) if param("equal").is_some() => {
+            return format!("Length must be exactly {}", param("equal").unwrap_or_default());
+        }
+        ("length" | "range", Some(min), Some(max)) => {
+            return format!("Must be between {} and {}", min, max);
+        }
+        ("length", Some(min), None) => {
+            return format!("Length must be at least {}", min);
+        }
+        ("length", None, Some(max)) => {
+            return format!("Length must be at most {}", max);
+        }
+        ("range", Some(min), None) => {
+            return format!("Must be at least {}", min);
+        }
+        ("range", None, Some(max)) => {
+            return format!("Must be at most {}", max);
+        }
+        ("email", _, _) => {
+            return "Must be a valid email address".to_string();
+        }
+        ("url", _, _) => {
+            return "Must be a valid URL".to_string();
+        }
+        ("required", _, _) => {
+            return "Is required".to_string();
+        }
+        ("must_match", _, _) => {
+            return format!("Must match {}", param("other").unwrap_or_default());
+        }
+        ("contains", _, _) => {
+            return format!("Must contain {}", param("pattern").unwrap_or_default());
+        }
+        ("does_not_contain", _, _) => {
+            return format!("Must not contain {}", param("pattern").unwrap_or_default());
+        }
+        ("regex", _, _) => {
+            return "Has invalid format".to_string();
+        }
+        (code, _, _) => {
+            return format!("Failed {} validation", code);
+        }
+    }
+}
+
+/// Validation done outside of the extractor, e.g. by `PasswordPolicy`, is reported the same way.
+impl From<ValidationErrors> for ErrorResponse {
+    fn from(errors: ValidationErrors) -> Self {
+        return ErrorResponse::new_field_errors(Some(flatten_errors(&errors)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+
+    use super::flatten_errors;
+
+    #[derive(Validate)]
+    struct Address {
+        #[validate(length(equal = 5))]
+        zip: String,
+    }
+
+    #[derive(Validate)]
+    struct Contact {
+        #[validate(email)]
+        email: String,
+    }
+
+    #[derive(Validate)]
+    struct Profile {
+        #[validate(length(min = 1, message = "Name must not be empty"))]
+        name: String,
+        #[validate(nested)]
+        addresses: Vec<Address>,
+        #[validate(nested)]
+        contact: Contact,
+    }
+
+    fn profile() -> Profile {
+        return Profile {
+            name: String::new(),
+            addresses: vec![
+                Address { zip: "12345".to_string() },
+                Address { zip: "123".to_string() }
+            ],
+            contact: Contact { email: "not an email".to_string() },
+        };
+    }
+
+    #[test]
+    fn nested_errors_are_keyed_by_path() {
+        let errors = flatten_errors(&profile().validate().unwrap_err());
+
+        let mut paths: Vec<&str> = errors.keys().map(String::as_str).collect();
+        paths.sort();
+        assert_eq!(paths, ["addresses[1].zip", "contact.email", "name"]);
+        assert_eq!(errors["addresses[1].zip"][0].code, "length");
+        assert_eq!(errors["name"][0].message, "Name must not be empty");
+    }
+
+    #[test]
+    fn missing_message_is_derived_from_code() {
+        let errors = flatten_errors(&profile().validate().unwrap_err());
+
+        assert_eq!(errors["addresses[1].zip"][0].message, "Length must be exactly 5");
+        assert_eq!(errors["contact.email"][0].message, "Must be a valid email address");
+    }
+
+    #[test]
+    fn rejected_value_is_not_returned() {
+        let errors = flatten_errors(&profile().validate().unwrap_err());
+
+        let zip = &errors["addresses[1].zip"][0];
+        assert_eq!(zip.params.get("equal"), Some(&serde_json::json!(5)));
+        assert!(!zip.params.contains_key("value"));
+        assert!(errors["contact.email"][0].params.is_empty());
+    }
 }
@@ -0,0 +1,57 @@
+//! Url encoded form extractor.
+use std::ops::Deref;
+
+use actix_web::{ dev::{ Payload, UrlEncoded }, FromRequest, HttpRequest };
+use futures::future::{ FutureExt, LocalBoxFuture };
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use super::{ error::Error, handle_error, ValidatorConfig };
+
+#[derive(Debug)]
+pub struct FormValidator<T>(pub T);
+
+impl<T> FormValidator<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> AsRef<T> for FormValidator<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Deref for FormValidator<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for FormValidator<T> where T: DeserializeOwned + Validate + 'static {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req2 = req.clone();
+        let limit = req
+            .app_data::<ValidatorConfig>()
+            .map(|c| c.limit)
+            .unwrap_or(4 * 1024 * 1024);
+
+        UrlEncoded::<T>::new(req, payload)
+            .limit(limit)
+            .map(|res| {
+                let data = res.map_err(Error::from)?;
+                data.validate()?;
+                return Ok(FormValidator(data));
+            })
+            .map(move |res| res.map_err(|e| handle_error(e, &req2)))
+            .boxed_local()
+    }
+}
@@ -1,4 +1,4 @@
-//! Json extractor.
+//! Extractors deserializing request data and validating it with `validator::Validate`.
 use core::fmt::Debug;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -14,7 +14,17 @@ use validator::Validate;
 use error::Error;
 
 mod error;
+mod form;
+mod path;
+mod query;
 pub mod user_request;
+pub mod auth_request;
+pub mod admin_request;
+pub mod api_key_request;
+
+pub use form::FormValidator;
+pub use path::PathValidator;
+pub use query::QueryValidator;
 
 #[derive(Debug)]
 pub struct JsonValidator<T>(pub T);
@@ -47,10 +57,10 @@ impl<T> FromRequest for JsonValidator<T> where T: DeserializeOwned + Validate +
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let req2 = req.clone();
-        let (limit, err, ctype) = req
-            .app_data::<JsonConfig>()
-            .map(|c| (c.limit, c.ehandler.clone(), c.content_type.clone()))
-            .unwrap_or((4 * 1024 * 1024, None, None));
+        let (limit, ctype) = req
+            .app_data::<ValidatorConfig>()
+            .map(|c| (c.limit, c.content_type.clone()))
+            .unwrap_or((4 * 1024 * 1024, None));
 
         JsonBody::new(req, payload, ctype.as_deref(), false)
             .limit(limit)
@@ -64,38 +74,23 @@ impl<T> FromRequest for JsonValidator<T> where T: DeserializeOwned + Validate +
                     Err(e) => Err(Error::from(e)),
                 }
             })
-            .map(move |res| {
-                match res {
-                    Ok(data) => Ok(data),
-                    Err(e) => {
-                        debug!(
-                            "Failed to deserialize Json from payload. \
-                         Request path: {}",
-                            req2.path()
-                        );
-                        if let Some(err) = err {
-                            Err((*err)(e, &req2))
-                        } else {
-                            Err(e.into())
-                        }
-                    }
-                }
-            })
+            .map(move |res| res.map_err(|e| handle_error(e, &req2)))
             .boxed_local()
     }
 }
 
 type ErrHandler = Arc<dyn (Fn(Error, &HttpRequest) -> actix_web::Error) + Send + Sync>;
 
+/// Configuration of all validating extractors, register it with `App::app_data`.
 #[derive(Clone)]
-pub struct JsonConfig {
+pub struct ValidatorConfig {
     limit: usize,
     ehandler: Option<ErrHandler>,
     content_type: Option<Arc<dyn (Fn(mime::Mime) -> bool) + Send + Sync>>,
 }
 
-impl JsonConfig {
-    /// Change max size of payload. By default max size is 32Kb
+impl ValidatorConfig {
+    /// Change max size of Json and form payload. By default max size is 4Mb
     pub fn limit(mut self, limit: usize) -> Self {
         self.limit = limit;
         self
@@ -109,7 +104,7 @@ impl JsonConfig {
         self
     }
 
-    /// Set predicate for allowed content types
+    /// Set predicate for allowed Json content types
     pub fn content_type<F>(mut self, predicate: F) -> Self
         where F: Fn(mime::Mime) -> bool + Send + Sync + 'static
     {
@@ -118,12 +113,21 @@ impl JsonConfig {
     }
 }
 
-impl Default for JsonConfig {
+impl Default for ValidatorConfig {
     fn default() -> Self {
-        JsonConfig {
+        ValidatorConfig {
             limit: 4 * 1024 * 1024,
             ehandler: None,
             content_type: None,
         }
     }
 }
+
+/// Renders the error with the handler from `ValidatorConfig` when there is one.
+fn handle_error(error: Error, req: &HttpRequest) -> actix_web::Error {
+    debug!("Failed to extract request data - {}. Request path: {}", error, req.path());
+    return match req.app_data::<ValidatorConfig>().and_then(|c| c.ehandler.as_ref()) {
+        Some(handler) => (*handler)(error, req),
+        None => error.into(),
+    };
+}
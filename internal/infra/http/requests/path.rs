@@ -0,0 +1,53 @@
+//! Path parameters extractor.
+use std::ops::Deref;
+
+use actix_web::{ dev::Payload, FromRequest, HttpRequest };
+use futures::future::{ ready, Ready };
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use super::{ error::{ DeserializeErrors, Error }, handle_error };
+
+/// Deserializes dynamic segments of the route, e.g. `{id}`, into a struct with the same
+/// field names.
+#[derive(Debug)]
+pub struct PathValidator<T>(pub T);
+
+impl<T> PathValidator<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> AsRef<T> for PathValidator<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Deref for PathValidator<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for PathValidator<T> where T: DeserializeOwned + Validate {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let result = req
+            .match_info()
+            .load::<T>()
+            .map_err(|e| Error::Deserialize(DeserializeErrors::DeserializePath(e)))
+            .and_then(|data| {
+                data.validate()?;
+                return Ok(PathValidator(data));
+            });
+        return ready(result.map_err(|e| handle_error(e, req)));
+    }
+}
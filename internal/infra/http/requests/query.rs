@@ -0,0 +1,55 @@
+//! Query string extractor.
+use std::ops::Deref;
+
+use actix_web::{ dev::Payload, FromRequest, HttpRequest };
+use futures::future::{ ready, Ready };
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use super::{ error::Error, handle_error };
+
+/// Nested parameters like `filter[name]=...` are deserialized into nested structs.
+const MAX_NESTING_DEPTH: usize = 5;
+
+#[derive(Debug)]
+pub struct QueryValidator<T>(pub T);
+
+impl<T> QueryValidator<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> AsRef<T> for QueryValidator<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Deref for QueryValidator<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for QueryValidator<T> where T: DeserializeOwned + Validate {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        // Brackets come percent encoded from most clients, so the strict mode is off.
+        let result = serde_qs::Config
+            ::new(MAX_NESTING_DEPTH, false)
+            .deserialize_str::<T>(req.query_string())
+            .map_err(Error::from)
+            .and_then(|data| {
+                data.validate()?;
+                return Ok(QueryValidator(data));
+            });
+        return ready(result.map_err(|e| handle_error(e, req)));
+    }
+}
@@ -1,11 +1,27 @@
+use chrono::{ NaiveDate, NaiveTime, TimeDelta };
 use serde::Deserialize;
-use validator::Validate;
+use validator::{ Validate, ValidationError };
+
+use crate::infra::{
+    domain::{
+        pagination::{
+            CursorPageRequestDTO,
+            PageRequestDTO,
+            SortDTO,
+            DEFAULT_PER_PAGE,
+            MAX_PER_PAGE,
+        },
+        user::{ UserFilterDTO, UserSortField },
+    },
+    http::resources::Cursor,
+};
+use crate::mail::templates::MailTemplates;
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct UserRequest {
     #[validate(length(min = 4, message = "Name must be at least 4 characters long"))]
     pub name: String,
-    #[validate(length(min = 4, message = "Password must be at least 4 characters long"))]
+    /// Checked by `PasswordPolicy`.
     pub password: String,
     #[validate(email(message = "Email must be a valid email address"))]
     pub email: String,
@@ -18,6 +34,17 @@ pub struct UserUpdateRequest {
     pub name: Option<String>,
     #[validate(email(message = "Email must be a valid email address"))]
     pub email: Option<String>,
+    /// Language of emails sent to the user.
+    #[validate(custom(function = "validate_locale"))]
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, message = "Current password must not be empty"))]
+    pub current_password: String,
+    /// Checked by `PasswordPolicy`.
+    pub new_password: String,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -27,3 +54,162 @@ pub struct AuthRequest {
     #[validate(length(min = 4, message = "Password must be at least 4 characters long"))]
     pub password: String,
 }
+
+/// Query of `GET /user/all`. Sending `cursor` (an opaque cursor, or `first`/`last` to start from
+/// an end of the list) switches from page numbers to cursor pagination.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_user_list"))]
+pub struct UserListRequest {
+    #[validate(range(min = 1, message = "Page must be at least 1"))]
+    pub page: Option<u32>,
+    #[validate(range(min = 1, max = MAX_PER_PAGE, message = "Page size must be between 1 and 100"))]
+    pub per_page: Option<u32>,
+    #[validate(custom(function = "validate_cursor"))]
+    pub cursor: Option<String>,
+    /// Comma separated fields, `-` prefix sorts descending, e.g. `-created_date,name`.
+    #[validate(custom(function = "validate_user_sort"))]
+    pub sort: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub created_from: Option<NaiveDate>,
+    /// Inclusive.
+    #[validate(custom(function = "validate_created_to"))]
+    pub created_to: Option<NaiveDate>,
+}
+
+impl UserListRequest {
+    pub fn to_page(&self) -> PageRequestDTO {
+        return PageRequestDTO {
+            page: self.page.unwrap_or(1),
+            per_page: self.per_page.unwrap_or(DEFAULT_PER_PAGE),
+        };
+    }
+
+    /// Expects validated request.
+    pub fn to_cursor_page(&self) -> Option<CursorPageRequestDTO> {
+        let cursor = Cursor::decode(self.cursor.as_deref()?)?;
+        let descending = self.sort.as_deref().is_some_and(|sort| sort.trim() == "-created_date");
+        return Some(cursor.to_page_request(descending, self.per_page.unwrap_or(DEFAULT_PER_PAGE)));
+    }
+
+    /// Expects validated request.
+    pub fn to_sort(&self) -> Vec<SortDTO<UserSortField>> {
+        return self.sort
+            .as_deref()
+            .and_then(|sort| SortDTO::parse_list(sort, UserSortField::from_name).ok())
+            .unwrap_or_default();
+    }
+
+    pub fn to_filter(&self) -> UserFilterDTO {
+        return UserFilterDTO {
+            name: self.name.clone().filter(|name| !name.is_empty()),
+            email: self.email.clone().filter(|email| !email.is_empty()),
+            created_from: self.created_from.map(|date| date.and_time(NaiveTime::MIN)),
+            created_before: self.created_to.and_then(|date| {
+                return date.and_time(NaiveTime::MIN).checked_add_signed(TimeDelta::days(1));
+            }),
+        };
+    }
+}
+
+fn validate_user_sort(sort: &str) -> Result<(), ValidationError> {
+    if let Err(message) = SortDTO::parse_list(sort, UserSortField::from_name) {
+        return Err(ValidationError::new("sort").with_message(message.into()));
+    }
+    return Ok(());
+}
+
+fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    if !MailTemplates::supports_locale(locale) {
+        return Err(ValidationError::new("locale").with_message("Unsupported locale".into()));
+    }
+    return Ok(());
+}
+
+/// The filter ends at the start of the next day, which must exist.
+fn validate_created_to(date: &NaiveDate) -> Result<(), ValidationError> {
+    if date.succ_opt().is_none() {
+        return Err(ValidationError::new("range").with_message("Date is out of range".into()));
+    }
+    return Ok(());
+}
+
+fn validate_cursor(cursor: &str) -> Result<(), ValidationError> {
+    if Cursor::decode(cursor).is_none() {
+        return Err(ValidationError::new("cursor").with_message("Invalid cursor".into()));
+    }
+    return Ok(());
+}
+
+fn validate_user_list(request: &UserListRequest) -> Result<(), ValidationError> {
+    if request.cursor.is_none() {
+        return Ok(());
+    }
+    if request.page.is_some() {
+        return Err(
+            ValidationError::new("pagination").with_message(
+                "Page and cursor can not be used together".into()
+            )
+        );
+    }
+    // Cursor positions are taken in `(created_date, id)` order.
+    let sort = request.sort.as_deref().map(str::trim).unwrap_or_default();
+    if !["", "created_date", "-created_date"].contains(&sort) {
+        return Err(
+            ValidationError::new("sort").with_message(
+                "Cursor pagination can be sorted only by created_date".into()
+            )
+        );
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use validator::Validate;
+
+    use super::{ UserListRequest, UserUpdateRequest };
+
+    fn request(created_to: NaiveDate) -> UserListRequest {
+        return UserListRequest {
+            page: None,
+            per_page: None,
+            cursor: None,
+            sort: None,
+            name: None,
+            email: None,
+            created_from: None,
+            created_to: Some(created_to),
+        };
+    }
+
+    #[test]
+    fn created_to_includes_whole_day() {
+        let request = request(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap());
+        assert!(request.validate().is_ok());
+        let created_before = request.to_filter().created_before.unwrap();
+        assert_eq!(created_before, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().into());
+    }
+
+    #[test]
+    fn last_representable_created_to_is_rejected() {
+        let request = request(NaiveDate::MAX);
+        let errors = request.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("created_to"));
+        assert_eq!(request.to_filter().created_before, None);
+    }
+
+    #[test]
+    fn only_supported_locales_are_accepted() {
+        let request = |locale: &str| UserUpdateRequest {
+            name: None,
+            email: None,
+            locale: Some(locale.to_owned()),
+        };
+
+        assert!(request("uk").validate().is_ok());
+        let errors = request("xx").validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("locale"));
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::infra::database::api_key_repository::ApiKey;
+
+#[derive(Clone, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub name: Arc<str>,
+    pub prefix: Arc<str>,
+    pub scopes: Vec<Arc<str>>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Returned once on creation, the key can not be read afterwards.
+#[derive(Clone, Serialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    pub key: Arc<str>,
+}
+
+impl ApiKeyResponse {
+    pub fn model_to_response(api_key: &ApiKey) -> Self {
+        return ApiKeyResponse {
+            id: api_key.id,
+            name: Arc::from(api_key.name.as_str()),
+            prefix: Arc::from(api_key.prefix.as_str()),
+            scopes: api_key.scopes
+                .iter()
+                .map(|scope| Arc::from(scope.as_str()))
+                .collect(),
+            last_used_at: api_key.last_used_at,
+            expires_at: api_key.expires_at,
+            created_at: api_key.created_at,
+        };
+    }
+
+    pub fn models_to_response(api_keys: &[ApiKey]) -> Vec<Self> {
+        return api_keys.iter().map(Self::model_to_response).collect();
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use rust_commons::uuid::Uuid;
+use serde::Serialize;
+
+use crate::infra::database::impersonation_event_repository::ImpersonationEvent;
+
+#[derive(Clone, Serialize)]
+pub struct ImpersonationEventResponse {
+    pub id: i32,
+    pub actor_id: i32,
+    pub user_id: i32,
+    pub session_uuid: Uuid,
+    pub event: Arc<str>,
+    pub reason: Option<Arc<str>>,
+    pub client_ip: Option<Arc<str>>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ImpersonationEventResponse {
+    pub fn model_to_response(event: &ImpersonationEvent) -> Self {
+        return ImpersonationEventResponse {
+            id: event.id,
+            actor_id: event.actor_id,
+            user_id: event.user_id,
+            session_uuid: event.session_uuid,
+            event: Arc::from(event.event.as_str()),
+            reason: event.reason.as_deref().map(Arc::from),
+            client_ip: event.client_ip.as_deref().map(Arc::from),
+            expires_at: event.expires_at,
+            created_at: event.created_at,
+        };
+    }
+
+    pub fn models_to_response(events: &[ImpersonationEvent]) -> Vec<Self> {
+        return events.iter().map(Self::model_to_response).collect();
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::infra::database::login_event_repository::LoginEvent;
+
+#[derive(Clone, Serialize)]
+pub struct LoginEventResponse {
+    pub id: i32,
+    pub outcome: Arc<str>,
+    pub reason: Option<Arc<str>>,
+    pub client_ip: Option<Arc<str>>,
+    pub user_agent: Option<Arc<str>>,
+    pub new_device: bool,
+    pub created_at: NaiveDateTime,
+}
+
+impl LoginEventResponse {
+    pub fn model_to_response(event: &LoginEvent) -> Self {
+        return LoginEventResponse {
+            id: event.id,
+            outcome: Arc::from(event.outcome.as_str()),
+            reason: event.reason.as_deref().map(Arc::from),
+            client_ip: event.client_ip.as_deref().map(Arc::from),
+            user_agent: event.user_agent.as_deref().map(Arc::from),
+            new_device: event.new_device,
+            created_at: event.created_at,
+        };
+    }
+
+    pub fn models_to_response(events: &[LoginEvent]) -> Vec<Self> {
+        return events.iter().map(Self::model_to_response).collect();
+    }
+}
@@ -1,20 +1,196 @@
 use std::collections::HashMap;
 
+use actix_web::HttpRequest;
+use chrono::DateTime;
+use rust_commons::base64::{ engine::general_purpose::URL_SAFE_NO_PAD, Engine };
 use serde::Serialize;
 
+use crate::infra::domain::pagination::{ CursorPageRequestDTO, KeysetDTO, PageRequestDTO };
+
 pub mod user_resource;
+pub mod session_resource;
+pub mod api_key_resource;
+pub mod impersonation_resource;
+pub mod login_event_resource;
 
 #[derive(Serialize, Clone, PartialEq)]
 pub struct BasedListResponse<T> where T: Serialize {
     pub data: Vec<T>,
-    pub total: u64,
-    pub page: u32,
+    /// Not set with cursor pagination, which does not count rows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// Not set with cursor pagination.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub per_page: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
+}
+
+impl<T> BasedListResponse<T> where T: Serialize {
+    pub fn from_page(data: Vec<T>, total: u64, page: PageRequestDTO) -> Self {
+        return BasedListResponse {
+            data,
+            total: Some(total),
+            page: Some(page.page),
+            per_page: page.per_page,
+            next_cursor: None,
+            prev_cursor: None,
+        };
+    }
+
+    pub fn from_cursor_page(
+        data: Vec<T>,
+        page: CursorPageRequestDTO,
+        next: Option<KeysetDTO>,
+        prev: Option<KeysetDTO>
+    ) -> Self {
+        let cursor = |key: KeysetDTO, backward: bool| {
+            return (Cursor { key: Some(key), backward, descending: page.descending }).encode();
+        };
+        return BasedListResponse {
+            data,
+            total: None,
+            page: None,
+            per_page: page.per_page,
+            next_cursor: next.map(|key| cursor(key, false)),
+            prev_cursor: prev.map(|key| cursor(key, true)),
+        };
+    }
+
+    pub fn last_page(&self) -> u32 {
+        let total = self.total.unwrap_or_default();
+        return total.div_ceil(u64::from(self.per_page.max(1))).max(1) as u32;
+    }
+
+    /// `Link` header (RFC 8288) with `first`, `prev`, `next` and `last` pages, or cursors with
+    /// cursor pagination. Other query parameters of the request are kept, so the links repeat
+    /// its filters and sorting.
+    pub fn link_header(&self, request: &HttpRequest) -> String {
+        let params: Vec<(String, String)> = serde_urlencoded
+            ::from_str(request.query_string())
+            .unwrap_or_default();
+        let link = |name: &str, value: String, rel: &str| {
+            let mut link_params: Vec<(&str, String)> = params
+                .iter()
+                .filter(|(param, _)| param != "page" && param != "cursor")
+                .map(|(param, value)| (param.as_str(), value.clone()))
+                .collect();
+            link_params.push((name, value));
+            let query = serde_urlencoded::to_string(link_params).unwrap_or_default();
+            return format!("<{}?{}>; rel=\"{}\"", request.path(), query, rel);
+        };
+        let mut links = Vec::new();
+        match self.page {
+            Some(page) => {
+                let last_page = self.last_page();
+                links.push(link("page", "1".to_string(), "first"));
+                if page > 1 {
+                    links.push(link("page", (page - 1).min(last_page).to_string(), "prev"));
+                }
+                if page < last_page {
+                    links.push(link("page", (page + 1).to_string(), "next"));
+                }
+                links.push(link("page", last_page.to_string(), "last"));
+            }
+            None => {
+                links.push(link("cursor", Cursor::FIRST.encode(), "first"));
+                if let Some(prev_cursor) = &self.prev_cursor {
+                    links.push(link("cursor", prev_cursor.clone(), "prev"));
+                }
+                if let Some(next_cursor) = &self.next_cursor {
+                    links.push(link("cursor", next_cursor.clone(), "next"));
+                }
+                links.push(link("cursor", Cursor::LAST.encode(), "last"));
+            }
+        }
+        return links.join(", ");
+    }
+}
+
+/// Opaque position in a list paginated by `(created_date, id)` together with the direction to
+/// read in from it and the order of the list. Clients only pass back values they received in
+/// `next_cursor` and `prev_cursor`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cursor {
+    /// Beginning of the list, or its end when reading backward, when not set.
+    pub key: Option<KeysetDTO>,
+    pub backward: bool,
+    /// Order the key was taken in. Ends of the list are read in the requested order instead.
+    pub descending: bool,
+}
+
+impl Cursor {
+    pub const FIRST: Cursor = Cursor { key: None, backward: false, descending: false };
+    pub const LAST: Cursor = Cursor { key: None, backward: true, descending: false };
+
+    /// Ends of the list are written as `first` and `last`, so that clients can start from them.
+    pub fn encode(&self) -> String {
+        let Some(key) = self.key else {
+            return (if self.backward { "last" } else { "first" }).to_string();
+        };
+        let direction = if self.backward { "b" } else { "f" };
+        let order = if self.descending { "d" } else { "a" };
+        let micros = key.created_date.and_utc().timestamp_micros();
+        return URL_SAFE_NO_PAD.encode(format!("{}{}{}.{}", direction, order, micros, key.id));
+    }
+
+    pub fn decode(value: &str) -> Option<Cursor> {
+        match value {
+            "first" => {
+                return Some(Cursor::FIRST);
+            }
+            "last" => {
+                return Some(Cursor::LAST);
+            }
+            _ => {}
+        }
+        let value = String::from_utf8(URL_SAFE_NO_PAD.decode(value).ok()?).ok()?;
+        let (backward, key) = match value.strip_prefix('b') {
+            Some(key) => (true, key),
+            None => (false, value.strip_prefix('f')?),
+        };
+        let (descending, key) = match key.strip_prefix('d') {
+            Some(key) => (true, key),
+            None => (false, key.strip_prefix('a')?),
+        };
+        let (micros, id) = key.split_once('.')?;
+        let key = KeysetDTO {
+            created_date: DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc(),
+            id: id.parse().ok()?,
+        };
+        return Some(Cursor { key: Some(key), backward, descending });
+    }
+
+    /// `descending` is the requested order, used only when starting from an end of the list.
+    pub fn to_page_request(&self, descending: bool, per_page: u32) -> CursorPageRequestDTO {
+        return CursorPageRequestDTO {
+            key: self.key,
+            backward: self.backward,
+            descending: if self.key.is_some() { self.descending } else { descending },
+            per_page,
+        };
+    }
+}
+
+/// Validation error of a single field.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FieldErrorResponse {
+    /// Validator that failed, e.g. `length` or `email`.
+    pub code: String,
+    pub message: String,
+    /// Arguments of the validator, e.g. `min` and `max` of `length`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Serialize)]
 pub struct ErrorResponse {
+    /// Keys are paths of the fields, e.g. `addresses[1].zip` for nested payloads.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub field_errors: Option<HashMap<String, Vec<String>>>,
+    pub field_errors: Option<HashMap<String, Vec<FieldErrorResponse>>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
@@ -25,7 +201,115 @@ impl ErrorResponse {
         return ErrorResponse { field_errors: None, error };
     }
 
-    pub fn new_field_errors(field_errors: Option<HashMap<String, Vec<String>>>) -> Self {
+    pub fn new_field_errors(
+        field_errors: Option<HashMap<String, Vec<FieldErrorResponse>>>
+    ) -> Self {
         return ErrorResponse { field_errors, error: None };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use chrono::DateTime;
+
+    use crate::infra::domain::pagination::{ KeysetDTO, PageRequestDTO };
+
+    use super::{ BasedListResponse, Cursor, Engine, URL_SAFE_NO_PAD };
+
+    fn key() -> KeysetDTO {
+        return KeysetDTO {
+            created_date: DateTime::from_timestamp_micros(1_733_000_000_123_456)
+                .unwrap()
+                .naive_utc(),
+            id: 42,
+        };
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        for backward in [false, true] {
+            for descending in [false, true] {
+                let cursor = Cursor { key: Some(key()), backward, descending };
+                assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+            }
+        }
+        assert_eq!(Cursor::decode(&Cursor::FIRST.encode()), Some(Cursor::FIRST));
+        assert_eq!(Cursor::decode(&Cursor::LAST.encode()), Some(Cursor::LAST));
+    }
+
+    #[test]
+    fn invalid_cursor_is_not_decoded() {
+        assert_eq!(Cursor::decode(""), None);
+        assert_eq!(Cursor::decode("not a cursor"), None);
+        assert_eq!(Cursor::decode(&URL_SAFE_NO_PAD.encode("fa1.x")), None);
+        assert_eq!(Cursor::decode(&URL_SAFE_NO_PAD.encode("x1.2")), None);
+    }
+
+    #[test]
+    fn cursor_keeps_its_order() {
+        let cursor = Cursor { key: Some(key()), backward: false, descending: true };
+        assert!(cursor.to_page_request(false, 20).descending);
+        assert!(Cursor::FIRST.to_page_request(true, 20).descending);
+        assert!(!Cursor::LAST.to_page_request(false, 20).descending);
+    }
+
+    #[test]
+    fn page_links_keep_other_parameters() {
+        let request = TestRequest::with_uri("/api/v1/user/all?name=jo&page=2&per_page=10")
+            .to_http_request();
+        let response = BasedListResponse::<()>::from_page(
+            Vec::new(),
+            35,
+            PageRequestDTO { page: 2, per_page: 10 }
+        );
+
+        assert_eq!(
+            response.link_header(&request),
+            [
+                "</api/v1/user/all?name=jo&per_page=10&page=1>; rel=\"first\"",
+                "</api/v1/user/all?name=jo&per_page=10&page=1>; rel=\"prev\"",
+                "</api/v1/user/all?name=jo&per_page=10&page=3>; rel=\"next\"",
+                "</api/v1/user/all?name=jo&per_page=10&page=4>; rel=\"last\"",
+            ].join(", ")
+        );
+    }
+
+    #[test]
+    fn page_links_skip_missing_neighbours() {
+        let request = TestRequest::with_uri("/api/v1/user/all").to_http_request();
+        let response = BasedListResponse::<()>::from_page(
+            Vec::new(),
+            0,
+            PageRequestDTO { page: 1, per_page: 20 }
+        );
+
+        assert_eq!(
+            response.link_header(&request),
+            "</api/v1/user/all?page=1>; rel=\"first\", </api/v1/user/all?page=1>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn cursor_links_replace_cursor() {
+        let request = TestRequest::with_uri("/api/v1/user/all?cursor=first&sort=-created_date")
+            .to_http_request();
+        let page = Cursor::FIRST.to_page_request(true, 20);
+        let response = BasedListResponse::<()>::from_cursor_page(
+            Vec::new(),
+            page,
+            Some(key()),
+            None
+        );
+        let next = Cursor { key: Some(key()), backward: false, descending: true }.encode();
+
+        assert_eq!(
+            response.link_header(&request),
+            [
+                "</api/v1/user/all?sort=-created_date&cursor=first>; rel=\"first\"".to_string(),
+                format!("</api/v1/user/all?sort=-created_date&cursor={}>; rel=\"next\"", next),
+                "</api/v1/user/all?sort=-created_date&cursor=last>; rel=\"last\"".to_string(),
+            ].join(", ")
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use chrono::NaiveDateTime;
+use rust_commons::uuid::Uuid;
+use serde::Serialize;
+
+use crate::infra::database::session_repository::Session;
+
+#[derive(Clone, Serialize)]
+pub struct SessionResponse {
+    pub uuid: Uuid,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub user_agent: Option<Arc<str>>,
+    pub client_ip: Option<Arc<str>>,
+    pub expires_at: NaiveDateTime,
+    pub current: bool,
+}
+
+impl SessionResponse {
+    pub fn model_to_response(session: &Session, current_uuid: &Uuid) -> Self {
+        return SessionResponse {
+            uuid: session.uuid,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            user_agent: session.user_agent.as_deref().map(Arc::from),
+            client_ip: session.client_ip.as_deref().map(Arc::from),
+            expires_at: session.expires_at,
+            current: &session.uuid == current_uuid,
+        };
+    }
+
+    pub fn models_to_response(sessions: &[Session], current_uuid: &Uuid) -> Vec<Self> {
+        return sessions
+            .iter()
+            .map(|session| Self::model_to_response(session, current_uuid))
+            .collect();
+    }
+}
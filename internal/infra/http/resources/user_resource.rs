@@ -14,6 +14,8 @@ pub struct UserResponse {
     pub created_date: Arc<NaiveDateTime>,
     pub updated_date: Arc<NaiveDateTime>,
     pub deleted_date: Arc<Option<NaiveDateTime>>,
+    pub email_verified_at: Arc<Option<NaiveDateTime>>,
+    pub locale: Option<Arc<str>>,
 }
 
 impl UserResponse {
@@ -26,6 +28,8 @@ impl UserResponse {
             created_date: dto.created_date.clone(),
             updated_date: dto.updated_date.clone(),
             deleted_date: dto.deleted_date.clone(),
+            email_verified_at: dto.email_verified_at.clone(),
+            locale: dto.locale.clone(),
         };
     }
 
@@ -38,6 +42,8 @@ impl UserResponse {
             created_date: Arc::new(dto.created_date),
             updated_date: Arc::new(dto.updated_date),
             deleted_date: Arc::new(dto.deleted_date),
+            email_verified_at: Arc::new(dto.email_verified_at),
+            locale: dto.locale.to_owned().map(Arc::from),
         };
     }
 
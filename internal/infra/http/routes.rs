@@ -6,24 +6,83 @@ use actix_web::{
     web::{ self, Data },
     HttpResponse,
     Responder,
+    Route,
     Scope,
 };
 use config::CONFIGURATION;
 use serde::Serialize;
 
-use crate::{ container::container::Container, services::user_service::UserService };
+use crate::{
+    container::container::Container,
+    infra::domain::role::permissions::{
+        ROLES_MANAGE,
+        TOKENS_INTROSPECT,
+        USERS_IMPERSONATE,
+        USERS_READ,
+        USERS_UNLOCK,
+    },
+    services::user_service::UserService,
+};
 
 const BASIC_PATH: &str = "/api/v1";
 
 use super::{
     controllers::{
-        auth_controller::{ login, logout, register, AuthController },
-        user_controller::{ delete, find_all, find_me, update, UserController },
+        admin_controller::{
+            find_impersonations,
+            find_user_roles,
+            grant_role,
+            impersonate_user,
+            revoke_role,
+            unlock_user,
+            AdminController,
+        },
+        api_key_controller::{
+            create_api_key,
+            find_api_keys,
+            revoke_api_key,
+            ApiKeyController,
+        },
+        auth_controller::{
+            consume_magic_link,
+            find_session,
+            find_sessions,
+            forgot_password,
+            jwks,
+            login,
+            logout,
+            refresh,
+            register,
+            resend_verification,
+            reset_password,
+            revoke_other_sessions,
+            revoke_session,
+            send_magic_link,
+            stop_impersonation,
+            verify_email,
+            verify_two_factor,
+            AuthController,
+        },
+        introspection_controller::{ introspect, IntrospectionController },
+        oidc_controller::{ oidc_callback, start_oidc_login, OidcController },
+        two_factor_controller::{ confirm, disable, enroll, TwoFactorController },
+        user_controller::{
+            change_password,
+            delete,
+            find_all,
+            find_logins,
+            find_me,
+            update,
+            UserController,
+        },
     },
     middlewares::{
         auth_middleware::auth_middleware,
+        impersonation_middleware::not_impersonating_middleware,
         is_owner_middleware::is_owner_middleware,
         path_object_middleware::path_object_middleware,
+        permission_middleware::permission_middleware,
+        verified_middleware::verified_middleware,
         Findable,
         Userable,
     },
@@ -32,11 +91,32 @@ use super::{
 pub fn init_routes(cfg: &mut web::ServiceConfig, container: Arc<Container>) {
     let auth_controller_data = web::Data::new(container.controllers.user_controller.clone());
     let user_controller_data = web::Data::new(container.controllers.auth_controller.clone());
+    let admin_controller_data = web::Data::new(container.controllers.admin_controller.clone());
+    let api_key_controller_data = web::Data::new(
+        container.controllers.api_key_controller.clone()
+    );
+    let two_factor_controller_data = web::Data::new(
+        container.controllers.two_factor_controller.clone()
+    );
+    let oidc_controller_data = web::Data::new(container.controllers.oidc_controller.clone());
+    let introspection_controller_data = web::Data::new(
+        container.controllers.introspection_controller.clone()
+    );
     cfg.service(
         web
             ::scope(BASIC_PATH)
-            .service(init_auth_routes(user_controller_data, Arc::clone(&container)))
+            .service(
+                init_auth_routes(
+                    user_controller_data,
+                    two_factor_controller_data,
+                    api_key_controller_data,
+                    oidc_controller_data,
+                    introspection_controller_data,
+                    Arc::clone(&container)
+                )
+            )
             .service(init_user_routes(auth_controller_data, Arc::clone(&container)))
+            .service(init_admin_routes(admin_controller_data, Arc::clone(&container)))
     );
     cfg.service(
         web::scope("/api").route(
@@ -46,6 +126,12 @@ pub fn init_routes(cfg: &mut web::ServiceConfig, container: Arc<Container>) {
             })
         )
     );
+    cfg.service(
+        web
+            ::resource("/.well-known/jwks.json")
+            .app_data(web::Data::new(container.controllers.auth_controller.clone()))
+            .route(web::get().to(jwks))
+    );
     cfg.service(
         actix_files::Files
             ::new("/static", &CONFIGURATION.file_storage_location)
@@ -60,6 +146,10 @@ async fn not_found_handler() -> impl Responder {
 
 fn init_auth_routes(
     auth_controller: Data<AuthController>,
+    two_factor_controller: Data<TwoFactorController>,
+    api_key_controller: Data<ApiKeyController>,
+    oidc_controller: Data<OidcController>,
+    introspection_controller: Data<IntrospectionController>,
     container: Arc<Container>
 ) -> Scope<
     impl ServiceFactory<
@@ -73,9 +163,43 @@ fn init_auth_routes(
     return web
         ::scope("/auth")
         .app_data(auth_controller.clone())
+        .app_data(two_factor_controller)
+        .app_data(api_key_controller)
+        .app_data(oidc_controller)
+        .app_data(introspection_controller)
         .route("/register", web::post().to(register))
         .route("/login", web::post().to(login))
-        .service(protected_route(container, "").route("/logout", web::post().to(logout)));
+        .route("/refresh", web::post().to(refresh))
+        .route("/logout", web::post().to(logout))
+        .route("/password/forgot", web::post().to(forgot_password))
+        .route("/password/reset", web::post().to(reset_password))
+        .route("/magic-link", web::post().to(send_magic_link))
+        .route("/magic-link/consume", web::post().to(consume_magic_link))
+        .route("/email/verify", web::post().to(verify_email))
+        .route("/email/resend", web::post().to(resend_verification))
+        .route("/2fa/verify", web::post().to(verify_two_factor))
+        .route("/oidc/{provider}/start", web::get().to(start_oidc_login))
+        .route("/oidc/{provider}/callback", web::get().to(oidc_callback))
+        .service(
+            permission_route(Arc::clone(&container), "/introspect", TOKENS_INTROSPECT).route(
+                "",
+                web::post().to(introspect)
+            )
+        )
+        .service(
+            protected_route(container, "")
+                .route("/impersonation/stop", web::post().to(stop_impersonation))
+                .route("/sessions", web::get().to(find_sessions))
+                .route("/sessions", not_impersonating(web::delete().to(revoke_other_sessions)))
+                .route("/sessions/{uuid}", web::get().to(find_session))
+                .route("/sessions/{uuid}", not_impersonating(web::delete().to(revoke_session)))
+                .route("/2fa/enroll", not_impersonating(web::post().to(enroll)))
+                .route("/2fa/confirm", not_impersonating(web::post().to(confirm)))
+                .route("/2fa/disable", not_impersonating(web::post().to(disable)))
+                .route("/api-keys", not_impersonating(web::post().to(create_api_key)))
+                .route("/api-keys", web::get().to(find_api_keys))
+                .route("/api-keys/{id}", not_impersonating(web::delete().to(revoke_api_key)))
+        );
 }
 
 fn init_user_routes(
@@ -90,14 +214,66 @@ fn init_user_routes(
         InitError = ()
     >
 > {
-    return protected_route(Arc::clone(&container), "/user")
+    return web
+        ::scope("/user")
         .app_data(us_controller)
-        .route("/all", web::get().to(find_all))
-        .route("", web::get().to(find_me))
-        .route("", web::delete().to(delete))
-        .route("", web::put().to(update));
+        .service(
+            permission_route(Arc::clone(&container), "/all", USERS_READ).route(
+                "",
+                web::get().to(find_all).wrap(from_fn(verified_middleware))
+            )
+        )
+        .service(
+            protected_route(container, "")
+                .route("", web::get().to(find_me))
+                .route("", not_impersonating(web::delete().to(delete)))
+                .route("", not_impersonating(web::put().to(update)))
+                .route("/logins", web::get().to(find_logins))
+                .route("/password", not_impersonating(web::put().to(change_password)))
+        );
 }
 
+fn init_admin_routes(
+    admin_controller: Data<AdminController>,
+    container: Arc<Container>
+) -> Scope<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse,
+        Error = actix_web::Error,
+        InitError = ()
+    >
+> {
+    return web
+        ::scope("/admin")
+        .app_data(admin_controller)
+        .service(
+            permission_route(Arc::clone(&container), "/users/{id}/unlock", USERS_UNLOCK).route(
+                "",
+                not_impersonating(web::post().to(unlock_user))
+            )
+        )
+        .service(
+            permission_route(Arc::clone(&container), "/users/{id}/roles", ROLES_MANAGE)
+                .route("", web::get().to(find_user_roles))
+                .route("", not_impersonating(web::post().to(grant_role)))
+                .route("/{role}", not_impersonating(web::delete().to(revoke_role)))
+        )
+        .service(
+            permission_route(Arc::clone(&container), "/users/{id}/impersonate", USERS_IMPERSONATE)
+                .route("", not_impersonating(web::post().to(impersonate_user)))
+        )
+        .service(
+            permission_route(
+                Arc::clone(&container),
+                "/users/{id}/impersonations",
+                USERS_IMPERSONATE
+            ).route("", web::get().to(find_impersonations))
+        );
+}
+
+/// Requires a login session, API keys are rejected.
 fn protected_route(
     container: Arc<Container>,
     path: &str
@@ -115,6 +291,9 @@ fn protected_route(
             return auth_middleware(
                 Arc::clone(&container.services.user_service),
                 Arc::clone(&container.services.auth_service),
+                Arc::clone(&container.services.role_service),
+                Arc::clone(&container.services.api_key_service),
+                false,
                 req,
                 next
             );
@@ -122,6 +301,48 @@ fn protected_route(
     );
 }
 
+/// Requires a permission. Accepts API keys too, as long as the key is scoped to the permission.
+fn permission_route(
+    container: Arc<Container>,
+    path: &str,
+    permission: &'static str
+) -> Scope<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse,
+        Error = actix_web::Error,
+        InitError = ()
+    >
+> {
+    return web
+        ::scope(path)
+        .wrap(
+            from_fn(move |req: ServiceRequest, next| {
+                return permission_middleware(permission, req, next);
+            })
+        )
+        .wrap(
+            from_fn(move |req: ServiceRequest, next| {
+                return auth_middleware(
+                    Arc::clone(&container.services.user_service),
+                    Arc::clone(&container.services.auth_service),
+                    Arc::clone(&container.services.role_service),
+                    Arc::clone(&container.services.api_key_service),
+                    true,
+                    req,
+                    next
+                );
+            })
+        );
+}
+
+/// Forbids the route while impersonating, must be used inside of `protected_route` or
+/// `permission_route`.
+fn not_impersonating(route: Route) -> Route {
+    return route.wrap(from_fn(not_impersonating_middleware));
+}
+
 // TODO
 #[allow(dead_code)]
 fn is_owner_route<T>(
@@ -2,7 +2,13 @@ pub mod infra;
 pub mod container;
 pub mod services;
 pub mod filesystem;
+pub mod tasks;
+pub mod notifications;
+pub mod mail;
 pub use actix_web::{ App, HttpServer, HttpResponse, Responder, web };
 pub use actix_web::main as actix_main;
 pub use actix_web;
 pub use rust_commons::database::migration::migrate;
+pub mod jwt;
+pub mod cache;
+pub mod password;
@@ -0,0 +1,94 @@
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters,
+        CommonParameters,
+        EllipticCurve,
+        Jwk,
+        OctetKeyPairParameters,
+        OctetKeyPairType,
+        PublicKeyUse,
+        RSAKeyParameters,
+        RSAKeyType,
+    },
+    Algorithm,
+};
+use rust_commons::base64::{ self, Engine };
+use simple_asn1::ASN1Block;
+
+/// Converts a PEM public key (SubjectPublicKeyInfo or PKCS#1 RSA) into JWK.
+pub fn public_pem_to_jwk(
+    public_pem: &[u8],
+    algorithm: Algorithm,
+    kid: &str
+) -> Result<Jwk, String> {
+    let pem = pem::parse(public_pem).map_err(|e| e.to_string())?;
+    let algorithm_parameters = match algorithm {
+        Algorithm::RS256 => {
+            let rsa_der = if pem.tag == "RSA PUBLIC KEY" {
+                pem.contents
+            } else {
+                subject_public_key(&pem.contents)?
+            };
+            let (n, e) = rsa_components(&rsa_der)?;
+            AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: encode(&n),
+                e: encode(&e),
+            })
+        }
+        Algorithm::EdDSA => {
+            AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: encode(&subject_public_key(&pem.contents)?),
+            })
+        }
+        _ => {
+            return Err(format!("Algorithm [{:?}] is not supported in JWKS", algorithm));
+        }
+    };
+    return Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_operations: None,
+            algorithm: Some(algorithm),
+            key_id: Some(kid.to_string()),
+            x509_url: None,
+            x509_chain: None,
+            x509_sha1_fingerprint: None,
+            x509_sha256_fingerprint: None,
+        },
+        algorithm: algorithm_parameters,
+    });
+}
+
+/// Extracts key bytes from `SEQUENCE { AlgorithmIdentifier, BIT STRING }`.
+fn subject_public_key(der: &[u8]) -> Result<Vec<u8>, String> {
+    let blocks = simple_asn1::from_der(der).map_err(|e| e.to_string())?;
+    if let Some(ASN1Block::Sequence(_, items)) = blocks.first() {
+        if let Some(ASN1Block::BitString(_, _, key)) = items.get(1) {
+            return Ok(key.clone());
+        }
+    }
+    return Err("Not a SubjectPublicKeyInfo".to_string());
+}
+
+/// Extracts modulus and exponent from `SEQUENCE { INTEGER n, INTEGER e }`.
+fn rsa_components(der: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let blocks = simple_asn1::from_der(der).map_err(|e| e.to_string())?;
+    if let Some(ASN1Block::Sequence(_, items)) = blocks.first() {
+        if
+            let (Some(ASN1Block::Integer(_, n)), Some(ASN1Block::Integer(_, e))) = (
+                items.first(),
+                items.get(1),
+            )
+        {
+            return Ok((n.to_bytes_be().1, e.to_bytes_be().1));
+        }
+    }
+    return Err("Not an RSA public key".to_string());
+}
+
+fn encode(bytes: &[u8]) -> String {
+    return base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+}
@@ -0,0 +1,140 @@
+use std::{ collections::HashMap, fs, sync::Arc };
+
+use config::{ JwtAlgorithm, CONFIGURATION };
+use jsonwebtoken::{
+    errors::ErrorKind,
+    jwk::JwkSet,
+    Algorithm,
+    DecodingKey,
+    EncodingKey,
+    Header,
+    Validation,
+};
+use serde::{ de::DeserializeOwned, Serialize };
+use thiserror::Error;
+
+pub mod jwk;
+
+/// Keys used to sign and verify JWT.
+///
+/// With RS256 or EdDSA tokens are signed with the private key and carry its `kid`.
+/// Every configured public key is accepted for verification, so tokens signed with
+/// the previous key stay valid during rotation, and published as JWKS.
+pub struct JwtKeys {
+    algorithm: Algorithm,
+    signing_key_id: Option<String>,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, DecodingKey>,
+    jwks: JwkSet,
+}
+
+#[derive(Error, Debug)]
+pub enum JwtKeysError {
+    #[error("Failed to read key [{0}] - {1}")] IoError(String, std::io::Error),
+    #[error("Invalid key [{0}] - {1}")] KeyError(String, String),
+    #[error("{0}")] ConfigurationError(String),
+}
+
+impl JwtKeys {
+    pub fn from_configuration() -> Result<Arc<JwtKeys>, JwtKeysError> {
+        let algorithm = match CONFIGURATION.jwt_algorithm {
+            JwtAlgorithm::HS256 => {
+                return Ok(Arc::new(JwtKeys::hmac(CONFIGURATION.jwt_secret.as_bytes())));
+            }
+            JwtAlgorithm::RS256 => Algorithm::RS256,
+            JwtAlgorithm::EdDSA => Algorithm::EdDSA,
+        };
+        if CONFIGURATION.jwt_key_id.is_empty() {
+            return Err(JwtKeysError::ConfigurationError("JWT_KEY_ID is not set".to_string()));
+        }
+        if !CONFIGURATION.jwt_public_keys.iter().any(|(kid, _)| kid == &CONFIGURATION.jwt_key_id) {
+            return Err(
+                JwtKeysError::ConfigurationError(
+                    format!("JWT_PUBLIC_KEYS has no key for [{}]", CONFIGURATION.jwt_key_id)
+                )
+            );
+        }
+
+        let private_pem = read_key(&CONFIGURATION.jwt_private_key_path)?;
+        let encoding_key = (
+            match algorithm {
+                Algorithm::RS256 => EncodingKey::from_rsa_pem(&private_pem),
+                _ => EncodingKey::from_ed_pem(&private_pem),
+            }
+        ).map_err(|e| {
+            JwtKeysError::KeyError(CONFIGURATION.jwt_private_key_path.clone(), e.to_string())
+        })?;
+
+        let mut decoding_keys = HashMap::new();
+        let mut jwks = JwkSet { keys: Vec::new() };
+        for (kid, path) in &CONFIGURATION.jwt_public_keys {
+            let public_pem = read_key(path)?;
+            let decoding_key = (
+                match algorithm {
+                    Algorithm::RS256 => DecodingKey::from_rsa_pem(&public_pem),
+                    _ => DecodingKey::from_ed_pem(&public_pem),
+                }
+            ).map_err(|e| JwtKeysError::KeyError(path.clone(), e.to_string()))?;
+            decoding_keys.insert(kid.clone(), decoding_key);
+            jwks.keys.push(
+                jwk::public_pem_to_jwk(&public_pem, algorithm, kid).map_err(|e| {
+                    JwtKeysError::KeyError(path.clone(), e)
+                })?
+            );
+        }
+
+        return Ok(
+            Arc::new(JwtKeys {
+                algorithm,
+                signing_key_id: Some(CONFIGURATION.jwt_key_id.clone()),
+                encoding_key,
+                decoding_keys,
+                jwks,
+            })
+        );
+    }
+
+    /// Symmetric keys, nothing is published in JWKS.
+    pub fn hmac(secret: &[u8]) -> JwtKeys {
+        return JwtKeys {
+            algorithm: Algorithm::HS256,
+            signing_key_id: None,
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_keys: HashMap::from([(String::new(), DecodingKey::from_secret(secret))]),
+            jwks: JwkSet { keys: Vec::new() },
+        };
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
+        let mut header = Header::new(self.algorithm);
+        header.kid = self.signing_key_id.clone();
+        return jsonwebtoken::encode(&header, claims, &self.encoding_key);
+    }
+
+    /// Picks the verification key by `kid` header, tokens without `kid` are accepted only
+    /// with HS256.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str
+    ) -> Result<T, jsonwebtoken::errors::Error> {
+        let header = jsonwebtoken::decode_header(token)?;
+        let kid = header.kid.unwrap_or_default();
+        let decoding_key = self.decoding_keys
+            .get(&kid)
+            .ok_or(jsonwebtoken::errors::Error::from(ErrorKind::InvalidToken))?;
+        let token_data = jsonwebtoken::decode::<T>(
+            token,
+            decoding_key,
+            &Validation::new(self.algorithm)
+        )?;
+        return Ok(token_data.claims);
+    }
+
+    pub fn jwks(&self) -> &JwkSet {
+        return &self.jwks;
+    }
+}
+
+fn read_key(path: &str) -> Result<Vec<u8>, JwtKeysError> {
+    return fs::read(path).map_err(|e| JwtKeysError::IoError(path.to_string(), e));
+}
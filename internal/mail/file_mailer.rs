@@ -0,0 +1,39 @@
+use std::{ error, fs, path::{ Path, PathBuf } };
+
+use chrono::Utc;
+use lettre::message::Mailbox;
+use rust_commons::rand::{ self, Rng };
+
+use super::{ build_message, Email, Mailer };
+
+/// Stores emails in a maildir, so they can be read by a mail client or a test.
+pub struct FileMailer {
+    loc: PathBuf,
+    from: Mailbox,
+}
+
+impl FileMailer {
+    pub fn new(
+        location: &str,
+        from: &str
+    ) -> Result<FileMailer, Box<dyn error::Error + Send + Sync + 'static>> {
+        let loc = Path::new(location).to_path_buf();
+        for dir in ["tmp", "new", "cur"] {
+            fs::create_dir_all(loc.join(dir))?;
+        }
+        return Ok(FileMailer { loc, from: from.parse()? });
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, email: &Email) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>> {
+        let message = build_message(&self.from, email)?;
+        let num: u64 = rand::thread_rng().gen();
+        let filename = format!("{}.{}.eml", Utc::now().timestamp_micros(), num);
+        // Maildir delivery: write to tmp first, so readers never see partially written files.
+        let tmp_path = self.loc.join("tmp").join(&filename);
+        fs::write(&tmp_path, message.formatted())?;
+        fs::rename(&tmp_path, self.loc.join("new").join(&filename))?;
+        return Ok(());
+    }
+}
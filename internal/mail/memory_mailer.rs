@@ -0,0 +1,71 @@
+use std::{ error, sync::Mutex };
+
+use super::{ Email, Mailer };
+
+/// Keeps sent emails in memory, so tests can read their content.
+#[derive(Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl MemoryMailer {
+    pub fn new() -> Self {
+        return MemoryMailer { sent: Mutex::new(Vec::new()) };
+    }
+
+    pub fn sent(&self) -> Vec<Email> {
+        return self.sent.lock().unwrap().clone();
+    }
+
+    pub fn last_to(&self, recipient: &str) -> Option<Email> {
+        return self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|email| email.to.as_ref() == recipient)
+            .cloned();
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send(&self, email: &Email) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>> {
+        self.sent.lock().unwrap().push(email.clone());
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ Email, Mailer, MemoryMailer };
+
+    fn email(to: &str, subject: &str) -> Email {
+        return Email { to: Arc::from(to), subject: Arc::from(subject), body: Arc::from("body") };
+    }
+
+    #[test]
+    fn sent_emails_are_kept_in_order() {
+        let mailer = MemoryMailer::new();
+        mailer.send(&email("a@example.com", "first")).unwrap();
+        mailer.send(&email("b@example.com", "second")).unwrap();
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].subject.as_ref(), "first");
+        assert_eq!(sent[1].subject.as_ref(), "second");
+    }
+
+    #[test]
+    fn last_email_to_recipient_is_found() {
+        let mailer = MemoryMailer::new();
+        mailer.send(&email("a@example.com", "first")).unwrap();
+        mailer.send(&email("b@example.com", "second")).unwrap();
+        mailer.send(&email("a@example.com", "third")).unwrap();
+
+        assert_eq!(mailer.last_to("a@example.com").unwrap().subject.as_ref(), "third");
+        assert_eq!(mailer.last_to("b@example.com").unwrap().subject.as_ref(), "second");
+        assert!(mailer.last_to("c@example.com").is_none());
+    }
+}
@@ -0,0 +1,32 @@
+use std::{ error, sync::Arc };
+
+use lettre::{ message::{ header::ContentType, Mailbox }, Message };
+
+pub mod file_mailer;
+pub mod memory_mailer;
+pub mod smtp_mailer;
+pub mod templates;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Email {
+    pub to: Arc<str>,
+    pub subject: Arc<str>,
+    pub body: Arc<str>,
+}
+
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>>;
+}
+
+pub(crate) fn build_message(
+    from: &Mailbox,
+    email: &Email
+) -> Result<Message, Box<dyn error::Error + Send + Sync + 'static>> {
+    let message = Message::builder()
+        .from(from.clone())
+        .to(email.to.parse()?)
+        .subject(email.subject.as_ref())
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.body.to_string())?;
+    return Ok(message);
+}
@@ -0,0 +1,47 @@
+use std::error;
+
+use lettre::{
+    message::Mailbox,
+    transport::smtp::authentication::Credentials,
+    SmtpTransport,
+    Transport,
+};
+
+use super::{ build_message, Email, Mailer };
+
+pub struct SmtpMailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// Without `tls` the connection is not encrypted, which is meant for local SMTP sinks only.
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+        tls: bool,
+        from: &str
+    ) -> Result<SmtpMailer, Box<dyn error::Error + Send + Sync + 'static>> {
+        let mut builder = if tls {
+            SmtpTransport::relay(host)?.port(port)
+        } else {
+            SmtpTransport::builder_dangerous(host).port(port)
+        };
+        if !username.is_empty() {
+            builder = builder.credentials(
+                Credentials::new(username.to_owned(), password.to_owned())
+            );
+        }
+        return Ok(SmtpMailer { transport: builder.build(), from: from.parse()? });
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &Email) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>> {
+        let message = build_message(&self.from, email)?;
+        self.transport.send(&message)?;
+        return Ok(());
+    }
+}
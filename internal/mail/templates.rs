@@ -0,0 +1,172 @@
+use std::error;
+
+/// Templates are embedded into the binary. First line of a template is the subject,
+/// the rest after an empty line is the body. `{{name}}` placeholders are replaced on rendering.
+const TEMPLATES: &[(&str, &str, &str)] = &[
+    ("en", "password_reset", include_str!("templates/en/password_reset.txt")),
+    ("en", "email_verification", include_str!("templates/en/email_verification.txt")),
+    ("en", "magic_link", include_str!("templates/en/magic_link.txt")),
+    ("en", "new_device_login", include_str!("templates/en/new_device_login.txt")),
+    ("uk", "password_reset", include_str!("templates/uk/password_reset.txt")),
+    ("uk", "email_verification", include_str!("templates/uk/email_verification.txt")),
+    ("uk", "magic_link", include_str!("templates/uk/magic_link.txt")),
+    ("uk", "new_device_login", include_str!("templates/uk/new_device_login.txt")),
+];
+
+const FALLBACK_LOCALE: &str = "en";
+
+pub struct RenderedTemplate {
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Clone)]
+pub struct MailTemplates {
+    default_locale: String,
+}
+
+impl MailTemplates {
+    pub fn new(default_locale: &str) -> Self {
+        return MailTemplates { default_locale: default_locale.to_owned() };
+    }
+
+    /// Renders template in requested locale, falling back to the default one and then to English.
+    pub fn render(
+        &self,
+        name: &str,
+        locale: Option<&str>,
+        vars: &[(&str, &str)]
+    ) -> Result<RenderedTemplate, Box<dyn error::Error + Send + Sync + 'static>> {
+        let source = [locale, Some(self.default_locale.as_str()), Some(FALLBACK_LOCALE)]
+            .into_iter()
+            .flatten()
+            .find_map(|locale| find_template(locale, name))
+            .ok_or_else(|| format!("Mail template [{}] not found", name))?;
+        return render_source(name, source, vars);
+    }
+
+    pub fn supports_locale(locale: &str) -> bool {
+        return TEMPLATES.iter().any(|(template_locale, _, _)| *template_locale == locale);
+    }
+}
+
+/// Splits the source into subject and body before substituting, so values can't move the split.
+fn render_source(
+    name: &str,
+    source: &str,
+    vars: &[(&str, &str)]
+) -> Result<RenderedTemplate, Box<dyn error::Error + Send + Sync + 'static>> {
+    let (subject, body) = source
+        .split_once("\n\n")
+        .ok_or_else(|| format!("Mail template [{}] has no subject line", name))?;
+    let subject = substitute(subject.trim(), vars);
+    // Line breaks would let a value add headers to the message.
+    if subject.contains(['\r', '\n']) {
+        return Err(Box::from(format!("Subject of mail template [{}] has a line break", name)));
+    }
+    return Ok(RenderedTemplate { subject, body: substitute(body, vars) });
+}
+
+/// Replaces `{{key}}` placeholders in a single pass, so placeholders inside substituted values
+/// are left as they are. Placeholders without a value are kept too.
+fn substitute(text: &str, vars: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start..].find("}}") else {
+            break;
+        };
+        let end = start + length + 2;
+        rendered.push_str(&rest[..start]);
+        match vars.iter().find(|(key, _)| *key == &rest[start + 2..end - 2]) {
+            Some((_, value)) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    rendered.push_str(rest);
+    return rendered;
+}
+
+fn find_template(locale: &str, name: &str) -> Option<&'static str> {
+    return TEMPLATES.iter()
+        .find(|(template_locale, template_name, _)| {
+            *template_locale == locale && *template_name == name
+        })
+        .map(|(_, _, source)| *source);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ render_source, substitute, MailTemplates };
+
+    #[test]
+    fn subject_and_body_are_rendered() {
+        let rendered = MailTemplates::new("en")
+            .render("password_reset", None, &[("name", "Jane"), ("token", "abc123")])
+            .unwrap();
+
+        assert!(!rendered.subject.is_empty());
+        assert!(!rendered.subject.contains('\n'));
+        assert!(rendered.body.contains("Hello, Jane!"));
+        assert!(rendered.body.contains("abc123"));
+        assert!(!rendered.body.contains("{{name}}"));
+    }
+
+    #[test]
+    fn requested_locale_is_used_before_default() {
+        let templates = MailTemplates::new("en");
+        let vars = [("name", "Jane"), ("token", "abc123")];
+        let english = templates.render("password_reset", None, &vars).unwrap();
+        let ukrainian = templates.render("password_reset", Some("uk"), &vars).unwrap();
+        let unknown = templates.render("password_reset", Some("xx"), &vars).unwrap();
+
+        assert_ne!(english.subject, ukrainian.subject);
+        assert_eq!(english.subject, unknown.subject);
+        let default_uk = MailTemplates::new("uk").render("password_reset", None, &vars).unwrap();
+        assert_eq!(default_uk.subject, ukrainian.subject);
+    }
+
+    #[test]
+    fn unknown_template_is_an_error() {
+        assert!(MailTemplates::new("en").render("unknown", None, &[]).is_err());
+    }
+
+    #[test]
+    fn line_breaks_are_rejected_in_subject() {
+        let source = "Hello, {{name}}\n\nBody for {{name}}";
+
+        for name in ["Jane\r\nBcc: x@example.com", "Jane\nBcc: x@example.com", "Jane\r"] {
+            assert!(render_source("test", source, &[("name", name)]).is_err());
+        }
+        let rendered = render_source("test", source, &[("name", "Jane")]).unwrap();
+        assert_eq!(rendered.subject, "Hello, Jane");
+        assert_eq!(rendered.body, "Body for Jane");
+    }
+
+    #[test]
+    fn values_do_not_move_subject_split() {
+        let source = "Hello\n\nBody for {{name}}";
+        let rendered = render_source("test", source, &[("name", "Jane\n\nmore")]).unwrap();
+
+        assert_eq!(rendered.subject, "Hello");
+        assert_eq!(rendered.body, "Body for Jane\n\nmore");
+        assert!(render_source("test", "no split", &[]).is_err());
+    }
+
+    #[test]
+    fn placeholders_are_substituted_once() {
+        let vars = [("name", "{{token}}"), ("token", "secret")];
+
+        assert_eq!(substitute("{{name}} {{token}}", &vars), "{{token}} secret");
+        assert_eq!(substitute("{{unknown}} {{name", &vars), "{{unknown}} {{name");
+        assert_eq!(substitute("{{name}}{{name}}", &[("name", "a")]), "aa");
+    }
+
+    #[test]
+    fn supported_locales_are_known() {
+        assert!(MailTemplates::supports_locale("en"));
+        assert!(MailTemplates::supports_locale("uk"));
+        assert!(!MailTemplates::supports_locale("xx"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::{ error, sync::Arc };
+
+use config::CONFIGURATION;
+
+use crate::mail::{ templates::MailTemplates, Email, Mailer };
+
+use super::{ Notification, Notifier };
+
+/// Delivers notifications as emails rendered from mail templates.
+pub struct MailNotifier {
+    mailer: Arc<dyn Mailer>,
+    templates: MailTemplates,
+}
+
+impl MailNotifier {
+    pub fn new(mailer: Arc<dyn Mailer>, templates: MailTemplates) -> Self {
+        return MailNotifier { mailer, templates };
+    }
+}
+
+impl Notifier for MailNotifier {
+    fn notify(
+        &self,
+        notification: Notification
+    ) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>> {
+        let (template, mut vars): (&str, Vec<(&str, &str)>) = match &notification {
+            Notification::PasswordReset { name, token, .. } => {
+                ("password_reset", vec![("name", name), ("token", token)])
+            }
+            Notification::EmailVerification { name, token, .. } => {
+                ("email_verification", vec![("name", name), ("token", token)])
+            }
+            Notification::MagicLink { name, token, .. } => {
+                ("magic_link", vec![("name", name), ("token", token)])
+            }
+            Notification::NewDeviceLogin { name, client_ip, user_agent, logged_in_at, .. } => {
+                (
+                    "new_device_login",
+                    vec![
+                        ("name", name),
+                        ("client_ip", client_ip),
+                        ("user_agent", user_agent),
+                        ("logged_in_at", logged_in_at),
+                    ],
+                )
+            }
+        };
+        vars.push(("app_url", &CONFIGURATION.app_url));
+        let rendered = self.templates.render(template, notification.locale(), &vars)?;
+        return self.mailer.send(
+            &(Email {
+                to: Arc::from(notification.recipient()),
+                subject: Arc::from(rendered.subject),
+                body: Arc::from(rendered.body),
+            })
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::{ error, sync::Mutex };
+
+use super::{ Notification, Notifier };
+
+/// Keeps sent notifications in memory, so tests can read tokens sent to users.
+#[derive(Default)]
+pub struct MemoryNotifier {
+    sent: Mutex<Vec<Notification>>,
+}
+
+impl MemoryNotifier {
+    pub fn new() -> Self {
+        return MemoryNotifier { sent: Mutex::new(Vec::new()) };
+    }
+
+    pub fn sent(&self) -> Vec<Notification> {
+        return self.sent.lock().unwrap().clone();
+    }
+
+    pub fn last_for(&self, recipient: &str) -> Option<Notification> {
+        return self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|notification| notification.recipient() == recipient)
+            .cloned();
+    }
+}
+
+impl Notifier for MemoryNotifier {
+    fn notify(
+        &self,
+        notification: Notification
+    ) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>> {
+        self.sent.lock().unwrap().push(notification);
+        return Ok(());
+    }
+}
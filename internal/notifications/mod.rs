@@ -0,0 +1,64 @@
+use std::{ error, sync::Arc };
+
+pub mod mail_notifier;
+pub mod memory_notifier;
+
+/// Messages which are delivered to users outside of the HTTP response.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notification {
+    PasswordReset {
+        email: Arc<str>,
+        name: Arc<str>,
+        token: Arc<str>,
+        locale: Option<Arc<str>>,
+    },
+    EmailVerification {
+        email: Arc<str>,
+        name: Arc<str>,
+        token: Arc<str>,
+        locale: Option<Arc<str>>,
+    },
+    MagicLink {
+        email: Arc<str>,
+        name: Arc<str>,
+        token: Arc<str>,
+        locale: Option<Arc<str>>,
+    },
+    /// Successful login from a device the account was not used from before.
+    NewDeviceLogin {
+        email: Arc<str>,
+        name: Arc<str>,
+        client_ip: Arc<str>,
+        user_agent: Arc<str>,
+        logged_in_at: Arc<str>,
+        locale: Option<Arc<str>>,
+    },
+}
+
+impl Notification {
+    pub fn recipient(&self) -> &str {
+        match self {
+            Notification::PasswordReset { email, .. } => email,
+            Notification::EmailVerification { email, .. } => email,
+            Notification::MagicLink { email, .. } => email,
+            Notification::NewDeviceLogin { email, .. } => email,
+        }
+    }
+
+    /// Language the recipient reads emails in, `None` means the default one.
+    pub fn locale(&self) -> Option<&str> {
+        match self {
+            Notification::PasswordReset { locale, .. } => locale.as_deref(),
+            Notification::EmailVerification { locale, .. } => locale.as_deref(),
+            Notification::MagicLink { locale, .. } => locale.as_deref(),
+            Notification::NewDeviceLogin { locale, .. } => locale.as_deref(),
+        }
+    }
+}
+
+pub trait Notifier: Send + Sync {
+    fn notify(
+        &self,
+        notification: Notification
+    ) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>>;
+}
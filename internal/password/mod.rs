@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use argon2::{
+    password_hash::SaltString,
+    Algorithm,
+    Argon2,
+    Params,
+    PasswordHash,
+    PasswordHasher as _,
+    PasswordVerifier as _,
+    Version,
+};
+use config::{ PasswordHashAlgorithm, CONFIGURATION };
+use rust_commons::{
+    pwhash::{ self, bcrypt::{ BcryptSetup, BcryptVariant } },
+    rand::rngs::OsRng,
+};
+use thiserror::Error;
+
+pub mod policy;
+
+const BCRYPT_PREFIXES: [&str; 4] = ["$2a$", "$2b$", "$2x$", "$2y$"];
+
+/// Hashes passwords with the configured algorithm and verifies hashes of any supported one.
+///
+/// The algorithm of a stored hash is detected from its prefix (`$argon2id$`, `$2b$`), so hashes
+/// created with previous settings keep working. `needs_rehash` reports them until they are
+/// replaced, which `AuthService` does after a successful login.
+pub struct PasswordHasher {
+    algorithm: PasswordHashAlgorithm,
+    argon2_params: Params,
+    bcrypt_cost: u32,
+}
+
+#[derive(Error, Debug)]
+pub enum PasswordHasherError {
+    #[error("Argon2 error: {0}")] Argon2Error(argon2::password_hash::Error),
+    #[error("Bcrypt error: {0}")] BcryptError(pwhash::error::Error),
+    #[error("Invalid Argon2 parameters: {0}")] ConfigurationError(argon2::Error),
+}
+
+impl PasswordHasher {
+    pub fn from_configuration() -> Result<Arc<PasswordHasher>, PasswordHasherError> {
+        let argon2_params = Params::new(
+            CONFIGURATION.argon2_memory_cost,
+            CONFIGURATION.argon2_iterations,
+            CONFIGURATION.argon2_parallelism,
+            None
+        ).map_err(PasswordHasherError::ConfigurationError)?;
+        return Ok(
+            Arc::new(PasswordHasher {
+                algorithm: CONFIGURATION.password_hash_algorithm,
+                argon2_params,
+                bcrypt_cost: CONFIGURATION.bcrypt_cost,
+            })
+        );
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String, PasswordHasherError> {
+        match self.algorithm {
+            PasswordHashAlgorithm::Argon2id => {
+                let salt = SaltString::generate(&mut OsRng);
+                return Argon2::new(Algorithm::Argon2id, Version::V0x13, self.argon2_params.clone())
+                    .hash_password(password.as_bytes(), &salt)
+                    .map(|hash| hash.to_string())
+                    .map_err(PasswordHasherError::Argon2Error);
+            }
+            PasswordHashAlgorithm::Bcrypt => {
+                let setup = BcryptSetup {
+                    salt: None,
+                    cost: Some(self.bcrypt_cost),
+                    variant: Some(BcryptVariant::V2b),
+                };
+                return pwhash::bcrypt
+                    ::hash_with(setup, password)
+                    .map_err(PasswordHasherError::BcryptError);
+            }
+        }
+    }
+
+    /// Malformed hashes and hashes of unsupported algorithms never match.
+    pub fn verify(&self, hash: &str, password: &str) -> bool {
+        if is_bcrypt(hash) {
+            return pwhash::bcrypt::verify(password, hash);
+        }
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return false;
+        };
+        // Algorithm, version and parameters are taken from the hash itself.
+        return Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok();
+    }
+
+    /// Tells whether the hash uses another algorithm or parameters than configured.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        match self.algorithm {
+            PasswordHashAlgorithm::Argon2id => {
+                let Ok(parsed) = PasswordHash::new(hash) else {
+                    return true;
+                };
+                if
+                    parsed.algorithm != Algorithm::Argon2id.ident() ||
+                    parsed.version != Some(Version::V0x13.into())
+                {
+                    return true;
+                }
+                match Params::try_from(&parsed) {
+                    Ok(params) => {
+                        return params.m_cost() != self.argon2_params.m_cost() ||
+                            params.t_cost() != self.argon2_params.t_cost() ||
+                            params.p_cost() != self.argon2_params.p_cost();
+                    }
+                    Err(_) => {
+                        return true;
+                    }
+                }
+            }
+            PasswordHashAlgorithm::Bcrypt => {
+                return !is_bcrypt(hash) || bcrypt_cost(hash) != Some(self.bcrypt_cost);
+            }
+        }
+    }
+}
+
+fn is_bcrypt(hash: &str) -> bool {
+    return BCRYPT_PREFIXES.iter().any(|prefix| hash.starts_with(prefix));
+}
+
+/// Cost is the second field of `$2b$<cost>$<salt and hash>`.
+fn bcrypt_cost(hash: &str) -> Option<u32> {
+    return hash.split('$').nth(2)?.parse().ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use argon2::{
+        password_hash::SaltString,
+        Algorithm,
+        Argon2,
+        Params,
+        PasswordHasher as _,
+        Version,
+    };
+    use config::PasswordHashAlgorithm;
+    use rust_commons::rand::rngs::OsRng;
+
+    use super::PasswordHasher;
+
+    const PASSWORD: &str = "correct-Horse-battery-9";
+
+    /// Parameters are kept small, tests check the format rather than the strength.
+    fn hasher(
+        algorithm: PasswordHashAlgorithm,
+        memory_cost: u32,
+        bcrypt_cost: u32
+    ) -> PasswordHasher {
+        return PasswordHasher {
+            algorithm,
+            argon2_params: Params::new(memory_cost, 1, 1, None).unwrap(),
+            bcrypt_cost,
+        };
+    }
+
+    #[test]
+    fn argon2id_hash_is_verified() {
+        let hasher = hasher(PasswordHashAlgorithm::Argon2id, 1024, 4);
+        let hash = hasher.hash(PASSWORD).unwrap();
+
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(hasher.verify(&hash, PASSWORD));
+        assert!(!hasher.verify(&hash, "wrong-Horse-battery-9"));
+        assert!(!hasher.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn bcrypt_hash_is_verified() {
+        let hasher = hasher(PasswordHashAlgorithm::Bcrypt, 1024, 4);
+        let hash = hasher.hash(PASSWORD).unwrap();
+
+        assert!(hash.starts_with("$2b$04$"));
+        assert!(hasher.verify(&hash, PASSWORD));
+        assert!(!hasher.verify(&hash, "wrong-Horse-battery-9"));
+        assert!(!hasher.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn hashes_of_other_algorithm_are_verified_and_rehashed() {
+        let argon2id = hasher(PasswordHashAlgorithm::Argon2id, 1024, 4);
+        let bcrypt = hasher(PasswordHashAlgorithm::Bcrypt, 1024, 4);
+        let argon2id_hash = argon2id.hash(PASSWORD).unwrap();
+        let bcrypt_hash = bcrypt.hash(PASSWORD).unwrap();
+
+        assert!(argon2id.verify(&bcrypt_hash, PASSWORD));
+        assert!(argon2id.needs_rehash(&bcrypt_hash));
+        assert!(bcrypt.verify(&argon2id_hash, PASSWORD));
+        assert!(bcrypt.needs_rehash(&argon2id_hash));
+    }
+
+    #[test]
+    fn outdated_parameters_are_rehashed() {
+        let argon2id = hasher(PasswordHashAlgorithm::Argon2id, 2048, 4);
+        let bcrypt = hasher(PasswordHashAlgorithm::Bcrypt, 1024, 4);
+        let argon2id_hash = hasher(PasswordHashAlgorithm::Argon2id, 1024, 4)
+            .hash(PASSWORD)
+            .unwrap();
+        let bcrypt_hash = hasher(PasswordHashAlgorithm::Bcrypt, 1024, 5).hash(PASSWORD).unwrap();
+
+        assert!(argon2id.verify(&argon2id_hash, PASSWORD));
+        assert!(argon2id.needs_rehash(&argon2id_hash));
+        assert!(bcrypt.verify(&bcrypt_hash, PASSWORD));
+        assert!(bcrypt.needs_rehash(&bcrypt_hash));
+    }
+
+    #[test]
+    fn other_argon2_variant_is_rehashed() {
+        let hasher = hasher(PasswordHashAlgorithm::Argon2id, 1024, 4);
+        let hash = Argon2::new(Algorithm::Argon2i, Version::V0x13, hasher.argon2_params.clone())
+            .hash_password(PASSWORD.as_bytes(), &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+
+        assert!(hasher.verify(&hash, PASSWORD));
+        assert!(hasher.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn malformed_hash_never_matches() {
+        let hasher = hasher(PasswordHashAlgorithm::Argon2id, 1024, 4);
+
+        for hash in ["", "plain text", "$argon2id$v=19$broken", "$2b$04$short"] {
+            assert!(!hasher.verify(hash, PASSWORD));
+            assert!(hasher.needs_rehash(hash));
+        }
+    }
+}
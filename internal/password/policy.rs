@@ -0,0 +1,309 @@
+use std::{ borrow::Cow, collections::HashSet, fs, sync::Arc };
+
+use config::{ log::info, PasswordCharacterClass, CONFIGURATION };
+use sha1::{ Digest, Sha1 };
+use thiserror::Error;
+use validator::{ ValidationError, ValidationErrors };
+
+/// Requirements for new passwords, checked on registration, password reset and change.
+///
+/// Besides length and character classes the password must not contain the user's name or
+/// email, must reach `min_entropy` bits of estimated entropy and must not be in the list of
+/// breached passwords. The list holds uppercase SHA-1 hashes, one per line, optionally followed
+/// by `:count` as in the Have I Been Pwned downloads, and is kept in memory.
+pub struct PasswordPolicy {
+    min_length: usize,
+    required_classes: Vec<PasswordCharacterClass>,
+    min_entropy: f64,
+    breached_hashes: HashSet<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum PasswordPolicyError {
+    #[error("Failed to read breached password list [{0}] - {1}")] IoError(String, std::io::Error),
+}
+
+/// Parts of the name or email shorter than this are too common to reject passwords for.
+const MIN_PERSONAL_PART_LENGTH: usize = 3;
+
+impl PasswordPolicy {
+    pub fn from_configuration() -> Result<Arc<PasswordPolicy>, PasswordPolicyError> {
+        let path = &CONFIGURATION.password_breached_list_path;
+        let breached_hashes = if path.is_empty() {
+            HashSet::new()
+        } else {
+            let content = fs
+                ::read_to_string(path)
+                .map_err(|e| PasswordPolicyError::IoError(path.clone(), e))?;
+            let hashes = parse_breached_hashes(&content);
+            info!("Loaded {} breached password hashes from [{}]", hashes.len(), path);
+            hashes
+        };
+        return Ok(
+            Arc::new(PasswordPolicy {
+                min_length: CONFIGURATION.password_min_length,
+                required_classes: CONFIGURATION.password_required_classes.clone(),
+                min_entropy: CONFIGURATION.password_min_entropy,
+                breached_hashes,
+            })
+        );
+    }
+
+    /// Checks the password of the user with given name and email, every violation is reported
+    /// as an error of `field`.
+    pub fn validate(
+        &self,
+        field: &'static str,
+        password: &str,
+        name: &str,
+        email: &str
+    ) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if password.chars().count() < self.min_length {
+            errors.add(
+                field,
+                violation(
+                    "length",
+                    format!("Password must be at least {} characters long", self.min_length)
+                )
+            );
+        }
+        for class in &self.required_classes {
+            if !password.chars().any(|c| is_of_class(c, *class)) {
+                let message = format!("Password must contain {}", describe(*class));
+                errors.add(field, violation("character_class", message));
+            }
+        }
+        if contains_personal_info(password, name, email) {
+            errors.add(
+                field,
+                violation("personal_info", "Password must not contain your name or email".into())
+            );
+        }
+        if estimate_entropy(password) < self.min_entropy {
+            errors.add(
+                field,
+                violation(
+                    "strength",
+                    "Password is too weak, make it longer or less predictable".into()
+                )
+            );
+        }
+        if self.is_breached(password) {
+            errors.add(
+                field,
+                violation(
+                    "breached",
+                    "Password has appeared in a data breach, choose another one".into()
+                )
+            );
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        return Err(errors);
+    }
+
+    fn is_breached(&self, password: &str) -> bool {
+        if self.breached_hashes.is_empty() {
+            return false;
+        }
+        let hash: String = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        return self.breached_hashes.contains(&hash);
+    }
+}
+
+fn violation(code: &'static str, message: String) -> ValidationError {
+    return ValidationError::new(code).with_message(Cow::from(message));
+}
+
+fn parse_breached_hashes(content: &str) -> HashSet<String> {
+    return content
+        .lines()
+        .filter_map(|line| line.split(':').next())
+        .map(str::trim)
+        .filter(|hash| hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_uppercase)
+        .collect();
+}
+
+fn is_of_class(c: char, class: PasswordCharacterClass) -> bool {
+    match class {
+        PasswordCharacterClass::Lowercase => c.is_lowercase(),
+        PasswordCharacterClass::Uppercase => c.is_uppercase(),
+        PasswordCharacterClass::Digit => c.is_numeric(),
+        PasswordCharacterClass::Symbol => !c.is_alphanumeric(),
+    }
+}
+
+fn describe(class: PasswordCharacterClass) -> &'static str {
+    match class {
+        PasswordCharacterClass::Lowercase => "a lowercase letter",
+        PasswordCharacterClass::Uppercase => "an uppercase letter",
+        PasswordCharacterClass::Digit => "a digit",
+        PasswordCharacterClass::Symbol => "a symbol",
+    }
+}
+
+/// Compares case-insensitively with the whole email, its local part and every word of the name.
+fn contains_personal_info(password: &str, name: &str, email: &str) -> bool {
+    let password = password.to_lowercase();
+    let email = email.to_lowercase();
+    let local_part = email.split('@').next().unwrap_or_default();
+    let name = name.to_lowercase();
+    return name
+        .split_whitespace()
+        .chain([local_part, email.as_str()])
+        .filter(|part| part.chars().count() >= MIN_PERSONAL_PART_LENGTH)
+        .any(|part| password.contains(part));
+}
+
+/// Rough estimate in bits: every character adds log2 of the alphabet formed by the character
+/// classes used in the password. Characters repeating or continuing a run of the previous one
+/// (`aaaa`, `1234`, `dcba`) are easy to guess and add a single bit.
+fn estimate_entropy(password: &str) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+    let mut alphabet = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        alphabet += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        alphabet += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        alphabet += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii() && !c.is_ascii_alphanumeric()) {
+        alphabet += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        alphabet += 100;
+    }
+    if alphabet == 0 {
+        return 0.0;
+    }
+    let bits_per_char = f64::from(alphabet).log2();
+    let mut entropy = 0.0;
+    for (index, c) in chars.iter().enumerate() {
+        let predictable = index > 0 && u32::from(*c).abs_diff(u32::from(chars[index - 1])) <= 1;
+        entropy += if predictable { 1.0 } else { bits_per_char };
+    }
+    return entropy;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use config::PasswordCharacterClass;
+
+    use super::{ estimate_entropy, parse_breached_hashes, PasswordPolicy };
+
+    /// SHA-1 of `password`.
+    const PASSWORD_HASH: &str = "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8";
+
+    fn policy() -> PasswordPolicy {
+        return PasswordPolicy {
+            min_length: 12,
+            required_classes: vec![
+                PasswordCharacterClass::Uppercase,
+                PasswordCharacterClass::Digit,
+                PasswordCharacterClass::Symbol
+            ],
+            min_entropy: 0.0,
+            breached_hashes: HashSet::new(),
+        };
+    }
+
+    /// Codes of the violations, in the order they are checked.
+    fn violations(policy: &PasswordPolicy, password: &str, name: &str, email: &str) -> Vec<String> {
+        let Err(errors) = policy.validate("password", password, name, email) else {
+            return Vec::new();
+        };
+        return errors
+            .field_errors()
+            .get("password")
+            .map(|errors| errors.iter().map(|error| error.code.to_string()).collect())
+            .unwrap_or_default();
+    }
+
+    #[test]
+    fn length_and_character_classes_are_required() {
+        let policy = policy();
+
+        assert_eq!(
+            violations(&policy, "short", "Jane Doe", "jane@example.com"),
+            ["length", "character_class", "character_class", "character_class"]
+        );
+        assert!(violations(&policy, "shortPass-42", "Jane Doe", "jane@example.com").is_empty());
+        assert_eq!(
+            violations(&policy, "longer-password-42", "Jane Doe", "jane@example.com"),
+            ["character_class"]
+        );
+    }
+
+    #[test]
+    fn name_and_email_are_not_allowed() {
+        let policy = policy();
+        let personal_info = |password: &str| {
+            return violations(&policy, password, "Jane Al Doe", "jd0e@Example.com").contains(
+                &"personal_info".to_string()
+            );
+        };
+
+        assert!(personal_info("Long-JANE-pass-42"));
+        assert!(personal_info("Long-doe-pass-42"));
+        assert!(personal_info("Long-JD0E-pass-42"));
+        assert!(personal_info("x-jd0e@example.com-1"));
+        // Parts shorter than three characters are too common to reject.
+        assert!(!personal_info("Long-al-pass-42!"));
+    }
+
+    #[test]
+    fn predictable_characters_add_little_entropy() {
+        assert_eq!(estimate_entropy(""), 0.0);
+        assert!(estimate_entropy("aaaaaaaaaaaa") < 16.0);
+        assert!(estimate_entropy("abcdefghijkl") < 16.0);
+        assert!(estimate_entropy("qmzrtxakfpeh") > 55.0);
+        assert!(estimate_entropy("aB3$") > estimate_entropy("abcd"));
+
+        let policy = PasswordPolicy { min_entropy: 40.0, ..policy() };
+        assert_eq!(violations(&policy, "Aaaaaaaaaa1!", "Jane", "jane@example.com"), ["strength"]);
+    }
+
+    #[test]
+    fn breached_list_is_parsed() {
+        let hashes = parse_breached_hashes(
+            &format!(
+                "{}:3861493\n\n{}\nnot a hash:12\n{}",
+                PASSWORD_HASH,
+                "7c4a8d09ca3762af61e59520943dc26494f8941b",
+                "ABC"
+            )
+        );
+
+        assert_eq!(
+            hashes,
+            HashSet::from([
+                PASSWORD_HASH.to_string(),
+                "7C4A8D09CA3762AF61E59520943DC26494F8941B".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn breached_password_is_rejected() {
+        let policy = PasswordPolicy {
+            min_length: 0,
+            required_classes: Vec::new(),
+            breached_hashes: parse_breached_hashes(PASSWORD_HASH),
+            ..policy()
+        };
+
+        assert_eq!(violations(&policy, "password", "Jane", "jane@example.com"), ["breached"]);
+        assert!(violations(&policy, "Password", "Jane", "jane@example.com").is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use core::error;
+use std::sync::Arc;
+
+use chrono::{ TimeDelta, Utc };
+use rust_commons::rand::{ self, distributions::Alphanumeric, Rng };
+use thiserror::Error;
+
+use crate::infra::{
+    database::api_key_repository::{ ApiKey, ApiKeyRepository },
+    domain::{ api_key::ApiKeyDTO, role::UserRolesDTO },
+    http::requests::api_key_request::CreateApiKeyRequest,
+};
+
+use super::{ generate_token, hash_token };
+
+const API_KEY_PREFIX: &str = "rab";
+const API_KEY_PREFIX_LENGTH: usize = 8;
+/// `last_used_at` is updated at most once per this many seconds.
+const LAST_USED_PRECISION: i64 = 60;
+
+pub struct ApiKeyService {
+    api_key_repository: Arc<ApiKeyRepository>,
+}
+
+#[derive(Error, Debug)]
+pub enum ApiKeyServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+}
+
+impl ApiKeyService {
+    pub fn new(api_key_repository: Arc<ApiKeyRepository>) -> Arc<ApiKeyService> {
+        return Arc::new(ApiKeyService { api_key_repository });
+    }
+
+    /// Creates a key and returns it together with the plain secret, which is not stored.
+    pub fn create(
+        &self,
+        user_id: Arc<i32>,
+        user_roles: &UserRolesDTO,
+        request: CreateApiKeyRequest
+    ) -> Result<(ApiKey, String), ApiKeyServiceError> {
+        if let Some(scope) = request.scopes.iter().find(|scope| !user_roles.has_permission(scope)) {
+            return Err(
+                ApiKeyServiceError::ServiceError(
+                    Box::from(format!("Scope [{}] is not granted to the user", scope))
+                )
+            );
+        }
+        let prefix: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(API_KEY_PREFIX_LENGTH)
+            .map(char::from)
+            .collect();
+        let key = format!("{}_{}_{}", API_KEY_PREFIX, prefix, generate_token());
+        let api_key = self.api_key_repository
+            .save(ApiKeyDTO {
+                user_id,
+                name: Arc::from(request.name),
+                prefix: Arc::from(prefix),
+                secret_hash: Arc::from(hash_token(&key)),
+                scopes: request.scopes.into_iter().map(Arc::from).collect(),
+                expires_at: request.expires_in_days.map(|days| {
+                    Utc::now().naive_utc() + TimeDelta::days(days as i64)
+                }),
+            })
+            .map_err(ApiKeyServiceError::DieselError)?;
+        return Ok((api_key, key));
+    }
+
+    pub fn find_by_user_id(&self, user_id: Arc<i32>) -> Result<Vec<ApiKey>, ApiKeyServiceError> {
+        return self.api_key_repository
+            .find_by_user_id(user_id)
+            .map_err(ApiKeyServiceError::DieselError);
+    }
+
+    /// Returns `false` when the user has no key with provided id.
+    pub fn revoke(&self, user_id: Arc<i32>, api_key_id: i32) -> Result<bool, ApiKeyServiceError> {
+        return self.api_key_repository
+            .delete(user_id, api_key_id)
+            .map_err(ApiKeyServiceError::DieselError);
+    }
+
+    /// Tells API keys apart from access tokens without a database lookup.
+    pub fn is_api_key(key: &str) -> bool {
+        return key
+            .strip_prefix(API_KEY_PREFIX)
+            .is_some_and(|rest| rest.starts_with('_'));
+    }
+
+    /// Resolves a plain key to the stored one and records its usage.
+    pub fn authenticate(&self, key: &str) -> Result<ApiKey, ApiKeyServiceError> {
+        let api_key = self.find_active(key)?;
+        let now = Utc::now().naive_utc();
+        self.api_key_repository
+            .touch(api_key.id, now, now - TimeDelta::seconds(LAST_USED_PRECISION))
+            .map_err(ApiKeyServiceError::DieselError)?;
+        return Ok(api_key);
+    }
+
+    /// Resolves a plain key to the stored one without recording its usage.
+    pub fn find_active(&self, key: &str) -> Result<ApiKey, ApiKeyServiceError> {
+        let api_key = self.api_key_repository
+            .find_by_hash(&hash_token(key))
+            .map_err(|_| ApiKeyServiceError::ServiceError(Box::from("Invalid API key")))?;
+        if api_key.expires_at.is_some_and(|expires_at| expires_at <= Utc::now().naive_utc()) {
+            return Err(ApiKeyServiceError::ServiceError(Box::from("API key expired")));
+        }
+        return Ok(api_key);
+    }
+}
@@ -1,67 +1,178 @@
 use core::error;
 use std::{ sync::Arc, time::{ Duration, SystemTime, UNIX_EPOCH } };
 
-use config::CONFIGURATION;
-use jsonwebtoken::{ EncodingKey, Header };
+use chrono::{ NaiveDateTime, TimeDelta, Utc };
+use config::{ log::{ error, warn }, EmailVerificationPolicy, CONFIGURATION };
+use jsonwebtoken::jwk::JwkSet;
 use serde::{ Deserialize, Serialize };
 use thiserror::Error;
-use rust_commons::{ base64::{ self, Engine }, pwhash, uuid::Uuid };
-use rust_commons::crypto::bcrypt::{ verify_password, hash_password };
+use validator::ValidationErrors;
+use rust_commons::{ base64::{ self, Engine }, uuid::Uuid };
 
 use crate::{
+    cache::auth_cache::{ AuthCache, AuthCacheStats },
     filesystem::image_storage_service::ImageStorageService,
     infra::{
         database::{
+            impersonation_event_repository::{ ImpersonationEvent, ImpersonationEventRepository },
+            magic_link_repository::MagicLinkRepository,
+            password_reset_repository::PasswordResetRepository,
+            refresh_token_repository::RefreshTokenRepository,
             session_repository::{ Session, SessionRepository },
             user_repository::UserRepository,
         },
-        domain::{ session::SessionDTO, user::{ AuthenticatedUserDTO, UserDTO } },
+        domain::{
+            impersonation::{
+                ImpersonationEventDTO,
+                ImpersonationTokenDTO,
+                IMPERSONATION_STARTED,
+                IMPERSONATION_STOPPED,
+            },
+            login_event::{ ACCOUNT_LOCKED, INVALID_PASSWORD, INVALID_TWO_FACTOR_CODE },
+            magic_link::MagicLinkDTO,
+            password_reset::PasswordResetDTO,
+            refresh_token::RefreshTokenDTO,
+            session::{ AuthTokensDTO, ClientInfoDTO, SessionDTO },
+            two_factor::TwoFactorChallengeDTO,
+            user::{ AuthenticatedUserDTO, LoginDTO, UserDTO },
+        },
         http::{
-            requests::user_request::{ AuthRequest, UserRequest },
+            requests::{
+                auth_request::ResetPasswordRequest,
+                user_request::{ AuthRequest, UserRequest },
+            },
             resources::user_resource::UserResponse,
         },
     },
+    jwt::JwtKeys,
+    notifications::{ Notification, Notifier },
+    password::{ policy::PasswordPolicy, PasswordHasher, PasswordHasherError },
 };
 
-use super::user_image_name;
+use super::{
+    email_verification_service::EmailVerificationService,
+    login_event_service::{ LoginEventService, LoginEventServiceError },
+    login_throttle_service::{ LoginThrottleService, LoginThrottleServiceError },
+    two_factor_service::TwoFactorService,
+    generate_token,
+    hash_token,
+    user_image_name,
+};
 
 #[derive(Serialize, Clone, Deserialize)]
 pub struct Claims {
     pub user_id: Arc<i32>,
     pub uuid: Uuid,
     pub exp: usize,
+    /// Real user behind an impersonation token, `user_id` holds the impersonated user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor_id: Option<Arc<i32>>,
+}
+
+const TWO_FACTOR_CHALLENGE_PURPOSE: &str = "two_factor_challenge";
+
+/// Short-lived token proving the password step of a login, exchanged for a session
+/// once the second factor is verified.
+#[derive(Serialize, Deserialize)]
+struct TwoFactorChallengeClaims {
+    user_id: i32,
+    purpose: String,
+    /// Id of the stored challenge, which is deleted once the token is used.
+    jti: Uuid,
+    exp: usize,
 }
 
 pub struct AuthService {
     user_repository: Arc<UserRepository>,
     session_repository: Arc<SessionRepository>,
+    refresh_token_repository: Arc<RefreshTokenRepository>,
+    password_reset_repository: Arc<PasswordResetRepository>,
+    magic_link_repository: Arc<MagicLinkRepository>,
+    impersonation_event_repository: Arc<ImpersonationEventRepository>,
     file_system: Arc<ImageStorageService>,
+    notifier: Arc<dyn Notifier>,
+    email_verification_service: Arc<EmailVerificationService>,
+    two_factor_service: Arc<TwoFactorService>,
+    login_throttle_service: Arc<LoginThrottleService>,
+    login_event_service: Arc<LoginEventService>,
+    jwt_keys: Arc<JwtKeys>,
+    password_hasher: Arc<PasswordHasher>,
+    password_policy: Arc<PasswordPolicy>,
+    auth_cache: Arc<AuthCache>,
 }
 
 #[derive(Error, Debug)]
 pub enum AuthServiceError {
     #[error("{0}")] DieselError(diesel::result::Error),
-    #[error("{0}")] ArgonError(pwhash::error::Error),
+    #[error("{0}")] PasswordHashError(PasswordHasherError),
+    /// Reported to the client as `field_errors`.
+    #[error("{0}")] PasswordPolicyError(ValidationErrors),
     #[error("{0}")] JWTError(jsonwebtoken::errors::Error),
     #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+    /// Holds seconds until the next login attempt is allowed.
+    #[error("Too many attempts, try again in {0} seconds")] LockedError(u64),
+}
+
+impl From<LoginThrottleServiceError> for AuthServiceError {
+    fn from(error: LoginThrottleServiceError) -> Self {
+        match error {
+            LoginThrottleServiceError::LockedError(seconds) => {
+                AuthServiceError::LockedError(seconds)
+            }
+            _ => AuthServiceError::ServiceError(Box::new(error)),
+        }
+    }
+}
+
+impl From<LoginEventServiceError> for AuthServiceError {
+    fn from(error: LoginEventServiceError) -> Self {
+        return AuthServiceError::ServiceError(Box::new(error));
+    }
 }
 
 impl AuthService {
     pub fn new(
         user_repository: Arc<UserRepository>,
         session_repository: Arc<SessionRepository>,
-        file_system: Arc<ImageStorageService>
+        refresh_token_repository: Arc<RefreshTokenRepository>,
+        password_reset_repository: Arc<PasswordResetRepository>,
+        magic_link_repository: Arc<MagicLinkRepository>,
+        impersonation_event_repository: Arc<ImpersonationEventRepository>,
+        file_system: Arc<ImageStorageService>,
+        notifier: Arc<dyn Notifier>,
+        email_verification_service: Arc<EmailVerificationService>,
+        two_factor_service: Arc<TwoFactorService>,
+        login_throttle_service: Arc<LoginThrottleService>,
+        login_event_service: Arc<LoginEventService>,
+        jwt_keys: Arc<JwtKeys>,
+        password_hasher: Arc<PasswordHasher>,
+        password_policy: Arc<PasswordPolicy>,
+        auth_cache: Arc<AuthCache>
     ) -> Arc<AuthService> {
         return Arc::new(AuthService {
             session_repository,
+            refresh_token_repository,
+            password_reset_repository,
+            magic_link_repository,
+            impersonation_event_repository,
             user_repository,
             file_system,
+            notifier,
+            email_verification_service,
+            two_factor_service,
+            login_throttle_service,
+            login_event_service,
+            jwt_keys,
+            password_hasher,
+            password_policy,
+            auth_cache,
         });
     }
 
     pub async fn register(
         &self,
-        mut user: UserRequest
+        mut user: UserRequest,
+        client_info: ClientInfoDTO
     ) -> Result<AuthenticatedUserDTO, AuthServiceError> {
         if self.user_repository.find_by_email(&user.email).is_ok() {
             return Err(
@@ -70,8 +181,12 @@ impl AuthService {
                 )
             );
         }
-        let hashed_password = hash_password(&user.password).map_err(AuthServiceError::ArgonError)?;
-        user.password = hashed_password;
+        self.password_policy
+            .validate("password", &user.password, &user.name, &user.email)
+            .map_err(AuthServiceError::PasswordPolicyError)?;
+        user.password = self.password_hasher
+            .hash(&user.password)
+            .map_err(AuthServiceError::PasswordHashError)?;
 
         if let Some(avatar_base64) = &user.avatar {
             if
@@ -90,70 +205,627 @@ impl AuthService {
             .create_user(&user)
             .map_err(AuthServiceError::DieselError)?;
 
-        let token = self.generate_jwt(Arc::from(saved_user.id))?;
-        return Ok(AuthenticatedUserDTO {
-            user: UserResponse::user_to_response(&saved_user),
-            token: Arc::from(token),
-        });
+        let user_dto = UserDTO::model_to_dto(saved_user);
+        self.email_verification_service
+            .send_verification(&user_dto)
+            .map_err(|e| AuthServiceError::ServiceError(Box::new(e)))?;
+
+        if CONFIGURATION.email_verification_policy == EmailVerificationPolicy::Login {
+            return Ok(AuthenticatedUserDTO {
+                user: UserResponse::dto_to_response(&user_dto),
+                token: None,
+                refresh_token: None,
+            });
+        }
+        return self.authenticate(&user_dto, &client_info);
     }
 
     pub fn login(
         &self,
-        request_user: AuthRequest
-    ) -> Result<AuthenticatedUserDTO, AuthServiceError> {
-        let user = self.user_repository
-            .find_by_email(&request_user.email)
-            .map_err(AuthServiceError::DieselError)?;
+        request_user: AuthRequest,
+        client_info: ClientInfoDTO
+    ) -> Result<LoginDTO, AuthServiceError> {
+        let client_ip = client_info.client_ip.as_deref();
+        self.login_throttle_service.check_ip(client_ip)?;
+        let user = match self.user_repository.find_by_email(&request_user.email) {
+            Ok(user) => user,
+            Err(e) => {
+                if e == diesel::result::Error::NotFound {
+                    self.login_throttle_service.register_failure(None, client_ip)?;
+                }
+                return Err(AuthServiceError::DieselError(e));
+            }
+        };
+        let user_dto = UserDTO::model_to_dto(user);
+        let user_id = user_dto.id.unwrap();
+        if let Err(e) = self.login_throttle_service.check_account(&user_dto) {
+            let user_id = Arc::new(user_id);
+            self.login_event_service.record_failure(user_id, ACCOUNT_LOCKED, &client_info);
+            return Err(e.into());
+        }
 
-        if verify_password(&user.password, &request_user.password) {
-            let user_dto = UserDTO::model_to_dto(user);
-            let token = self.generate_jwt(Arc::new(user_dto.id.unwrap()))?;
-            return Ok(AuthenticatedUserDTO {
-                user: UserResponse::dto_to_response(&user_dto),
-                token: Arc::from(token.to_string()),
-            });
+        if self.password_hasher.verify(&user_dto.password, &request_user.password) {
+            if self.password_hasher.needs_rehash(&user_dto.password) {
+                self.rehash_password(Arc::new(user_id), &request_user.password);
+            }
+            return self.complete_login(&user_dto, &client_info);
         }
 
+        // Registered first, so that a failure to record history can not skip the lockout.
+        self.login_throttle_service.register_failure(Some(Arc::new(user_id)), client_ip)?;
+        self.login_event_service.record_failure(Arc::new(user_id), INVALID_PASSWORD, &client_info);
         return Err(AuthServiceError::ServiceError(Box::from("Invalid password")));
     }
 
+    /// Finishes login of a user whose identity is already proven, either by password or by an
+    /// external identity provider. Issues the two-factor challenge when it is enabled.
+    pub fn complete_login(
+        &self,
+        user_dto: &UserDTO,
+        client_info: &ClientInfoDTO
+    ) -> Result<LoginDTO, AuthServiceError> {
+        let user_id = user_dto.id.unwrap();
+        if
+            CONFIGURATION.email_verification_policy == EmailVerificationPolicy::Login &&
+            !user_dto.is_email_verified()
+        {
+            return Err(AuthServiceError::ServiceError(Box::from("Email is not verified")));
+        }
+        let two_factor_enabled = self.two_factor_service
+            .is_enabled(Arc::new(user_id))
+            .map_err(|e| AuthServiceError::ServiceError(Box::new(e)))?;
+        if two_factor_enabled {
+            return Ok(
+                LoginDTO::TwoFactorRequired(TwoFactorChallengeDTO {
+                    two_factor_required: true,
+                    challenge_token: Arc::from(self.encode_two_factor_challenge(user_id)?),
+                })
+            );
+        }
+        self.login_throttle_service.register_success(Arc::new(user_id))?;
+        self.login_event_service.record_success(user_dto, client_info);
+        return Ok(LoginDTO::Authenticated(self.authenticate(user_dto, client_info)?));
+    }
+
+    /// Completes a login started with valid credentials by checking a TOTP or recovery code.
+    pub fn verify_two_factor(
+        &self,
+        challenge_token: &str,
+        code: &str,
+        client_info: ClientInfoDTO
+    ) -> Result<AuthenticatedUserDTO, AuthServiceError> {
+        let claims = self.jwt_keys
+            .decode::<TwoFactorChallengeClaims>(challenge_token)
+            .map_err(|_| AuthServiceError::ServiceError(Box::from("Invalid challenge token")))?;
+        let challenge_active = claims.purpose == TWO_FACTOR_CHALLENGE_PURPOSE &&
+            self.two_factor_service
+                .is_challenge_active(Arc::new(claims.user_id), claims.jti)
+                .map_err(|e| AuthServiceError::ServiceError(Box::new(e)))?;
+        if !challenge_active {
+            return Err(AuthServiceError::ServiceError(Box::from("Invalid challenge token")));
+        }
+
+        let user_dto = UserDTO::model_to_dto(
+            self.user_repository
+                .find_by_id(Arc::new(claims.user_id))
+                .map_err(AuthServiceError::DieselError)?
+        );
+        let client_ip = client_info.client_ip.as_deref();
+        self.login_throttle_service.check_ip(client_ip)?;
+        self.login_throttle_service.check_account(&user_dto)?;
+        let valid = self.two_factor_service
+            .verify_login_code(&user_dto, code)
+            .map_err(|e| AuthServiceError::ServiceError(Box::new(e)))?;
+        if !valid {
+            self.login_throttle_service.register_failure(
+                Some(Arc::new(claims.user_id)),
+                client_ip
+            )?;
+            self.login_event_service.record_failure(
+                Arc::new(claims.user_id),
+                INVALID_TWO_FACTOR_CODE,
+                &client_info
+            );
+            return Err(AuthServiceError::ServiceError(Box::from("Invalid code")));
+        }
+        // Concurrent exchanges of the same token may all pass the check above, only one wins.
+        let consumed = self.two_factor_service
+            .consume_challenge(Arc::new(claims.user_id), claims.jti)
+            .map_err(|e| AuthServiceError::ServiceError(Box::new(e)))?;
+        if !consumed {
+            return Err(AuthServiceError::ServiceError(Box::from("Invalid challenge token")));
+        }
+        self.login_throttle_service.register_success(Arc::new(claims.user_id))?;
+        self.login_event_service.record_success(&user_dto, &client_info);
+        return self.authenticate(&user_dto, &client_info);
+    }
+
+    /// Rotates refresh token. Presenting an already used token revokes the whole session,
+    /// as it means the token was leaked and replayed.
+    pub fn refresh(&self, refresh_token: &str) -> Result<AuthTokensDTO, AuthServiceError> {
+        let stored_token = self.refresh_token_repository
+            .find_by_hash(&hash_token(refresh_token))
+            .map_err(|_| AuthServiceError::ServiceError(Box::from("Invalid refresh token")))?;
+        let session = SessionDTO::new(Arc::new(stored_token.user_id), stored_token.session_uuid);
+        // Sessions of a deleted account must not mint new access tokens.
+        match self.user_repository.find_by_id(session.user_id.clone()) {
+            Ok(_) => {}
+            Err(diesel::result::Error::NotFound) => {
+                let (user_id, uuid) = (*session.user_id, session.uuid);
+                self.session_repository.delete(session).map_err(AuthServiceError::DieselError)?;
+                self.auth_cache.invalidate_session(user_id, &uuid);
+                return Err(AuthServiceError::ServiceError(Box::from("Invalid refresh token")));
+            }
+            Err(e) => {
+                return Err(AuthServiceError::DieselError(e));
+            }
+        }
+
+        let now = Utc::now().naive_utc();
+        if stored_token.used_at.is_none() && stored_token.expires_at < now {
+            return Err(AuthServiceError::ServiceError(Box::from("Refresh token expired")));
+        }
+        if
+            stored_token.used_at.is_some() ||
+            !self.refresh_token_repository
+                .mark_used(stored_token.id, now)
+                .map_err(AuthServiceError::DieselError)?
+        {
+            let (user_id, uuid) = (*session.user_id, session.uuid);
+            self.session_repository.delete(session).map_err(AuthServiceError::DieselError)?;
+            self.auth_cache.invalidate_session(user_id, &uuid);
+            return Err(
+                AuthServiceError::ServiceError(
+                    Box::from("Refresh token reuse detected, session has been revoked")
+                )
+            );
+        }
+
+        let saved_session = self.session_repository
+            .touch(&session, session_expires_at())
+            .map_err(AuthServiceError::DieselError)?;
+        return Ok(AuthTokensDTO {
+            token: Arc::from(self.encode_jwt(&saved_session)?),
+            refresh_token: Arc::from(self.issue_refresh_token(&saved_session)?),
+        });
+    }
+
+    /// The cached session is dropped after the delete. A concurrent `check` that read the
+    /// session before the delete does not cache it again, see `AuthCache::generation`.
     pub fn logout(&self, session: SessionDTO) -> Result<(), AuthServiceError> {
+        let (user_id, uuid) = (*session.user_id, session.uuid);
         self.session_repository.delete(session).map_err(AuthServiceError::DieselError)?;
+        self.auth_cache.invalidate_session(user_id, &uuid);
         return Ok(());
     }
 
-    pub fn check(&self, session: Claims) -> bool {
+    /// Logs out the session the refresh token was issued for. Used or expired tokens still
+    /// identify their session, so a client with stale tokens can end it too.
+    pub fn logout_by_refresh_token(&self, refresh_token: &str) -> Result<(), AuthServiceError> {
+        let stored_token = self.refresh_token_repository
+            .find_by_hash(&hash_token(refresh_token))
+            .map_err(|_| AuthServiceError::ServiceError(Box::from("Invalid refresh token")))?;
+        return self.logout(
+            SessionDTO::new(Arc::new(stored_token.user_id), stored_token.session_uuid)
+        );
+    }
+
+    /// Sends single-use password reset token to the user. Unknown emails and delivery failures
+    /// are silently ignored, so the endpoint can not be used to find out registered addresses.
+    pub fn forgot_password(&self, email: &str) -> Result<(), AuthServiceError> {
+        let user = match self.user_repository.find_by_email(email) {
+            Ok(user) => user,
+            Err(diesel::result::Error::NotFound) => {
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(AuthServiceError::DieselError(e));
+            }
+        };
+        let user_id = Arc::new(user.id);
+        self.password_reset_repository
+            .delete_by_user_id(user_id.clone())
+            .map_err(AuthServiceError::DieselError)?;
+
+        let token = generate_token();
+        self.password_reset_repository
+            .save(PasswordResetDTO {
+                user_id,
+                token_hash: Arc::from(hash_token(&token)),
+                expires_at: Utc::now().naive_utc() +
+                TimeDelta::seconds(CONFIGURATION.password_reset_ttl as i64),
+            })
+            .map_err(AuthServiceError::DieselError)?;
+        let notification = Notification::PasswordReset {
+            email: Arc::from(user.email),
+            name: Arc::from(user.name),
+            token: Arc::from(token),
+            locale: user.locale.map(Arc::from),
+        };
+        if let Err(e) = self.notifier.notify(notification) {
+            error!("Failed to send password reset to user [{}] - {}", user.id, e);
+        }
+        return Ok(());
+    }
+
+    /// Sets new password and logs the user out of every session.
+    pub fn reset_password(&self, request: ResetPasswordRequest) -> Result<(), AuthServiceError> {
+        let invalid_token = || {
+            AuthServiceError::ServiceError(Box::from("Invalid or expired reset token"))
+        };
+        let reset = self.password_reset_repository
+            .find_by_hash(&hash_token(&request.token))
+            .map_err(|_| invalid_token())?;
+        let now = Utc::now().naive_utc();
+        if reset.used_at.is_some() || reset.expires_at < now {
+            return Err(invalid_token());
+        }
+        // Checked before the token is used up, so the user can retry with another password.
+        // Soft-deleted users are not found, so their pending tokens are rejected.
+        let user = self.user_repository
+            .find_by_id(Arc::new(reset.user_id))
+            .map_err(|_| invalid_token())?;
+        self.password_policy
+            .validate("password", &request.password, &user.name, &user.email)
+            .map_err(AuthServiceError::PasswordPolicyError)?;
         if
-            self.session_repository
-                .exists(SessionDTO {
-                    user_id: session.user_id,
-                    uuid: session.uuid,
-                })
-                .is_ok()
+            !self.password_reset_repository
+                .mark_used(reset.id, now)
+                .map_err(AuthServiceError::DieselError)?
+        {
+            return Err(invalid_token());
+        }
+
+        let user_id = Arc::new(reset.user_id);
+        let hashed_password = self.password_hasher
+            .hash(&request.password)
+            .map_err(AuthServiceError::PasswordHashError)?;
+        self.user_repository
+            .update_password(user_id.clone(), &hashed_password)
+            .map_err(AuthServiceError::DieselError)?;
+        self.session_repository
+            .delete_by_user_id(user_id.clone(), None)
+            .map_err(AuthServiceError::DieselError)?;
+        self.auth_cache.invalidate_user(*user_id);
+        return Ok(());
+    }
+
+    /// Sends single-use login link to the user. Like `forgot_password`, unknown emails and
+    /// delivery failures are silently ignored. Links sent before stay valid until they are used
+    /// or expire, so requests made by someone else do not invalidate the link the user is about
+    /// to open.
+    pub fn send_magic_link(
+        &self,
+        email: &str,
+        client_ip: Option<&str>
+    ) -> Result<(), AuthServiceError> {
+        self.login_throttle_service.register_magic_link_request(email, client_ip)?;
+        let user = match self.user_repository.find_by_email(email) {
+            Ok(user) => user,
+            Err(diesel::result::Error::NotFound) => {
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(AuthServiceError::DieselError(e));
+            }
+        };
+        let user_id = Arc::new(user.id);
+        let token = generate_token();
+        self.magic_link_repository
+            .save(MagicLinkDTO {
+                user_id,
+                email: Arc::from(user.email.as_str()),
+                token_hash: Arc::from(hash_token(&token)),
+                expires_at: Utc::now().naive_utc() +
+                TimeDelta::seconds(CONFIGURATION.magic_link_ttl as i64),
+            })
+            .map_err(AuthServiceError::DieselError)?;
+        let notification = Notification::MagicLink {
+            email: Arc::from(user.email),
+            name: Arc::from(user.name),
+            token: Arc::from(token),
+            locale: user.locale.map(Arc::from),
+        };
+        if let Err(e) = self.notifier.notify(notification) {
+            error!("Failed to send login link to user [{}] - {}", user.id, e);
+        }
+        return Ok(());
+    }
+
+    /// Logs the user in with a token from the login link. The link is rejected once used,
+    /// after it expires or when the user's email no longer matches the one it was sent to.
+    pub fn consume_magic_link(
+        &self,
+        token: &str,
+        client_info: ClientInfoDTO
+    ) -> Result<LoginDTO, AuthServiceError> {
+        let invalid_token = || {
+            AuthServiceError::ServiceError(Box::from("Invalid or expired login link"))
+        };
+        let link = self.magic_link_repository
+            .find_by_hash(&hash_token(token))
+            .map_err(|_| invalid_token())?;
+        let now = Utc::now().naive_utc();
+        if link.used_at.is_some() || link.expires_at < now {
+            return Err(invalid_token());
+        }
+        let user_dto = UserDTO::model_to_dto(
+            self.user_repository
+                .find_by_id(Arc::new(link.user_id))
+                .map_err(|_| invalid_token())?
+        );
+        if !user_dto.email.eq_ignore_ascii_case(&link.email) {
+            return Err(invalid_token());
+        }
+        self.login_throttle_service.check_account(&user_dto)?;
+        if
+            !self.magic_link_repository
+                .mark_used(link.id, now)
+                .map_err(AuthServiceError::DieselError)?
         {
+            return Err(invalid_token());
+        }
+        return self.complete_login(&user_dto, &client_info);
+    }
+
+    /// Opens a short-lived session of the user on behalf of the actor. The token carries
+    /// both ids and can not be refreshed. Every impersonation is recorded in the audit trail.
+    pub fn impersonate(
+        &self,
+        actor_id: Arc<i32>,
+        user_id: Arc<i32>,
+        reason: &str,
+        client_info: &ClientInfoDTO
+    ) -> Result<ImpersonationTokenDTO, AuthServiceError> {
+        if actor_id == user_id {
+            return Err(
+                AuthServiceError::ServiceError(Box::from("You can not impersonate yourself"))
+            );
+        }
+        let user = self.user_repository
+            .find_by_id(user_id.clone())
+            .map_err(AuthServiceError::DieselError)?;
+        let user_dto = UserDTO::model_to_dto(user);
+        let expires_at =
+            Utc::now().naive_utc() + TimeDelta::seconds(CONFIGURATION.impersonation_ttl as i64);
+        let session = self.session_repository
+            .save(SessionDTO::new(user_id.clone(), Uuid::new_v4()), client_info, expires_at)
+            .map_err(AuthServiceError::DieselError)?;
+        let claims = Claims {
+            user_id: user_id.clone(),
+            uuid: session.uuid,
+            exp: expires_at.and_utc().timestamp() as usize,
+            actor_id: Some(actor_id.clone()),
+        };
+        let token = self.jwt_keys.encode(&claims).map_err(AuthServiceError::JWTError)?;
+        self.impersonation_event_repository
+            .save(ImpersonationEventDTO {
+                actor_id,
+                user_id,
+                session_uuid: session.uuid,
+                event: IMPERSONATION_STARTED,
+                reason: Some(Arc::from(reason)),
+                client_ip: client_info.client_ip.clone(),
+                expires_at: Some(expires_at),
+            })
+            .map_err(AuthServiceError::DieselError)?;
+        return Ok(ImpersonationTokenDTO {
+            user: UserResponse::dto_to_response(&user_dto),
+            token: Arc::from(token),
+            expires_at,
+        });
+    }
+
+    /// Revokes the impersonation session of provided claims and records it in the audit trail.
+    pub fn stop_impersonation(
+        &self,
+        claims: &Claims,
+        client_info: &ClientInfoDTO
+    ) -> Result<(), AuthServiceError> {
+        let Some(actor_id) = claims.actor_id.clone() else {
+            return Err(AuthServiceError::ServiceError(Box::from("Not impersonating")));
+        };
+        self.session_repository
+            .delete(SessionDTO::new(claims.user_id.clone(), claims.uuid))
+            .map_err(AuthServiceError::DieselError)?;
+        self.auth_cache.invalidate_session(*claims.user_id, &claims.uuid);
+        self.impersonation_event_repository
+            .save(ImpersonationEventDTO {
+                actor_id,
+                user_id: claims.user_id.clone(),
+                session_uuid: claims.uuid,
+                event: IMPERSONATION_STOPPED,
+                reason: None,
+                client_ip: client_info.client_ip.clone(),
+                expires_at: None,
+            })
+            .map_err(AuthServiceError::DieselError)?;
+        return Ok(());
+    }
+
+    pub fn find_impersonation_events(
+        &self,
+        user_id: Arc<i32>
+    ) -> Result<Vec<ImpersonationEvent>, AuthServiceError> {
+        return self.impersonation_event_repository
+            .find_by_user_id(user_id)
+            .map_err(AuthServiceError::DieselError);
+    }
+
+    pub fn find_sessions(&self, user_id: Arc<i32>) -> Result<Vec<Session>, AuthServiceError> {
+        return self.session_repository
+            .find_by_user_id(user_id)
+            .map_err(AuthServiceError::DieselError);
+    }
+
+    pub fn find_session(&self, session: SessionDTO) -> Result<Session, AuthServiceError> {
+        return self.session_repository.find(&session).map_err(AuthServiceError::DieselError);
+    }
+
+    /// Returns `false` when the user has no session with provided uuid.
+    pub fn revoke_session(&self, session: SessionDTO) -> Result<bool, AuthServiceError> {
+        let (user_id, uuid) = (*session.user_id, session.uuid);
+        let deleted = self.session_repository
+            .delete(session)
+            .map_err(AuthServiceError::DieselError)?;
+        self.auth_cache.invalidate_session(user_id, &uuid);
+        return Ok(deleted > 0);
+    }
+
+    /// Logs the user out everywhere except the session of provided claims.
+    pub fn revoke_other_sessions(&self, current: &Claims) -> Result<usize, AuthServiceError> {
+        let revoked = self.session_repository
+            .delete_by_user_id(current.user_id.clone(), Some(current.uuid))
+            .map_err(AuthServiceError::DieselError)?;
+        self.auth_cache.invalidate_sessions(*current.user_id);
+        return Ok(revoked);
+    }
+
+    pub fn prune_expired_sessions(&self) -> Result<usize, AuthServiceError> {
+        return self.session_repository.delete_expired().map_err(AuthServiceError::DieselError);
+    }
+
+    pub fn prune_login_attempts(&self) -> Result<usize, AuthServiceError> {
+        return Ok(self.login_throttle_service.prune()?);
+    }
+
+    pub fn prune_login_events(&self) -> Result<usize, AuthServiceError> {
+        return Ok(self.login_event_service.prune()?);
+    }
+
+    /// Lifts a brute-force lockout of the account with provided email.
+    pub fn unlock_user(&self, email: &str) -> Result<(), AuthServiceError> {
+        let user = self.user_repository
+            .find_by_email(email)
+            .map_err(AuthServiceError::DieselError)?;
+        self.login_throttle_service.unlock(Arc::new(user.id))?;
+        return Ok(());
+    }
+
+    /// Verifies signature and expiry of an access token.
+    pub fn decode_claims(&self, token: &str) -> Result<Claims, AuthServiceError> {
+        return self.jwt_keys.decode::<Claims>(token).map_err(AuthServiceError::JWTError);
+    }
+
+    /// Public keys for verifying access tokens, served at `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> &JwkSet {
+        return self.jwt_keys.jwks();
+    }
+
+    /// Checks that the session of the token was not revoked, recently confirmed sessions
+    /// are answered from `AuthCache`.
+    pub fn check(&self, session: Claims) -> bool {
+        if self.auth_cache.has_session(*session.user_id, &session.uuid) {
+            return true;
+        }
+        let generation = self.auth_cache.generation();
+        let exists = self.session_repository.exists(SessionDTO {
+            user_id: session.user_id.clone(),
+            uuid: session.uuid,
+        });
+        // Errors are treated as revoked, so a database outage does not accept every token.
+        if matches!(exists, Ok(true)) {
+            self.auth_cache.put_session(*session.user_id, &session.uuid, generation);
             return true;
         }
         return false;
     }
 
-    fn generate_jwt(&self, user_id: Arc<i32>) -> Result<String, AuthServiceError> {
+    pub fn auth_cache_stats(&self) -> AuthCacheStats {
+        return self.auth_cache.stats();
+    }
+
+    /// Replaces an outdated hash while the plain password is known. Failures are only logged,
+    /// the old hash keeps working and is upgraded on the next login.
+    fn rehash_password(&self, user_id: Arc<i32>, password: &str) {
+        let result = self.password_hasher
+            .hash(password)
+            .map_err(AuthServiceError::PasswordHashError)
+            .and_then(|hash| {
+                return self.user_repository
+                    .update_password(user_id.clone(), &hash)
+                    .map_err(AuthServiceError::DieselError);
+            });
+        match result {
+            Ok(_) => {
+                self.auth_cache.invalidate_user(*user_id);
+            }
+            Err(e) => {
+                warn!("Failed to rehash password of user [{}] - {}", user_id, e);
+            }
+        }
+    }
+
+    fn authenticate(
+        &self,
+        user_dto: &UserDTO,
+        client_info: &ClientInfoDTO
+    ) -> Result<AuthenticatedUserDTO, AuthServiceError> {
+        let tokens = self.generate_jwt(Arc::new(user_dto.id.unwrap()), client_info)?;
+        return Ok(AuthenticatedUserDTO {
+            user: UserResponse::dto_to_response(user_dto),
+            token: Some(tokens.token),
+            refresh_token: Some(tokens.refresh_token),
+        });
+    }
+
+    fn generate_jwt(
+        &self,
+        user_id: Arc<i32>,
+        client_info: &ClientInfoDTO
+    ) -> Result<AuthTokensDTO, AuthServiceError> {
         let session = SessionDTO { user_id, uuid: Uuid::new_v4() };
         let saved_session: Session = self.session_repository
-            .save(session)
+            .save(session, client_info, session_expires_at())
             .map_err(AuthServiceError::DieselError)?;
+        return Ok(AuthTokensDTO {
+            token: Arc::from(self.encode_jwt(&saved_session)?),
+            refresh_token: Arc::from(self.issue_refresh_token(&saved_session)?),
+        });
+    }
+
+    fn issue_refresh_token(&self, session: &Session) -> Result<String, AuthServiceError> {
+        let refresh_token = generate_token();
+        self.refresh_token_repository
+            .save(RefreshTokenDTO {
+                user_id: Arc::new(session.user_id),
+                session_uuid: session.uuid,
+                token_hash: Arc::from(hash_token(&refresh_token)),
+                expires_at: session.expires_at,
+            })
+            .map_err(AuthServiceError::DieselError)?;
+        return Ok(refresh_token);
+    }
+
+    fn encode_jwt(&self, saved_session: &Session) -> Result<String, AuthServiceError> {
         let claims = Claims {
             user_id: saved_session.user_id.into(),
             uuid: saved_session.uuid,
             exp: (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as usize) +
             (Duration::from_secs(CONFIGURATION.jwt_ttl).as_secs() as usize),
+            actor_id: None,
         };
-        let token = jsonwebtoken
-            ::encode(
-                &Header::default(),
-                &claims,
-                &EncodingKey::from_secret(CONFIGURATION.jwt_secret.as_ref())
-            )
-            .map_err(AuthServiceError::JWTError)?;
-        return Ok(token);
+        return self.jwt_keys.encode(&claims).map_err(AuthServiceError::JWTError);
     }
+
+    fn encode_two_factor_challenge(&self, user_id: i32) -> Result<String, AuthServiceError> {
+        let jti = self.two_factor_service
+            .issue_challenge(Arc::new(user_id))
+            .map_err(|e| AuthServiceError::ServiceError(Box::new(e)))?;
+        let claims = TwoFactorChallengeClaims {
+            user_id,
+            purpose: TWO_FACTOR_CHALLENGE_PURPOSE.to_string(),
+            jti,
+            exp: (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as usize) +
+            (CONFIGURATION.two_factor_challenge_ttl as usize),
+        };
+        return self.jwt_keys.encode(&claims).map_err(AuthServiceError::JWTError);
+    }
+}
+
+/// Session must outlive both the access token and the refresh token issued for it.
+/// It is prolonged on every refresh token rotation.
+fn session_expires_at() -> NaiveDateTime {
+    let ttl = CONFIGURATION.jwt_ttl.max(CONFIGURATION.refresh_token_ttl);
+    return Utc::now().naive_utc() + TimeDelta::seconds(ttl as i64);
 }
@@ -0,0 +1,145 @@
+use core::error;
+use std::sync::Arc;
+
+use chrono::{ TimeDelta, Utc };
+use config::{ log::error, CONFIGURATION };
+use thiserror::Error;
+
+use crate::{
+    cache::auth_cache::AuthCache,
+    infra::{
+        database::{
+            email_verification_repository::EmailVerificationRepository,
+            user_repository::UserRepository,
+        },
+        domain::{ email_verification::EmailVerificationDTO, user::UserDTO },
+    },
+    notifications::{ Notification, Notifier },
+};
+
+use super::{ generate_token, hash_token };
+
+pub struct EmailVerificationService {
+    user_repository: Arc<UserRepository>,
+    email_verification_repository: Arc<EmailVerificationRepository>,
+    notifier: Arc<dyn Notifier>,
+    auth_cache: Arc<AuthCache>,
+}
+
+#[derive(Error, Debug)]
+pub enum EmailVerificationServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+}
+
+impl EmailVerificationService {
+    pub fn new(
+        user_repository: Arc<UserRepository>,
+        email_verification_repository: Arc<EmailVerificationRepository>,
+        notifier: Arc<dyn Notifier>,
+        auth_cache: Arc<AuthCache>
+    ) -> Arc<EmailVerificationService> {
+        return Arc::new(EmailVerificationService {
+            user_repository,
+            email_verification_repository,
+            notifier,
+            auth_cache,
+        });
+    }
+
+    /// Invalidates previously sent tokens and sends a new one to the current email of the user.
+    /// Delivery failures are only logged, the user can ask for another token with `resend`.
+    pub fn send_verification(&self, user: &UserDTO) -> Result<(), EmailVerificationServiceError> {
+        let user_id = Arc::new(user.id.unwrap());
+        self.email_verification_repository
+            .delete_by_user_id(user_id.clone())
+            .map_err(EmailVerificationServiceError::DieselError)?;
+
+        let token = generate_token();
+        self.email_verification_repository
+            .save(EmailVerificationDTO {
+                user_id,
+                email: user.email.clone(),
+                token_hash: Arc::from(hash_token(&token)),
+                expires_at: Utc::now().naive_utc() +
+                TimeDelta::seconds(CONFIGURATION.email_verification_ttl as i64),
+            })
+            .map_err(EmailVerificationServiceError::DieselError)?;
+        let notification = Notification::EmailVerification {
+            email: user.email.clone(),
+            name: user.name.clone(),
+            token: Arc::from(token),
+            locale: user.locale.clone(),
+        };
+        if let Err(e) = self.notifier.notify(notification) {
+            error!("Failed to send email verification to user [{}] - {}", user.id.unwrap(), e);
+        }
+        return Ok(());
+    }
+
+    /// Sends a new token for unverified account. Unknown and already verified emails
+    /// are silently ignored, so the endpoint can not be used to find out registered addresses.
+    pub fn resend(&self, email: &str) -> Result<(), EmailVerificationServiceError> {
+        let user = match self.user_repository.find_by_email(email) {
+            Ok(user) => UserDTO::model_to_dto(user),
+            Err(diesel::result::Error::NotFound) => {
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(EmailVerificationServiceError::DieselError(e));
+            }
+        };
+        if user.is_email_verified() {
+            return Ok(());
+        }
+        return self.send_verification(&user);
+    }
+
+    /// Marks email as unverified, e.g. after it was changed, and sends a new token.
+    pub fn reset_verification(
+        &self,
+        user: &mut UserDTO
+    ) -> Result<(), EmailVerificationServiceError> {
+        let updated_user = self.user_repository
+            .set_email_verified(Arc::new(user.id.unwrap()), None)
+            .map_err(EmailVerificationServiceError::DieselError)?;
+        self.auth_cache.invalidate_user(updated_user.id);
+        *user = UserDTO::model_to_dto(updated_user);
+        return self.send_verification(user);
+    }
+
+    pub fn verify(&self, token: &str) -> Result<UserDTO, EmailVerificationServiceError> {
+        let invalid_token = || {
+            EmailVerificationServiceError::ServiceError(
+                Box::from("Invalid or expired verification token")
+            )
+        };
+        let verification = self.email_verification_repository
+            .find_by_hash(&hash_token(token))
+            .map_err(|_| invalid_token())?;
+        let now = Utc::now().naive_utc();
+        if verification.used_at.is_some() || verification.expires_at < now {
+            return Err(invalid_token());
+        }
+
+        let user_id = Arc::new(verification.user_id);
+        let user = self.user_repository.find_by_id(user_id.clone()).map_err(|_| invalid_token())?;
+        // Token was sent to the address which is not used by the account anymore.
+        if user.email != verification.email {
+            return Err(invalid_token());
+        }
+        if
+            !self.email_verification_repository
+                .mark_used(verification.id, now)
+                .map_err(EmailVerificationServiceError::DieselError)?
+        {
+            return Err(invalid_token());
+        }
+
+        let verified_user = self.user_repository
+            .set_email_verified(user_id, Some(now))
+            .map_err(EmailVerificationServiceError::DieselError)?;
+        self.auth_cache.invalidate_user(verified_user.id);
+        return Ok(UserDTO::model_to_dto(verified_user));
+    }
+}
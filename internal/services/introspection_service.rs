@@ -0,0 +1,145 @@
+use core::error;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::infra::domain::{
+    introspection::{ IntrospectionActorDTO, IntrospectionDTO, ACCESS_TOKEN_TYPE, API_KEY_TYPE },
+    role::UserRolesDTO,
+    user::UserDTO,
+};
+
+use super::{
+    api_key_service::{ ApiKeyService, ApiKeyServiceError },
+    auth_service::AuthService,
+    role_service::RoleService,
+    user_service::UserService,
+};
+
+/// Answers whether a token is still usable, following the same rules as `auth_middleware`.
+pub struct IntrospectionService {
+    auth_service: Arc<AuthService>,
+    user_service: Arc<UserService>,
+    role_service: Arc<RoleService>,
+    api_key_service: Arc<ApiKeyService>,
+}
+
+#[derive(Error, Debug)]
+pub enum IntrospectionServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+}
+
+impl IntrospectionService {
+    pub fn new(
+        auth_service: Arc<AuthService>,
+        user_service: Arc<UserService>,
+        role_service: Arc<RoleService>,
+        api_key_service: Arc<ApiKeyService>
+    ) -> Arc<IntrospectionService> {
+        return Arc::new(IntrospectionService {
+            auth_service,
+            user_service,
+            role_service,
+            api_key_service,
+        });
+    }
+
+    /// Invalid, expired or revoked tokens are reported as inactive, errors are returned
+    /// only when the answer can not be determined.
+    pub fn introspect(&self, token: &str) -> Result<IntrospectionDTO, IntrospectionServiceError> {
+        if ApiKeyService::is_api_key(token) {
+            return self.introspect_api_key(token);
+        }
+        return self.introspect_access_token(token);
+    }
+
+    fn introspect_access_token(
+        &self,
+        token: &str
+    ) -> Result<IntrospectionDTO, IntrospectionServiceError> {
+        let Ok(claims) = self.auth_service.decode_claims(token) else {
+            return Ok(IntrospectionDTO::inactive());
+        };
+        if !self.auth_service.check(claims.clone()) {
+            return Ok(IntrospectionDTO::inactive());
+        }
+        let Some(user) = self.find_user(claims.user_id.clone())? else {
+            return Ok(IntrospectionDTO::inactive());
+        };
+        let roles = self.find_roles(claims.user_id.clone())?;
+        return Ok(IntrospectionDTO {
+            active: true,
+            sub: Some(Arc::from(claims.user_id.to_string())),
+            username: Some(user.email),
+            token_type: Some(ACCESS_TOKEN_TYPE),
+            scope: Some(Self::scope(&roles)),
+            exp: Some(claims.exp as i64),
+            sid: Some(claims.uuid),
+            act: claims.actor_id.map(|actor_id| IntrospectionActorDTO {
+                sub: Arc::from(actor_id.to_string()),
+            }),
+        });
+    }
+
+    fn introspect_api_key(
+        &self,
+        token: &str
+    ) -> Result<IntrospectionDTO, IntrospectionServiceError> {
+        // Looking a key up for someone else is not a use of it, `last_used_at` stays untouched.
+        let api_key = match self.api_key_service.find_active(token) {
+            Ok(api_key) => api_key,
+            Err(ApiKeyServiceError::DieselError(e)) => {
+                return Err(IntrospectionServiceError::DieselError(e));
+            }
+            Err(ApiKeyServiceError::ServiceError(_)) => {
+                return Ok(IntrospectionDTO::inactive());
+            }
+        };
+        let user_id = Arc::new(api_key.user_id);
+        let Some(user) = self.find_user(user_id.clone())? else {
+            return Ok(IntrospectionDTO::inactive());
+        };
+        let roles = self.find_roles(user_id.clone())?.scoped(&api_key.scopes);
+        return Ok(IntrospectionDTO {
+            active: true,
+            sub: Some(Arc::from(user_id.to_string())),
+            username: Some(user.email),
+            token_type: Some(API_KEY_TYPE),
+            scope: Some(Self::scope(&roles)),
+            exp: api_key.expires_at.map(|expires_at| expires_at.and_utc().timestamp()),
+            sid: None,
+            act: None,
+        });
+    }
+
+    /// Deleted users are not found, so their tokens are inactive.
+    fn find_user(&self, user_id: Arc<i32>) -> Result<Option<UserDTO>, IntrospectionServiceError> {
+        match self.user_service.find_by_id(user_id) {
+            Ok(user) => {
+                return Ok(Some(user));
+            }
+            Err(diesel::result::Error::NotFound) => {
+                return Ok(None);
+            }
+            Err(e) => {
+                return Err(IntrospectionServiceError::DieselError(e));
+            }
+        }
+    }
+
+    fn find_roles(&self, user_id: Arc<i32>) -> Result<UserRolesDTO, IntrospectionServiceError> {
+        return self.role_service
+            .find_user_roles(user_id)
+            .map_err(|e| IntrospectionServiceError::ServiceError(Box::from(e)));
+    }
+
+    fn scope(roles: &UserRolesDTO) -> Arc<str> {
+        let mut permissions: Vec<&str> = roles.permissions
+            .iter()
+            .map(|permission| &**permission)
+            .collect();
+        permissions.sort_unstable();
+        return Arc::from(permissions.join(" "));
+    }
+}
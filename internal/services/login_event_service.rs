@@ -0,0 +1,186 @@
+use core::error;
+use std::{ net::{ IpAddr, SocketAddr }, sync::Arc };
+
+use chrono::{ TimeDelta, Utc };
+use actix_web::{ rt, web };
+use config::{ log::{ error, warn }, CONFIGURATION };
+use thiserror::Error;
+
+use crate::{
+    infra::{
+        database::login_event_repository::{ LoginEvent, LoginEventRepository },
+        domain::{
+            login_event::{ LoginEventDTO, LOGIN_FAILED, LOGIN_SUCCEEDED },
+            session::ClientInfoDTO,
+            user::UserDTO,
+        },
+    },
+    notifications::{ Notification, Notifier },
+};
+
+use super::hash_token;
+
+/// Number of latest events returned to the account owner.
+const LOGIN_HISTORY_LIMIT: i64 = 100;
+
+/// Keeps login history of accounts and warns users about logins from new devices.
+pub struct LoginEventService {
+    login_event_repository: Arc<LoginEventRepository>,
+    notifier: Arc<dyn Notifier>,
+}
+
+#[derive(Error, Debug)]
+pub enum LoginEventServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+}
+
+impl LoginEventService {
+    pub fn new(
+        login_event_repository: Arc<LoginEventRepository>,
+        notifier: Arc<dyn Notifier>
+    ) -> Arc<LoginEventService> {
+        return Arc::new(LoginEventService { login_event_repository, notifier });
+    }
+
+    /// Records a successful login. When the account was used before, but never from this
+    /// device, the user is notified in the background. History is best effort, failures to
+    /// record it or to notify are only logged and do not fail the login.
+    pub fn record_success(&self, user: &UserDTO, client_info: &ClientInfoDTO) {
+        let user_id = user.id.unwrap();
+        let notification = match self.save_success(user, client_info) {
+            Ok(notification) => notification,
+            Err(e) => {
+                error!("Failed to record login of user [{}] - {}", user_id, e);
+                return;
+            }
+        };
+        if let Some(notification) = notification {
+            // Sending mail can take a while, the login response does not wait for it.
+            let notifier = Arc::clone(&self.notifier);
+            rt::spawn(async move {
+                match web::block(move || notifier.notify(notification)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        warn!("Failed to notify user [{}] about new device login - {}", user_id, e);
+                    }
+                    Err(e) => {
+                        warn!("Failed to notify user [{}] about new device login - {}", user_id, e);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Records a failed login of an existing account, `reason` is one of the constants
+    /// from `domain::login_event`. Failures are only logged, like in `record_success`.
+    pub fn record_failure(
+        &self,
+        user_id: Arc<i32>,
+        reason: &'static str,
+        client_info: &ClientInfoDTO
+    ) {
+        let result = self.login_event_repository.save(LoginEventDTO {
+            user_id: user_id.clone(),
+            outcome: LOGIN_FAILED,
+            reason: Some(reason),
+            client_ip: client_info.client_ip.clone(),
+            user_agent: client_info.user_agent.clone(),
+            device_fingerprint: Arc::from(device_fingerprint(client_info)),
+            new_device: false,
+        });
+        if let Err(e) = result {
+            error!("Failed to record failed login of user [{}] - {}", user_id, e);
+        }
+    }
+
+    /// Saves the event and returns the notification to send when the device is new.
+    fn save_success(
+        &self,
+        user: &UserDTO,
+        client_info: &ClientInfoDTO
+    ) -> Result<Option<Notification>, LoginEventServiceError> {
+        let user_id = Arc::new(user.id.unwrap());
+        let fingerprint = device_fingerprint(client_info);
+        let known_device = self.login_event_repository
+            .has_successful_login_from(user_id.clone(), &fingerprint)
+            .map_err(LoginEventServiceError::DieselError)?;
+        // The first login of an account has nothing to be compared with.
+        let new_device =
+            !known_device &&
+            self.login_event_repository
+                .has_successful_login(user_id.clone())
+                .map_err(LoginEventServiceError::DieselError)?;
+        let event = self.login_event_repository
+            .save(LoginEventDTO {
+                user_id,
+                outcome: LOGIN_SUCCEEDED,
+                reason: None,
+                client_ip: client_info.client_ip.clone(),
+                user_agent: client_info.user_agent.clone(),
+                device_fingerprint: Arc::from(fingerprint),
+                new_device,
+            })
+            .map_err(LoginEventServiceError::DieselError)?;
+        if !new_device {
+            return Ok(None);
+        }
+        return Ok(
+            Some(Notification::NewDeviceLogin {
+                email: user.email.clone(),
+                name: user.name.clone(),
+                client_ip: event.client_ip.as_deref().unwrap_or("unknown").into(),
+                user_agent: event.user_agent.as_deref().unwrap_or("unknown").into(),
+                logged_in_at: event.created_at.format("%Y-%m-%d %H:%M UTC").to_string().into(),
+                locale: user.locale.clone(),
+            })
+        );
+    }
+
+    pub fn find_by_user_id(
+        &self,
+        user_id: Arc<i32>
+    ) -> Result<Vec<LoginEvent>, LoginEventServiceError> {
+        return self.login_event_repository
+            .find_by_user_id(user_id, LOGIN_HISTORY_LIMIT)
+            .map_err(LoginEventServiceError::DieselError);
+    }
+
+    pub fn prune(&self) -> Result<usize, LoginEventServiceError> {
+        let before =
+            Utc::now().naive_utc() - TimeDelta::seconds(CONFIGURATION.login_event_ttl as i64);
+        return self.login_event_repository
+            .delete_older_than(before)
+            .map_err(LoginEventServiceError::DieselError);
+    }
+}
+
+/// Identifies a device by its user agent and network. Only the network prefix of the IP is used
+/// (/24 for IPv4, /48 for IPv6), so a changing address from the same provider is not a new device.
+fn device_fingerprint(client_info: &ClientInfoDTO) -> String {
+    let network = client_info.client_ip
+        .as_deref()
+        .map(network_prefix)
+        .unwrap_or_default();
+    let user_agent = client_info.user_agent.as_deref().unwrap_or_default();
+    return hash_token(&format!("{}|{}", user_agent, network));
+}
+
+fn network_prefix(client_ip: &str) -> String {
+    let ip = client_ip
+        .parse::<IpAddr>()
+        .or_else(|_| client_ip.parse::<SocketAddr>().map(|address| address.ip()));
+    match ip {
+        Ok(IpAddr::V4(ip)) => {
+            let [a, b, c, _] = ip.octets();
+            return format!("{}.{}.{}.0/24", a, b, c);
+        }
+        Ok(IpAddr::V6(ip)) => {
+            let segments = ip.segments();
+            return format!("{:x}:{:x}:{:x}::/48", segments[0], segments[1], segments[2]);
+        }
+        Err(_) => {
+            return client_ip.to_string();
+        }
+    }
+}
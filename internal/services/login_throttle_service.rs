@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use chrono::{ NaiveDateTime, TimeDelta, Utc };
+use config::CONFIGURATION;
+use thiserror::Error;
+
+use crate::infra::{
+    database::{
+        login_attempt_repository::{ LoginAttemptRepository, LoginAttemptScope },
+        user_repository::UserRepository,
+    },
+    domain::user::UserDTO,
+};
+
+/// Limits failed logins per account and per client IP with an exponential lockout.
+/// Counters are kept in the database, so they are shared between server instances.
+pub struct LoginThrottleService {
+    user_repository: Arc<UserRepository>,
+    login_attempt_repository: Arc<LoginAttemptRepository>,
+}
+
+#[derive(Error, Debug)]
+pub enum LoginThrottleServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("Too many attempts, try again in {0} seconds")] LockedError(u64),
+}
+
+impl LoginThrottleService {
+    pub fn new(
+        user_repository: Arc<UserRepository>,
+        login_attempt_repository: Arc<LoginAttemptRepository>
+    ) -> Arc<LoginThrottleService> {
+        return Arc::new(LoginThrottleService { user_repository, login_attempt_repository });
+    }
+
+    /// Fails with `LockedError` holding seconds left when the IP is locked out.
+    pub fn check_ip(&self, client_ip: Option<&str>) -> Result<(), LoginThrottleServiceError> {
+        if let Some(client_ip) = client_ip {
+            return self.check_scope(LoginAttemptScope::Ip, client_ip);
+        }
+        return Ok(());
+    }
+
+    /// Fails with `LockedError` holding seconds left when the account is locked out.
+    pub fn check_account(&self, user: &UserDTO) -> Result<(), LoginThrottleServiceError> {
+        return check_locked_until(*user.locked_until);
+    }
+
+    /// Counts a failed login. Unknown emails are counted only against the IP.
+    pub fn register_failure(
+        &self,
+        user_id: Option<Arc<i32>>,
+        client_ip: Option<&str>
+    ) -> Result<(), LoginThrottleServiceError> {
+        let window_start = Utc::now().naive_utc() -
+        TimeDelta::seconds(CONFIGURATION.login_attempt_window as i64);
+        if let Some(user_id) = user_id {
+            let attempt = self.login_attempt_repository
+                .register_failure(LoginAttemptScope::Account, &user_id.to_string(), window_start)
+                .map_err(LoginThrottleServiceError::DieselError)?;
+            let max_attempts = CONFIGURATION.login_max_attempts;
+            if let Some(until) = lock_until(attempt.failed_attempts, max_attempts) {
+                self.user_repository
+                    .set_locked_until(user_id, Some(until))
+                    .map_err(LoginThrottleServiceError::DieselError)?;
+            }
+        }
+        if let Some(client_ip) = client_ip {
+            let attempt = self.login_attempt_repository
+                .register_failure(LoginAttemptScope::Ip, client_ip, window_start)
+                .map_err(LoginThrottleServiceError::DieselError)?;
+            let max_attempts = CONFIGURATION.login_max_attempts_per_ip;
+            if let Some(until) = lock_until(attempt.failed_attempts, max_attempts) {
+                self.login_attempt_repository
+                    .lock(LoginAttemptScope::Ip, client_ip, until)
+                    .map_err(LoginThrottleServiceError::DieselError)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Forgets failed attempts of the account after a successful login.
+    /// IP counters are kept, otherwise a valid account could be used to reset them.
+    pub fn register_success(&self, user_id: Arc<i32>) -> Result<(), LoginThrottleServiceError> {
+        self.login_attempt_repository
+            .delete(LoginAttemptScope::Account, &user_id.to_string())
+            .map_err(LoginThrottleServiceError::DieselError)?;
+        return Ok(());
+    }
+
+    /// Lifts the account lockout and resets its counter.
+    pub fn unlock(&self, user_id: Arc<i32>) -> Result<(), LoginThrottleServiceError> {
+        self.user_repository
+            .set_locked_until(user_id.clone(), None)
+            .map_err(LoginThrottleServiceError::DieselError)?;
+        return self.register_success(user_id);
+    }
+
+    /// Limits login link requests per email and per client IP. Unknown emails are counted
+    /// too, so the limit does not tell which addresses are registered. Once a limit is reached,
+    /// further requests are rejected until the end of the window.
+    pub fn register_magic_link_request(
+        &self,
+        email: &str,
+        client_ip: Option<&str>
+    ) -> Result<(), LoginThrottleServiceError> {
+        let email = email.trim().to_lowercase();
+        self.check_scope(LoginAttemptScope::MagicLinkEmail, &email)?;
+        if let Some(client_ip) = client_ip {
+            self.check_scope(LoginAttemptScope::MagicLinkIp, client_ip)?;
+        }
+        let max_per_email = CONFIGURATION.magic_link_max_per_email;
+        self.count_request(LoginAttemptScope::MagicLinkEmail, &email, max_per_email)?;
+        if let Some(client_ip) = client_ip {
+            let max_per_ip = CONFIGURATION.magic_link_max_per_ip;
+            self.count_request(LoginAttemptScope::MagicLinkIp, client_ip, max_per_ip)?;
+        }
+        return Ok(());
+    }
+
+    pub fn prune(&self) -> Result<usize, LoginThrottleServiceError> {
+        let window_start = Utc::now().naive_utc() -
+        TimeDelta::seconds(CONFIGURATION.login_attempt_window as i64);
+        return self.login_attempt_repository
+            .delete_stale(window_start)
+            .map_err(LoginThrottleServiceError::DieselError);
+    }
+}
+
+impl LoginThrottleService {
+    fn check_scope(
+        &self,
+        scope: LoginAttemptScope,
+        identifier: &str
+    ) -> Result<(), LoginThrottleServiceError> {
+        let attempt = self.login_attempt_repository
+            .find(scope, identifier)
+            .map_err(LoginThrottleServiceError::DieselError)?;
+        return check_locked_until(attempt.and_then(|attempt| attempt.locked_until));
+    }
+
+    fn count_request(
+        &self,
+        scope: LoginAttemptScope,
+        identifier: &str,
+        max_requests: i32
+    ) -> Result<(), LoginThrottleServiceError> {
+        let now = Utc::now().naive_utc();
+        let window = TimeDelta::seconds(CONFIGURATION.login_attempt_window as i64);
+        let attempt = self.login_attempt_repository
+            .register_failure(scope, identifier, now - window)
+            .map_err(LoginThrottleServiceError::DieselError)?;
+        if attempt.failed_attempts >= max_requests {
+            self.login_attempt_repository
+                .lock(scope, identifier, now + window)
+                .map_err(LoginThrottleServiceError::DieselError)?;
+        }
+        return Ok(());
+    }
+}
+
+fn check_locked_until(
+    locked_until: Option<NaiveDateTime>
+) -> Result<(), LoginThrottleServiceError> {
+    if let Some(locked_until) = locked_until {
+        let left = (locked_until - Utc::now().naive_utc()).num_seconds();
+        if left > 0 {
+            return Err(LoginThrottleServiceError::LockedError(left as u64));
+        }
+    }
+    return Ok(());
+}
+
+/// Lockout starts at `login_lockout_base` once the limit is reached
+/// and doubles with every next failure.
+fn lock_until(failed_attempts: i32, max_attempts: i32) -> Option<NaiveDateTime> {
+    if failed_attempts < max_attempts {
+        return None;
+    }
+    let exponent = (failed_attempts - max_attempts).min(16) as u32;
+    let seconds = CONFIGURATION.login_lockout_base
+        .saturating_mul(2u64.pow(exponent))
+        .min(CONFIGURATION.login_lockout_max);
+    return Some(Utc::now().naive_utc() + TimeDelta::seconds(seconds as i64));
+}
@@ -1,6 +1,33 @@
+use rust_commons::{ base64::{ self, Engine }, rand::{ self, RngCore } };
+use sha2::{ Digest, Sha256 };
+
 pub mod user_service;
 pub mod auth_service;
+pub mod email_verification_service;
+pub mod two_factor_service;
+pub mod login_throttle_service;
+pub mod role_service;
+pub mod api_key_service;
+pub mod oidc_service;
+pub mod introspection_service;
+pub mod login_event_service;
 
 pub fn user_image_name(username: &str) -> String {
     return format!("users/user_{}.png", username);
 }
+
+/// Generates url-safe random token which is given to the client only once.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    return base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+}
+
+/// Tokens are stored hashed, so a database leak does not expose usable credentials.
+pub fn hash_token(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    return digest
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+}
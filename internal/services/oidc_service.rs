@@ -0,0 +1,385 @@
+use core::error;
+use std::{ collections::HashMap, sync::{ Arc, RwLock } };
+
+use chrono::{ TimeDelta, Utc };
+use config::{ OidcProviderConfig, CONFIGURATION };
+use jsonwebtoken::{ jwk::JwkSet, Algorithm, DecodingKey, Validation };
+use rust_commons::base64::{ self, Engine };
+use serde::Deserialize;
+use sha2::{ Digest, Sha256 };
+use thiserror::Error;
+
+use crate::{
+    infra::{
+        database::{
+            user_identity_repository::UserIdentityRepository,
+            user_repository::UserRepository,
+        },
+        domain::{ user::UserDTO, user_identity::{ OidcLoginStateDTO, UserIdentityDTO } },
+        http::requests::user_request::UserRequest,
+    },
+    password::PasswordHasher,
+};
+
+use super::{ email_verification_service::EmailVerificationService, generate_token, hash_token };
+
+/// Endpoints from the provider's discovery document.
+#[derive(Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    name: Option<String>,
+    nonce: Option<String>,
+}
+
+/// Signs users in with external OpenID Connect providers using the authorization code flow
+/// with PKCE. Discovery documents and provider keys are cached in memory.
+pub struct OidcService {
+    http_client: reqwest::Client,
+    user_repository: Arc<UserRepository>,
+    user_identity_repository: Arc<UserIdentityRepository>,
+    email_verification_service: Arc<EmailVerificationService>,
+    password_hasher: Arc<PasswordHasher>,
+    metadata: RwLock<HashMap<String, Arc<ProviderMetadata>>>,
+    keys: RwLock<HashMap<String, Arc<JwkSet>>>,
+}
+
+#[derive(Error, Debug)]
+pub enum OidcServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("Identity provider request failed: {0}")] HttpError(reqwest::Error),
+    #[error("Invalid ID token: {0}")] JWTError(jsonwebtoken::errors::Error),
+    #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+}
+
+impl OidcService {
+    pub fn new(
+        user_repository: Arc<UserRepository>,
+        user_identity_repository: Arc<UserIdentityRepository>,
+        email_verification_service: Arc<EmailVerificationService>,
+        password_hasher: Arc<PasswordHasher>
+    ) -> Arc<OidcService> {
+        return Arc::new(OidcService {
+            http_client: reqwest::Client::new(),
+            user_repository,
+            user_identity_repository,
+            email_verification_service,
+            password_hasher,
+            metadata: RwLock::new(HashMap::new()),
+            keys: RwLock::new(HashMap::new()),
+        });
+    }
+
+    /// Starts login and returns the provider's authorization URL to redirect the user to,
+    /// with the binding value which must come back from the same browser on callback.
+    pub async fn authorization_url(
+        &self,
+        provider_name: &str
+    ) -> Result<(String, String), OidcServiceError> {
+        let provider = find_provider(provider_name)?;
+        let metadata = self.metadata(provider).await?;
+
+        let state = generate_token();
+        let nonce = generate_token();
+        let code_verifier = generate_token();
+        let binding = generate_token();
+        self.user_identity_repository
+            .delete_expired_states()
+            .map_err(OidcServiceError::DieselError)?;
+        self.user_identity_repository
+            .save_state(OidcLoginStateDTO {
+                state_hash: Arc::from(hash_token(&state)),
+                provider: Arc::from(provider.name.as_str()),
+                code_verifier: Arc::from(code_verifier.as_str()),
+                nonce: Arc::from(nonce.as_str()),
+                expires_at: Utc::now().naive_utc() +
+                TimeDelta::seconds(CONFIGURATION.oidc_state_ttl as i64),
+                binding_hash: Arc::from(hash_token(&binding)),
+            })
+            .map_err(OidcServiceError::DieselError)?;
+
+        let code_challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(
+            Sha256::digest(code_verifier.as_bytes())
+        );
+        let url = reqwest::Url
+            ::parse_with_params(
+                &metadata.authorization_endpoint,
+                &[
+                    ("response_type", "code"),
+                    ("client_id", provider.client_id.as_str()),
+                    ("redirect_uri", redirect_uri(provider).as_str()),
+                    ("scope", provider.scopes.as_str()),
+                    ("state", state.as_str()),
+                    ("nonce", nonce.as_str()),
+                    ("code_challenge", code_challenge.as_str()),
+                    ("code_challenge_method", "S256"),
+                ]
+            )
+            .map_err(|e| OidcServiceError::ServiceError(Box::new(e)))?;
+        return Ok((url.to_string(), binding));
+    }
+
+    /// Finishes login: exchanges the code, verifies ID token and returns the linked user.
+    /// A new user is created when no account uses the email, an existing account is linked
+    /// only when the provider has verified the email. The state is rejected when `binding` is not
+    /// the one issued with it, so a callback URL of someone else's login can not be replayed
+    /// in another browser.
+    pub async fn authenticate(
+        &self,
+        provider_name: &str,
+        code: &str,
+        state: &str,
+        binding: &str
+    ) -> Result<UserDTO, OidcServiceError> {
+        let provider = find_provider(provider_name)?;
+        let login_state = self.user_identity_repository
+            .take_state(&hash_token(state))
+            .map_err(OidcServiceError::DieselError)?
+            .filter(|login_state| {
+                login_state.provider == provider.name &&
+                    login_state.binding_hash == hash_token(binding) &&
+                    login_state.expires_at > Utc::now().naive_utc()
+            })
+            .ok_or_else(|| {
+                OidcServiceError::ServiceError(Box::from("Invalid or expired login state"))
+            })?;
+        let metadata = self.metadata(provider).await?;
+
+        let redirect_uri = redirect_uri(provider);
+        let token_response = self.http_client
+            .post(&metadata.token_endpoint)
+            .form(
+                &[
+                    ("grant_type", "authorization_code"),
+                    ("code", code),
+                    ("redirect_uri", redirect_uri.as_str()),
+                    ("client_id", provider.client_id.as_str()),
+                    ("client_secret", provider.client_secret.as_str()),
+                    ("code_verifier", login_state.code_verifier.as_str()),
+                ]
+            )
+            .send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(OidcServiceError::HttpError)?
+            .json::<TokenResponse>().await
+            .map_err(OidcServiceError::HttpError)?;
+
+        let claims = self.verify_id_token(provider, &metadata, &token_response.id_token).await?;
+        if claims.nonce.as_deref() != Some(login_state.nonce.as_str()) {
+            return Err(OidcServiceError::ServiceError(Box::from("ID token nonce does not match")));
+        }
+        return self.resolve_user(provider, claims);
+    }
+
+    async fn verify_id_token(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        id_token: &str
+    ) -> Result<IdTokenClaims, OidcServiceError> {
+        let header = jsonwebtoken::decode_header(id_token).map_err(OidcServiceError::JWTError)?;
+        // Symmetric algorithms would let anyone holding the client secret forge tokens.
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(
+                OidcServiceError::ServiceError(Box::from("HMAC signed ID tokens are not allowed"))
+            );
+        }
+        let kid = header.kid.unwrap_or_default();
+        let mut keys = self.keys(provider, metadata, false).await?;
+        if keys.find(&kid).is_none() {
+            // Provider may have rotated keys since they were cached.
+            keys = self.keys(provider, metadata, true).await?;
+        }
+        let jwk = keys
+            .find(&kid)
+            .or(if keys.keys.len() == 1 { keys.keys.first() } else { None })
+            .ok_or_else(|| {
+                OidcServiceError::ServiceError(Box::from("ID token signing key not found"))
+            })?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&provider.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+        let token_data = jsonwebtoken
+            ::decode::<IdTokenClaims>(
+                id_token,
+                &DecodingKey::from_jwk(jwk).map_err(OidcServiceError::JWTError)?,
+                &validation
+            )
+            .map_err(OidcServiceError::JWTError)?;
+        return Ok(token_data.claims);
+    }
+
+    fn resolve_user(
+        &self,
+        provider: &OidcProviderConfig,
+        claims: IdTokenClaims
+    ) -> Result<UserDTO, OidcServiceError> {
+        let identity = self.user_identity_repository
+            .find(&provider.name, &claims.sub)
+            .map_err(OidcServiceError::DieselError)?;
+        if let Some(identity) = identity {
+            match self.user_repository.find_by_id(Arc::new(identity.user_id)) {
+                Ok(user) => {
+                    self.user_identity_repository
+                        .touch(identity.id, claims.email.as_deref())
+                        .map_err(OidcServiceError::DieselError)?;
+                    return Ok(UserDTO::model_to_dto(user));
+                }
+                // Linked account was deleted. It is not restored, the identity is unlinked and
+                // signs in as a new user below, like registering with a deleted account's email.
+                Err(diesel::result::Error::NotFound) => {
+                    self.user_identity_repository
+                        .delete(identity.id)
+                        .map_err(OidcServiceError::DieselError)?;
+                }
+                Err(e) => {
+                    return Err(OidcServiceError::DieselError(e));
+                }
+            }
+        }
+
+        let email = claims.email.ok_or_else(|| {
+            OidcServiceError::ServiceError(Box::from("Identity provider did not return an email"))
+        })?;
+        let email_verified = claims.email_verified.unwrap_or(false);
+        // Deleted accounts are not found, so their email gets a new account.
+        let user_dto = match self.user_repository.find_by_email(&email) {
+            Ok(user) => {
+                if !email_verified {
+                    return Err(
+                        OidcServiceError::ServiceError(
+                            Box::from("Email is taken and not verified by the identity provider")
+                        )
+                    );
+                }
+                UserDTO::model_to_dto(user)
+            }
+            Err(diesel::result::Error::NotFound) => {
+                self.create_user(&email, claims.name.as_deref(), email_verified)?
+            }
+            Err(e) => {
+                return Err(OidcServiceError::DieselError(e));
+            }
+        };
+        self.user_identity_repository
+            .save(UserIdentityDTO {
+                user_id: Arc::new(user_dto.id.unwrap()),
+                provider: Arc::from(provider.name.as_str()),
+                subject: Arc::from(claims.sub),
+                email: Some(Arc::from(email)),
+            })
+            .map_err(OidcServiceError::DieselError)?;
+        return Ok(user_dto);
+    }
+
+    /// Users created from an external identity get a random password,
+    /// they can set their own with the password reset flow.
+    fn create_user(
+        &self,
+        email: &str,
+        name: Option<&str>,
+        email_verified: bool
+    ) -> Result<UserDTO, OidcServiceError> {
+        let password = self.password_hasher
+            .hash(&generate_token())
+            .map_err(|e| OidcServiceError::ServiceError(Box::new(e)))?;
+        let name = name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| email.split('@').next().unwrap_or(email));
+        let user = self.user_repository
+            .create_user(
+                &(UserRequest {
+                    name: name.to_string(),
+                    password,
+                    email: email.to_string(),
+                    avatar: None,
+                })
+            )
+            .map_err(OidcServiceError::DieselError)?;
+        if email_verified {
+            let user = self.user_repository
+                .set_email_verified(Arc::new(user.id), Some(Utc::now().naive_utc()))
+                .map_err(OidcServiceError::DieselError)?;
+            return Ok(UserDTO::model_to_dto(user));
+        }
+        let user_dto = UserDTO::model_to_dto(user);
+        self.email_verification_service
+            .send_verification(&user_dto)
+            .map_err(|e| OidcServiceError::ServiceError(Box::new(e)))?;
+        return Ok(user_dto);
+    }
+
+    async fn metadata(
+        &self,
+        provider: &OidcProviderConfig
+    ) -> Result<Arc<ProviderMetadata>, OidcServiceError> {
+        if let Some(metadata) = self.metadata.read().unwrap().get(&provider.name) {
+            return Ok(Arc::clone(metadata));
+        }
+        let metadata = Arc::new(
+            self.http_client
+                .get(format!("{}/.well-known/openid-configuration", provider.issuer))
+                .send().await
+                .and_then(|response| response.error_for_status())
+                .map_err(OidcServiceError::HttpError)?
+                .json::<ProviderMetadata>().await
+                .map_err(OidcServiceError::HttpError)?
+        );
+        self.metadata.write().unwrap().insert(provider.name.clone(), Arc::clone(&metadata));
+        return Ok(metadata);
+    }
+
+    async fn keys(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        refresh: bool
+    ) -> Result<Arc<JwkSet>, OidcServiceError> {
+        if !refresh {
+            if let Some(keys) = self.keys.read().unwrap().get(&provider.name) {
+                return Ok(Arc::clone(keys));
+            }
+        }
+        let keys = Arc::new(
+            self.http_client
+                .get(&metadata.jwks_uri)
+                .send().await
+                .and_then(|response| response.error_for_status())
+                .map_err(OidcServiceError::HttpError)?
+                .json::<JwkSet>().await
+                .map_err(OidcServiceError::HttpError)?
+        );
+        self.keys.write().unwrap().insert(provider.name.clone(), Arc::clone(&keys));
+        return Ok(keys);
+    }
+}
+
+fn find_provider(provider_name: &str) -> Result<&'static OidcProviderConfig, OidcServiceError> {
+    return CONFIGURATION.oidc_providers
+        .iter()
+        .find(|provider| provider.name == provider_name)
+        .ok_or_else(|| {
+            OidcServiceError::ServiceError(
+                Box::from(format!("Unknown identity provider [{}]", provider_name))
+            )
+        });
+}
+
+fn redirect_uri(provider: &OidcProviderConfig) -> String {
+    return format!("{}/api/v1/auth/oidc/{}/callback", CONFIGURATION.app_url, provider.name);
+}
@@ -0,0 +1,78 @@
+use core::error;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::infra::{
+    database::{ role_repository::RoleRepository, user_repository::UserRepository },
+    domain::role::UserRolesDTO,
+};
+
+pub struct RoleService {
+    role_repository: Arc<RoleRepository>,
+    user_repository: Arc<UserRepository>,
+}
+
+#[derive(Error, Debug)]
+pub enum RoleServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+}
+
+impl RoleService {
+    pub fn new(
+        role_repository: Arc<RoleRepository>,
+        user_repository: Arc<UserRepository>
+    ) -> Arc<RoleService> {
+        return Arc::new(RoleService { role_repository, user_repository });
+    }
+
+    pub fn find_user_roles(&self, user_id: Arc<i32>) -> Result<UserRolesDTO, RoleServiceError> {
+        let roles = self.role_repository
+            .find_role_names_by_user_id(user_id.clone())
+            .map_err(RoleServiceError::DieselError)?;
+        let permissions = self.role_repository
+            .find_permission_names_by_user_id(user_id)
+            .map_err(RoleServiceError::DieselError)?;
+        return Ok(UserRolesDTO {
+            roles: roles.into_iter().map(Arc::from).collect(),
+            permissions: permissions.into_iter().map(Arc::from).collect(),
+        });
+    }
+
+    /// Returns `false` when the user already had the role.
+    pub fn grant(&self, user_id: Arc<i32>, role_name: &str) -> Result<bool, RoleServiceError> {
+        self.user_repository.find_by_id(user_id.clone()).map_err(RoleServiceError::DieselError)?;
+        let role_id = self.find_role_id(role_name)?;
+        return self.role_repository
+            .assign(user_id, role_id)
+            .map_err(RoleServiceError::DieselError);
+    }
+
+    /// Returns `false` when the user did not have the role.
+    pub fn revoke(&self, user_id: Arc<i32>, role_name: &str) -> Result<bool, RoleServiceError> {
+        let role_id = self.find_role_id(role_name)?;
+        return self.role_repository
+            .unassign(user_id, role_id)
+            .map_err(RoleServiceError::DieselError);
+    }
+
+    /// Used to bootstrap the first admin from the command line.
+    pub fn grant_by_email(&self, email: &str, role_name: &str) -> Result<bool, RoleServiceError> {
+        let user = self.user_repository
+            .find_by_email(email)
+            .map_err(RoleServiceError::DieselError)?;
+        return self.grant(Arc::new(user.id), role_name);
+    }
+
+    fn find_role_id(&self, role_name: &str) -> Result<i32, RoleServiceError> {
+        match self.role_repository.find_role_id(role_name) {
+            Ok(role_id) => Ok(role_id),
+            Err(diesel::result::Error::NotFound) => {
+                let message = format!("Unknown role [{}]", role_name);
+                Err(RoleServiceError::ServiceError(Box::from(message)))
+            }
+            Err(e) => Err(RoleServiceError::DieselError(e)),
+        }
+    }
+}
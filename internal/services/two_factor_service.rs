@@ -0,0 +1,244 @@
+use core::error;
+use std::{ sync::Arc, time::{ SystemTime, UNIX_EPOCH } };
+
+use chrono::{ TimeDelta, Utc };
+use config::CONFIGURATION;
+use rust_commons::{ rand::{ self, distributions::Alphanumeric, Rng }, uuid::Uuid };
+use thiserror::Error;
+use totp_rs::{ Algorithm, Secret, TOTP };
+
+use crate::infra::{
+    database::two_factor_repository::TwoFactorRepository,
+    domain::{ two_factor::{ RecoveryCodesDTO, TwoFactorEnrollmentDTO }, user::UserDTO },
+};
+
+use super::hash_token;
+
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP: u64 = 30;
+/// Number of neighbour time steps accepted to tolerate clock drift.
+const TOTP_SKEW: i64 = 1;
+const RECOVERY_CODES_COUNT: usize = 10;
+
+pub struct TwoFactorService {
+    two_factor_repository: Arc<TwoFactorRepository>,
+}
+
+#[derive(Error, Debug)]
+pub enum TwoFactorServiceError {
+    #[error("Database error: {0}")] DieselError(diesel::result::Error),
+    #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+}
+
+impl TwoFactorService {
+    pub fn new(two_factor_repository: Arc<TwoFactorRepository>) -> Arc<TwoFactorService> {
+        return Arc::new(TwoFactorService { two_factor_repository });
+    }
+
+    pub fn is_enabled(&self, user_id: Arc<i32>) -> Result<bool, TwoFactorServiceError> {
+        match self.two_factor_repository.find_by_user_id(user_id) {
+            Ok(secret) => Ok(secret.enabled_at.is_some()),
+            Err(diesel::result::Error::NotFound) => Ok(false),
+            Err(e) => Err(TwoFactorServiceError::DieselError(e)),
+        }
+    }
+
+    /// Generates a new secret. Two-factor authentication is enabled only after `confirm`.
+    pub fn enroll(&self, user: &UserDTO) -> Result<TwoFactorEnrollmentDTO, TwoFactorServiceError> {
+        let user_id = Arc::new(user.id.unwrap());
+        if self.is_enabled(user_id.clone())? {
+            return Err(
+                TwoFactorServiceError::ServiceError(
+                    Box::from("Two-factor authentication is already enabled")
+                )
+            );
+        }
+        let secret = Secret::generate_secret().to_encoded().to_string();
+        let totp = build_totp(&secret, &user.email)?;
+        self.two_factor_repository
+            .save_pending(user_id, &secret)
+            .map_err(TwoFactorServiceError::DieselError)?;
+        return Ok(TwoFactorEnrollmentDTO {
+            secret: Arc::from(secret),
+            otpauth_uri: Arc::from(totp.get_url()),
+        });
+    }
+
+    /// Enables two-factor authentication after the first valid code and returns recovery codes,
+    /// which are shown to the user only once.
+    pub fn confirm(
+        &self,
+        user: &UserDTO,
+        code: &str
+    ) -> Result<RecoveryCodesDTO, TwoFactorServiceError> {
+        let user_id = Arc::new(user.id.unwrap());
+        let secret = self.two_factor_repository
+            .find_by_user_id(user_id.clone())
+            .map_err(|_| {
+                TwoFactorServiceError::ServiceError(
+                    Box::from("Two-factor authentication enrolment was not started")
+                )
+            })?;
+        if secret.enabled_at.is_some() {
+            return Err(
+                TwoFactorServiceError::ServiceError(
+                    Box::from("Two-factor authentication is already enabled")
+                )
+            );
+        }
+        if !self.verify_totp(user_id.clone(), &secret.secret, &user.email, code)? {
+            return Err(TwoFactorServiceError::ServiceError(Box::from("Invalid code")));
+        }
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODES_COUNT)
+            .map(|_| generate_recovery_code())
+            .collect();
+        self.two_factor_repository
+            .enable(
+                user_id,
+                recovery_codes
+                    .iter()
+                    .map(|code| hash_token(&normalize_recovery_code(code)))
+                    .collect()
+            )
+            .map_err(TwoFactorServiceError::DieselError)?;
+        return Ok(RecoveryCodesDTO {
+            recovery_codes: recovery_codes.into_iter().map(Arc::from).collect(),
+        });
+    }
+
+    /// Disabling requires a current TOTP code, recovery codes are not accepted.
+    pub fn disable(&self, user: &UserDTO, code: &str) -> Result<(), TwoFactorServiceError> {
+        let user_id = Arc::new(user.id.unwrap());
+        let secret = match self.two_factor_repository.find_by_user_id(user_id.clone()) {
+            Ok(secret) if secret.enabled_at.is_some() => secret,
+            Ok(_) | Err(diesel::result::Error::NotFound) => {
+                return Err(
+                    TwoFactorServiceError::ServiceError(
+                        Box::from("Two-factor authentication is not enabled")
+                    )
+                );
+            }
+            Err(e) => {
+                return Err(TwoFactorServiceError::DieselError(e));
+            }
+        };
+        if !self.verify_totp(user_id.clone(), &secret.secret, &user.email, code)? {
+            return Err(TwoFactorServiceError::ServiceError(Box::from("Invalid code")));
+        }
+        self.two_factor_repository.delete(user_id).map_err(TwoFactorServiceError::DieselError)?;
+        return Ok(());
+    }
+
+    /// Stores a challenge of the login's password step and returns its id, which goes into
+    /// the challenge token. Expired challenges are cleaned up on the way.
+    pub fn issue_challenge(&self, user_id: Arc<i32>) -> Result<Uuid, TwoFactorServiceError> {
+        self.two_factor_repository
+            .delete_expired_challenges()
+            .map_err(TwoFactorServiceError::DieselError)?;
+        let jti = Uuid::new_v4();
+        let ttl = TimeDelta::seconds(CONFIGURATION.two_factor_challenge_ttl as i64);
+        let expires_at = Utc::now().naive_utc() + ttl;
+        self.two_factor_repository
+            .save_challenge(jti, user_id, expires_at)
+            .map_err(TwoFactorServiceError::DieselError)?;
+        return Ok(jti);
+    }
+
+    pub fn is_challenge_active(
+        &self,
+        user_id: Arc<i32>,
+        jti: Uuid
+    ) -> Result<bool, TwoFactorServiceError> {
+        return self.two_factor_repository
+            .is_challenge_active(jti, user_id)
+            .map_err(TwoFactorServiceError::DieselError);
+    }
+
+    /// Returns `false` when the challenge was already exchanged for a session.
+    pub fn consume_challenge(
+        &self,
+        user_id: Arc<i32>,
+        jti: Uuid
+    ) -> Result<bool, TwoFactorServiceError> {
+        return self.two_factor_repository
+            .consume_challenge(jti, user_id)
+            .map_err(TwoFactorServiceError::DieselError);
+    }
+
+    /// Checks TOTP code or one of unused recovery codes during login.
+    pub fn verify_login_code(
+        &self,
+        user: &UserDTO,
+        code: &str
+    ) -> Result<bool, TwoFactorServiceError> {
+        let user_id = Arc::new(user.id.unwrap());
+        let secret = self.two_factor_repository
+            .find_by_user_id(user_id.clone())
+            .map_err(TwoFactorServiceError::DieselError)?;
+        if secret.enabled_at.is_none() {
+            return Ok(false);
+        }
+        if self.verify_totp(user_id.clone(), &secret.secret, &user.email, code)? {
+            return Ok(true);
+        }
+        return self.two_factor_repository
+            .use_recovery_code(user_id, &hash_token(&normalize_recovery_code(code)))
+            .map_err(TwoFactorServiceError::DieselError);
+    }
+
+    fn verify_totp(
+        &self,
+        user_id: Arc<i32>,
+        secret: &str,
+        email: &str,
+        code: &str
+    ) -> Result<bool, TwoFactorServiceError> {
+        let totp = build_totp(secret, email)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| TwoFactorServiceError::ServiceError(Box::new(e)))?
+            .as_secs() as i64;
+        let current_step = now / (TOTP_STEP as i64);
+        for step in current_step - TOTP_SKEW..=current_step + TOTP_SKEW {
+            if totp.check(code.trim(), (step as u64) * TOTP_STEP) {
+                return self.two_factor_repository
+                    .use_step(user_id, step)
+                    .map_err(TwoFactorServiceError::DieselError);
+            }
+        }
+        return Ok(false);
+    }
+}
+
+fn build_totp(secret: &str, email: &str) -> Result<TOTP, TwoFactorServiceError> {
+    let secret_bytes = Secret::Encoded(secret.to_owned())
+        .to_bytes()
+        .map_err(|e| TwoFactorServiceError::ServiceError(Box::from(format!("{:?}", e))))?;
+    return TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP,
+        secret_bytes,
+        Some(CONFIGURATION.totp_issuer.clone()),
+        email.to_owned()
+    ).map_err(|e| TwoFactorServiceError::ServiceError(Box::new(e)));
+}
+
+fn generate_recovery_code() -> String {
+    let code: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(|c| (c as char).to_ascii_lowercase())
+        .collect();
+    return format!("{}-{}", &code[..5], &code[5..]);
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    return code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+}
@@ -1,30 +1,58 @@
 use core::error;
 use std::sync::Arc;
 use config::log::error;
+use rust_commons::uuid::Uuid;
 use thiserror::Error;
+use validator::ValidationErrors;
 
 use crate::{
+    cache::auth_cache::AuthCache,
     filesystem::image_storage_service::ImageStorageService,
     infra::{
-        database::{ session_repository::SessionRepository, user_repository::UserRepository },
-        domain::user::UserDTO,
+        database::{
+            session_repository::SessionRepository,
+            user_repository::{ User, UserRepository },
+        },
+        domain::{
+            pagination::{
+                CursorPageDTO,
+                CursorPageRequestDTO,
+                KeysetDTO,
+                PageDTO,
+                PageRequestDTO,
+                SortDTO,
+            },
+            user::{ UserDTO, UserFilterDTO, UserSortField },
+        },
         http::{
             middlewares::Findable,
-            requests::{ user_request::UserUpdateRequest, JsonValidator },
+            requests::{
+                user_request::{ ChangePasswordRequest, UserUpdateRequest },
+                JsonValidator,
+            },
         },
     },
+    password::{ policy::PasswordPolicy, PasswordHasher },
 };
 
+use super::email_verification_service::EmailVerificationService;
+
 pub struct UserService {
     session_repository: Arc<SessionRepository>,
     user_repository: Arc<UserRepository>,
     file_system: Arc<ImageStorageService>,
+    email_verification_service: Arc<EmailVerificationService>,
+    password_hasher: Arc<PasswordHasher>,
+    password_policy: Arc<PasswordPolicy>,
+    auth_cache: Arc<AuthCache>,
 }
 
 #[derive(Error, Debug)]
 pub enum UserServiceError {
     #[error("Database error: {0}")] DieselError(diesel::result::Error),
     #[error("{0}")] ServiceError(Box<dyn error::Error + Send + Sync + 'static>),
+    /// Reported to the client as `field_errors`.
+    #[error("{0}")] PasswordPolicyError(ValidationErrors),
 }
 
 impl Findable<UserDTO> for UserService {
@@ -40,23 +68,63 @@ impl UserService {
     pub fn new(
         user_repository: Arc<UserRepository>,
         session_repository: Arc<SessionRepository>,
-        file_system: Arc<ImageStorageService>
+        file_system: Arc<ImageStorageService>,
+        email_verification_service: Arc<EmailVerificationService>,
+        password_hasher: Arc<PasswordHasher>,
+        password_policy: Arc<PasswordPolicy>,
+        auth_cache: Arc<AuthCache>
     ) -> Arc<UserService> {
         return Arc::from(UserService {
             user_repository,
             session_repository,
             file_system,
+            email_verification_service,
+            password_hasher,
+            password_policy,
+            auth_cache,
         });
     }
 
-    pub fn find_all(&self) -> Result<Vec<UserDTO>, diesel::result::Error> {
-        let users = self.user_repository.find_all()?;
-        return Ok(UserDTO::models_to_dto(users));
+    pub fn find_page(
+        &self,
+        filter: &UserFilterDTO,
+        sort: &[SortDTO<UserSortField>],
+        page: PageRequestDTO
+    ) -> Result<PageDTO<UserDTO>, diesel::result::Error> {
+        let (users, total) = self.user_repository.find_page(filter, sort, page)?;
+        return Ok(PageDTO { data: UserDTO::models_to_dto(users), total: total as u64 });
+    }
+
+    pub fn find_cursor_page(
+        &self,
+        filter: &UserFilterDTO,
+        page: CursorPageRequestDTO
+    ) -> Result<CursorPageDTO<UserDTO>, diesel::result::Error> {
+        let (users, has_more) = self.user_repository.find_cursor_page(filter, page)?;
+        let keyset = |user: &User| KeysetDTO { created_date: user.created_date, id: user.id };
+        let (first, last) = (users.first().map(keyset), users.last().map(keyset));
+        // Rows the cursor was taken from lie in the direction opposite to the read one.
+        let (next, prev) = if page.backward {
+            (last.filter(|_| page.key.is_some()), first.filter(|_| has_more))
+        } else {
+            (last.filter(|_| has_more), first.filter(|_| page.key.is_some()))
+        };
+        return Ok(CursorPageDTO {
+            data: UserDTO::models_to_dto(users),
+            next,
+            prev,
+        });
     }
 
+    /// Served from `AuthCache` when the user was loaded recently.
     pub fn find_by_id(&self, user_id: Arc<i32>) -> Result<UserDTO, diesel::result::Error> {
-        let user = self.user_repository.find_by_id(user_id)?;
-        return Ok(UserDTO::model_to_dto(user));
+        if let Some(user) = self.auth_cache.get_user(*user_id) {
+            return Ok(user);
+        }
+        let generation = self.auth_cache.generation();
+        let user = UserDTO::model_to_dto(self.user_repository.find_by_id(user_id)?);
+        self.auth_cache.put_user(&user, generation);
+        return Ok(user);
     }
 
     pub fn update(
@@ -67,6 +135,10 @@ impl UserService {
         if let Some(name) = &update_data.name {
             current_user.name = Arc::from(name.to_string());
         }
+        if let Some(locale) = &update_data.locale {
+            current_user.locale = Some(Arc::from(locale.as_str()));
+        }
+        let mut email_changed = false;
         if let Some(email) = &update_data.email {
             if self.user_repository.find_by_email(&email).is_ok() {
                 return Err(
@@ -76,12 +148,53 @@ impl UserService {
                 );
             }
             current_user.email = Arc::from(email.to_string());
+            email_changed = true;
         }
         let user = self.user_repository
             .update(current_user)
             .map_err(UserServiceError::DieselError)?;
 
-        return Ok(UserDTO::model_to_dto(user));
+        let mut user = UserDTO::model_to_dto(user);
+        self.auth_cache.invalidate_user(user.id.unwrap());
+        if email_changed {
+            self.email_verification_service
+                .reset_verification(&mut user)
+                .map_err(|e| UserServiceError::ServiceError(Box::new(e)))?;
+        }
+        return Ok(user);
+    }
+
+    /// Changes password of the user and logs out every session except the current one.
+    pub fn change_password(
+        &self,
+        current_user: &mut UserDTO,
+        current_session: Uuid,
+        request: ChangePasswordRequest
+    ) -> Result<(), UserServiceError> {
+        if !self.password_hasher.verify(&current_user.password, &request.current_password) {
+            return Err(UserServiceError::ServiceError(Box::from("Invalid current password")));
+        }
+        self.password_policy
+            .validate(
+                "new_password",
+                &request.new_password,
+                &current_user.name,
+                &current_user.email
+            )
+            .map_err(UserServiceError::PasswordPolicyError)?;
+        let hashed_password = self.password_hasher
+            .hash(&request.new_password)
+            .map_err(|e| UserServiceError::ServiceError(Box::new(e)))?;
+        let user_id = Arc::new(current_user.id.unwrap());
+        let user = self.user_repository
+            .update_password(user_id.clone(), &hashed_password)
+            .map_err(UserServiceError::DieselError)?;
+        *current_user = UserDTO::model_to_dto(user);
+        self.session_repository
+            .delete_by_user_id(user_id.clone(), Some(current_session))
+            .map_err(UserServiceError::DieselError)?;
+        self.auth_cache.invalidate_user(*user_id);
+        return Ok(());
     }
 
     pub fn delete(
@@ -90,7 +203,8 @@ impl UserService {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
         let user_id_ref = Arc::new(user.id.unwrap());
         self.user_repository.delete(user_id_ref.clone())?;
-        self.session_repository.delete_by_user_id(user_id_ref)?;
+        self.session_repository.delete_by_user_id(user_id_ref.clone(), None)?;
+        self.auth_cache.invalidate_user(*user_id_ref);
         if let Some(avatar) = &user.avatar {
             self.file_system.remove_file_image(avatar)?;
         }
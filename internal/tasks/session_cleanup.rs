@@ -0,0 +1,45 @@
+use std::{ sync::Arc, time::Duration };
+
+use actix_web::{ rt, web };
+use config::{ log::{ error, info }, CONFIGURATION };
+
+use crate::services::auth_service::AuthService;
+
+/// Spawns a task on the current runtime which periodically deletes expired sessions,
+/// stale failed login counters and old login history, and logs auth cache counters.
+pub fn start_session_cleanup(auth_service: Arc<AuthService>) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(
+            Duration::from_secs(CONFIGURATION.session_prune_interval)
+        );
+        loop {
+            interval.tick().await;
+            let service = Arc::clone(&auth_service);
+            match web::block(move || service.prune_expired_sessions()).await {
+                Ok(Ok(pruned)) => info!("Pruned {} expired sessions", pruned),
+                Ok(Err(e)) => error!("Failed to prune expired sessions - [{}]", e),
+                Err(e) => error!("Failed to prune expired sessions - [{}]", e),
+            }
+            let service = Arc::clone(&auth_service);
+            match web::block(move || service.prune_login_attempts()).await {
+                Ok(Ok(pruned)) => info!("Pruned {} stale login attempt counters", pruned),
+                Ok(Err(e)) => error!("Failed to prune login attempts - [{}]", e),
+                Err(e) => error!("Failed to prune login attempts - [{}]", e),
+            }
+            let service = Arc::clone(&auth_service);
+            match web::block(move || service.prune_login_events()).await {
+                Ok(Ok(pruned)) => info!("Pruned {} old login events", pruned),
+                Ok(Err(e)) => error!("Failed to prune login events - [{}]", e),
+                Err(e) => error!("Failed to prune login events - [{}]", e),
+            }
+            let stats = auth_service.auth_cache_stats();
+            info!(
+                "Auth cache: {} session hits, {} session misses, {} user hits, {} user misses",
+                stats.session_hits,
+                stats.session_misses,
+                stats.user_hits,
+                stats.user_misses
+            );
+        }
+    });
+}
@@ -0,0 +1,86 @@
+//! Integration tests of the routes available to API keys.
+mod common;
+
+use actix_web::{ body::MessageBody, dev::{ Service, ServiceResponse }, http::StatusCode, test };
+use common::{ connection, init, register };
+use diesel::{ sql_query, sql_types::Text, RunQueryDsl };
+use serde_json::{ json, Value };
+
+/// Creates an API key of the user limited to `scopes` and returns the key.
+async fn create_api_key<S, B>(app: &S, access_token: &str, scopes: &[&str]) -> String
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/api-keys")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .set_json(json!({ "name": "Machine", "scopes": scopes }))
+        .to_request();
+    let created: Value = test::call_and_read_body_json(app, request).await;
+    return created["key"].as_str().expect("key is returned on creation").to_owned();
+}
+
+async fn call_with_key<S, B>(app: &S, request: test::TestRequest, key: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = request.insert_header(("X-API-Key", key)).to_request();
+    return test::call_service(app, request).await.status();
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn scoped_key_reaches_permission_route() {
+    let (container, _, app) = init().await;
+    let (email, access_token) = register(&app).await;
+    container.services.role_service.grant_by_email(&email, "admin").unwrap();
+    sql_query("UPDATE users SET email_verified_at = now() WHERE email = $1")
+        .bind::<Text, _>(&email)
+        .execute(&mut connection())
+        .unwrap();
+    let scoped_key = create_api_key(&app, &access_token, &["users.read"]).await;
+    let unscoped_key = create_api_key(&app, &access_token, &[]).await;
+
+    let list = || test::TestRequest::get().uri("/api/v1/user/all");
+    assert_eq!(call_with_key(&app, list(), &scoped_key).await, StatusCode::OK);
+    assert_eq!(call_with_key(&app, list(), &unscoped_key).await, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn key_is_rejected_on_account_routes() {
+    let (container, _, app) = init().await;
+    let (email, access_token) = register(&app).await;
+    container.services.role_service.grant_by_email(&email, "admin").unwrap();
+    let key = create_api_key(&app, &access_token, &["users.read"]).await;
+
+    let requests = [
+        test::TestRequest::get().uri("/api/v1/user"),
+        test::TestRequest::put().uri("/api/v1/user").set_json(json!({ "name": "Renamed" })),
+        test::TestRequest::delete().uri("/api/v1/user"),
+        test::TestRequest
+            ::put()
+            .uri("/api/v1/user/password")
+            .set_json(json!({ "current_password": "x", "new_password": "y" })),
+        test::TestRequest::get().uri("/api/v1/user/logins"),
+        test::TestRequest::get().uri("/api/v1/auth/sessions"),
+        test::TestRequest::delete().uri("/api/v1/auth/sessions"),
+        test::TestRequest::post().uri("/api/v1/auth/2fa/enroll"),
+        test::TestRequest::get().uri("/api/v1/auth/api-keys"),
+        test::TestRequest::post().uri("/api/v1/auth/api-keys").set_json(json!({ "name": "New" })),
+    ];
+    for request in requests {
+        assert_eq!(call_with_key(&app, request, &key).await, StatusCode::FORBIDDEN);
+    }
+
+    let request = test::TestRequest
+        ::get()
+        .uri("/api/v1/user")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .to_request();
+    let user: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(user["name"], "Tester");
+}
@@ -0,0 +1,78 @@
+//! Setup shared by the integration tests, they need the database configured by the usual
+//! environment variables and run with `cargo test -p internal -- --ignored`.
+#![allow(dead_code)]
+use std::{ fs, sync::{ Arc, RwLock } };
+
+use actix_web::{ body::MessageBody, dev::{ Service, ServiceResponse }, test, App };
+use config::{ Configuration, CONFIGURATION };
+use diesel::{ r2d2::{ ConnectionManager, Pool }, Connection, PgConnection };
+use internal::{
+    container::container::{ with_notifier, Container },
+    infra::http::routes,
+    migrate,
+    notifications::memory_notifier::MemoryNotifier,
+};
+use rust_commons::uuid::Uuid;
+use serde_json::{ json, Value };
+
+pub const PASSWORD: &str = "correct-Horse-battery-9";
+
+/// Builds the container and the app serving all routes on top of it. Notifications sent by the
+/// app are kept in the returned notifier.
+pub async fn init() -> (
+    Arc<Container>,
+    Arc<MemoryNotifier>,
+    impl Service<
+        actix_http::Request,
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error
+    >,
+) {
+    migrate::<Configuration>(&CONFIGURATION).unwrap();
+    fs::create_dir_all(&CONFIGURATION.file_storage_location).unwrap();
+    let notifier = Arc::new(MemoryNotifier::new());
+    let container = Arc::new(with_notifier(notifier.clone()).unwrap());
+    let app = test::init_service(
+        App::new().configure(|cfg| routes::init_routes(cfg, Arc::clone(&container)))
+    ).await;
+    return (container, notifier, app);
+}
+
+/// Connection for preparing state which can not be reached through the API.
+pub fn connection() -> PgConnection {
+    return PgConnection::establish(&database_url()).unwrap();
+}
+
+/// Pool for building repositories directly, e.g. to call them from several threads at once.
+pub fn pool() -> Arc<RwLock<Pool<ConnectionManager<PgConnection>>>> {
+    let pool = Pool::builder().max_size(10).build(ConnectionManager::new(database_url())).unwrap();
+    return Arc::new(RwLock::new(pool));
+}
+
+fn database_url() -> String {
+    return format!(
+        "postgres://{}:{}@{}/{}?sslmode=disable",
+        CONFIGURATION.database_user,
+        CONFIGURATION.database_password,
+        CONFIGURATION.database_host,
+        CONFIGURATION.database_name
+    );
+}
+
+/// Registers a user with a unique email and [`PASSWORD`], returns the email with the access
+/// token.
+pub async fn register<S, B>(app: &S) -> (String, String)
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let email = format!("test_{}@example.com", Uuid::new_v4().simple());
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/register")
+        .set_json(json!({ "name": "Tester", "password": PASSWORD, "email": email }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(app, request).await;
+    let token = body["token"].as_str().expect("token is issued on registration").to_owned();
+    return (email, token);
+}
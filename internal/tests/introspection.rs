@@ -0,0 +1,169 @@
+//! Integration tests of `/auth/introspect`.
+mod common;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ Service, ServiceResponse },
+    http::StatusCode,
+    test,
+};
+use common::{ init, register };
+use internal::{ container::container::Container, jwt::JwtKeys, services::auth_service::Claims };
+use serde_json::{ json, Value };
+
+/// Registers an admin allowed to introspect tokens and returns the access token.
+async fn register_admin<S, B>(app: &S, container: &Container) -> String
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let (email, token) = register(app).await;
+    container.services.role_service.grant_by_email(&email, "admin").unwrap();
+    return token;
+}
+
+async fn introspect<S, B>(app: &S, admin_token: &str, token: &str) -> Value
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/introspect")
+        .insert_header(("Authorization", format!("Bearer {}", admin_token)))
+        .set_form([("token", token)])
+        .to_request();
+    return test::call_and_read_body_json(app, request).await;
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn active_token_is_described() {
+    let (container, _, app) = init().await;
+    let admin_token = register_admin(&app, &container).await;
+    let (email, token) = register(&app).await;
+    let claims = container.services.auth_service.decode_claims(&token).unwrap();
+
+    let body = introspect(&app, &admin_token, &token).await;
+
+    assert_eq!(body["active"], true);
+    assert_eq!(body["sub"], claims.user_id.to_string());
+    assert_eq!(body["username"], email);
+    assert_eq!(body["token_type"], "access_token");
+    assert_eq!(body["sid"], claims.uuid.to_string());
+    assert_eq!(body["exp"], claims.exp);
+    assert_eq!(body["scope"], "");
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn introspection_requires_permission() {
+    let (_, _, app) = init().await;
+    let (_, token) = register(&app).await;
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/introspect")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_form([("token", token.as_str())])
+        .to_request();
+
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn token_is_inactive_after_logout() {
+    let (container, _, app) = init().await;
+    let admin_token = register_admin(&app, &container).await;
+    let (_, token) = register(&app).await;
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/logout")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .to_request();
+    assert!(test::call_service(&app, request).await.status().is_success());
+
+    let body = introspect(&app, &admin_token, &token).await;
+
+    assert_eq!(body, json!({ "active": false }));
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn token_is_inactive_after_user_deletion() {
+    let (container, _, app) = init().await;
+    let admin_token = register_admin(&app, &container).await;
+    let (_, token) = register(&app).await;
+    let request = test::TestRequest
+        ::delete()
+        .uri("/api/v1/user")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .to_request();
+    assert!(test::call_service(&app, request).await.status().is_success());
+
+    let body = introspect(&app, &admin_token, &token).await;
+
+    assert_eq!(body, json!({ "active": false }));
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn expired_token_is_inactive() {
+    let (container, _, app) = init().await;
+    let admin_token = register_admin(&app, &container).await;
+    let (_, token) = register(&app).await;
+    let claims = container.services.auth_service.decode_claims(&token).unwrap();
+    // Same session, but signed with an expiry in the past.
+    let expired = JwtKeys::from_configuration()
+        .unwrap()
+        .encode(
+            &(Claims {
+                exp: claims.exp - 24 * 60 * 60,
+                ..claims
+            })
+        )
+        .unwrap();
+
+    let body = introspect(&app, &admin_token, &expired).await;
+
+    assert_eq!(body, json!({ "active": false }));
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn malformed_token_is_inactive() {
+    let (container, _, app) = init().await;
+    let admin_token = register_admin(&app, &container).await;
+
+    let body = introspect(&app, &admin_token, "not a token").await;
+
+    assert_eq!(body, json!({ "active": false }));
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn api_key_usage_is_not_recorded() {
+    let (container, _, app) = init().await;
+    let admin_token = register_admin(&app, &container).await;
+    let (_, token) = register(&app).await;
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/api-keys")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(json!({ "name": "Introspected" }))
+        .to_request();
+    let created: Value = test::call_and_read_body_json(&app, request).await;
+
+    let body = introspect(&app, &admin_token, created["key"].as_str().unwrap()).await;
+
+    assert_eq!(body["active"], true);
+    let request = test::TestRequest
+        ::get()
+        .uri("/api/v1/auth/api-keys")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .to_request();
+    let api_keys: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(api_keys[0]["last_used_at"], Value::Null);
+}
@@ -0,0 +1,52 @@
+//! Integration tests of the failed attempt counters behind login and login link throttling.
+mod common;
+
+use std::{ sync::Barrier, thread };
+
+use chrono::{ TimeDelta, Utc };
+use common::pool;
+use internal::infra::database::login_attempt_repository::{
+    LoginAttemptRepository,
+    LoginAttemptScope,
+};
+use rust_commons::uuid::Uuid;
+
+#[test]
+#[ignore = "requires database"]
+fn concurrent_failures_are_all_counted() {
+    let repository = LoginAttemptRepository::new(pool());
+    let identifier = Uuid::new_v4().to_string();
+    let window_start = Utc::now().naive_utc() - TimeDelta::minutes(15);
+    let barrier = Barrier::new(10);
+
+    thread::scope(|scope| {
+        for _ in 0..10 {
+            scope.spawn(|| {
+                barrier.wait();
+                repository
+                    .register_failure(LoginAttemptScope::Ip, &identifier, window_start)
+                    .unwrap();
+            });
+        }
+    });
+
+    let attempt = repository.find(LoginAttemptScope::Ip, &identifier).unwrap().unwrap();
+    assert_eq!(attempt.failed_attempts, 10);
+}
+
+#[test]
+#[ignore = "requires database"]
+fn counter_starts_over_after_window() {
+    let repository = LoginAttemptRepository::new(pool());
+    let identifier = Uuid::new_v4().to_string();
+    let window_start = Utc::now().naive_utc() - TimeDelta::minutes(15);
+    repository.register_failure(LoginAttemptScope::Ip, &identifier, window_start).unwrap();
+    repository.register_failure(LoginAttemptScope::Ip, &identifier, window_start).unwrap();
+
+    let later_window_start = Utc::now().naive_utc();
+    let attempt = repository
+        .register_failure(LoginAttemptScope::Ip, &identifier, later_window_start)
+        .unwrap();
+
+    assert_eq!(attempt.failed_attempts, 1);
+}
@@ -0,0 +1,180 @@
+//! Integration tests of `/auth/logout`. Cookie tests run only with `AUTH_COOKIE_MODE = true`.
+mod common;
+
+use actix_web::{
+    body::MessageBody,
+    cookie::Cookie,
+    dev::{ Service, ServiceResponse },
+    http::StatusCode,
+    test,
+};
+use common::{ init, register, PASSWORD };
+use config::CONFIGURATION;
+use rust_commons::uuid::Uuid;
+use serde_json::{ json, Value };
+
+fn cookie_mode() -> bool {
+    if !CONFIGURATION.auth_cookie_mode {
+        eprintln!("Cookie mode is not enabled, skipping");
+    }
+    return CONFIGURATION.auth_cookie_mode;
+}
+
+/// Logs in and returns the access and refresh tokens from the body.
+async fn login<S, B>(app: &S, email: &str) -> (String, String)
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/login")
+        .set_json(json!({ "email": email, "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(app, request).await;
+    return (
+        body["token"].as_str().unwrap().to_owned(),
+        body["refresh_token"].as_str().unwrap().to_owned(),
+    );
+}
+
+async fn refresh<S, B>(app: &S, refresh_token: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/refresh")
+        .set_json(json!({ "refresh_token": refresh_token }))
+        .to_request();
+    return test::call_service(app, request).await.status();
+}
+
+async fn sessions_status<S, B>(app: &S, access_token: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::get()
+        .uri("/api/v1/auth/sessions")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .to_request();
+    return test::call_service(app, request).await.status();
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn logout_revokes_session_of_access_token() {
+    if cookie_mode() {
+        return;
+    }
+    let (_, _, app) = init().await;
+    let (email, _) = register(&app).await;
+    let (access_token, refresh_token) = login(&app, &email).await;
+
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/logout")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+
+    assert_eq!(sessions_status(&app, &access_token).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(refresh(&app, &refresh_token).await, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn logout_with_invalid_access_token_uses_refresh_token() {
+    if cookie_mode() {
+        return;
+    }
+    let (_, _, app) = init().await;
+    let (email, _) = register(&app).await;
+    let (access_token, refresh_token) = login(&app, &email).await;
+
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/logout")
+        .insert_header(("Authorization", "Bearer expired"))
+        .set_json(json!({ "refresh_token": refresh_token }))
+        .to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+
+    assert_eq!(sessions_status(&app, &access_token).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(refresh(&app, &refresh_token).await, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn logout_without_tokens_is_rejected() {
+    if cookie_mode() {
+        return;
+    }
+    let (_, _, app) = init().await;
+
+    let request = test::TestRequest::post().uri("/api/v1/auth/logout").to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn cookie_logout_uses_refresh_cookie_and_clears_cookies() {
+    if !cookie_mode() {
+        return;
+    }
+    let (_, _, app) = init().await;
+    let email = format!("test_{}@example.com", Uuid::new_v4().simple());
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/register")
+        .set_json(json!({ "name": "Tester", "password": PASSWORD, "email": email }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    let cookies: Vec<Cookie> = response
+        .response()
+        .cookies()
+        .map(|cookie| cookie.into_owned())
+        .collect();
+    let cookie = |name: &str| cookies.iter().find(|cookie| cookie.name() == name).unwrap().clone();
+    let refresh_cookie = cookie("refresh_token");
+    let csrf_cookie = cookie("csrf_token");
+
+    // The access token has expired, only the refresh token and CSRF cookies are left.
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/logout")
+        .cookie(refresh_cookie.clone())
+        .to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::FORBIDDEN);
+
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/logout")
+        .cookie(refresh_cookie.clone())
+        .cookie(csrf_cookie.clone())
+        .insert_header(("X-CSRF-Token", csrf_cookie.value()))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let cleared: Vec<String> = response
+        .response()
+        .cookies()
+        .filter(|cookie| cookie.value().is_empty())
+        .map(|cookie| cookie.name().to_owned())
+        .collect();
+    for name in ["access_token", "refresh_token", "csrf_token"] {
+        assert!(cleared.iter().any(|cleared| cleared == name), "{} is not cleared", name);
+    }
+
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/refresh")
+        .cookie(refresh_cookie)
+        .cookie(csrf_cookie.clone())
+        .insert_header(("X-CSRF-Token", csrf_cookie.value()))
+        .to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,106 @@
+//! Integration tests of `/auth/magic-link` and `/auth/magic-link/consume`.
+mod common;
+
+use std::net::SocketAddr;
+
+use actix_web::{ body::MessageBody, dev::{ Service, ServiceResponse }, http::StatusCode, test };
+use common::{ init, register };
+use internal::notifications::{ memory_notifier::MemoryNotifier, Notification };
+use rust_commons::uuid::Uuid;
+use serde_json::json;
+
+async fn request_link<S, B>(app: &S, email: &str, peer_addr: Option<SocketAddr>) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let mut request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/magic-link")
+        .set_json(json!({ "email": email }));
+    if let Some(peer_addr) = peer_addr {
+        request = request.peer_addr(peer_addr);
+    }
+    return test::call_service(app, request.to_request()).await.status();
+}
+
+fn last_token(notifier: &MemoryNotifier, email: &str) -> String {
+    match notifier.last_for(email) {
+        Some(Notification::MagicLink { token, .. }) => {
+            return token.to_string();
+        }
+        notification => panic!("expected a login link, got {:?}", notification),
+    }
+}
+
+async fn consume<S, B>(app: &S, token: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/magic-link/consume")
+        .set_json(json!({ "token": token }))
+        .to_request();
+    return test::call_service(app, request).await.status();
+}
+
+/// Address no other test uses, so counters of the IP start from zero.
+fn unique_peer_addr() -> SocketAddr {
+    let bytes = Uuid::new_v4().into_bytes();
+    return SocketAddr::from(([10, bytes[0], bytes[1], bytes[2]], 40000));
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn earlier_link_stays_valid() {
+    let (_, notifier, app) = init().await;
+    let (email, _) = register(&app).await;
+    assert_eq!(request_link(&app, &email, None).await, StatusCode::OK);
+    let first_token = last_token(&notifier, &email);
+    assert_eq!(request_link(&app, &email, None).await, StatusCode::OK);
+    let second_token = last_token(&notifier, &email);
+
+    assert_eq!(consume(&app, &first_token).await, StatusCode::OK);
+    assert_eq!(consume(&app, &first_token).await, StatusCode::BAD_REQUEST);
+    assert_eq!(consume(&app, &second_token).await, StatusCode::OK);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn requests_are_limited_per_email() {
+    let (_, _, app) = init().await;
+    let (email, _) = register(&app).await;
+    let unknown_email = format!("unknown_{}@example.com", Uuid::new_v4().simple());
+
+    for email in [&email, &unknown_email] {
+        for _ in 0..3 {
+            assert_eq!(request_link(&app, email, None).await, StatusCode::OK);
+        }
+        let request = test::TestRequest
+            ::post()
+            .uri("/api/v1/auth/magic-link")
+            .set_json(json!({ "email": email.to_uppercase() }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("Retry-After"));
+    }
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn requests_are_limited_per_ip() {
+    let (_, _, app) = init().await;
+    let peer_addr = unique_peer_addr();
+
+    for _ in 0..10 {
+        let email = format!("unknown_{}@example.com", Uuid::new_v4().simple());
+        assert_eq!(request_link(&app, &email, Some(peer_addr)).await, StatusCode::OK);
+    }
+    let email = format!("unknown_{}@example.com", Uuid::new_v4().simple());
+    let status = request_link(&app, &email, Some(peer_addr)).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(request_link(&app, &email, Some(unique_peer_addr())).await, StatusCode::OK);
+}
@@ -0,0 +1,156 @@
+//! Integration tests of the OpenID Connect login against the `mock` provider, configured as in
+//! `.env.example` and served by mock-oauth2-server from the docker compose setup.
+mod common;
+
+use actix_web::{
+    body::MessageBody,
+    cookie::Cookie,
+    dev::{ Service, ServiceResponse },
+    http::{ header::LOCATION, StatusCode },
+    test,
+};
+use common::init;
+use config::CONFIGURATION;
+use internal::container::container::Container;
+use rust_commons::uuid::Uuid;
+use serde_json::{ json, Value };
+
+const PROVIDER: &str = "mock";
+
+fn is_configured() -> bool {
+    let configured = CONFIGURATION.oidc_providers.iter().any(|provider| provider.name == PROVIDER);
+    if !configured {
+        eprintln!("OIDC provider [{}] is not configured, skipping", PROVIDER);
+    }
+    return configured;
+}
+
+/// Starts login, signs in as `subject` on the provider's form and returns the callback URI
+/// together with the binding cookie set by start.
+async fn authorize<S, B>(app: &S, subject: &str, email: &str) -> (String, Cookie<'static>)
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::get()
+        .uri(&format!("/api/v1/auth/oidc/{}/start", PROVIDER))
+        .to_request();
+    let response = test::call_service(app, request).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let binding = response
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == "oidc_binding")
+        .expect("binding cookie is set on start")
+        .into_owned();
+    let authorization_url = response.headers().get(LOCATION).unwrap().to_str().unwrap();
+
+    // Submits the form the mock shows on interactive login, it redirects back with the code.
+    let http_client = reqwest::Client
+        ::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let claims = json!({ "email": email, "email_verified": true, "name": "OIDC tester" });
+    let provider_response = http_client
+        .post(authorization_url)
+        .form(&[("username", subject), ("claims", &claims.to_string())])
+        .send().await
+        .unwrap();
+    assert!(provider_response.status().is_redirection());
+    let callback_url = reqwest::Url
+        ::parse(provider_response.headers()[LOCATION.as_str()].to_str().unwrap())
+        .unwrap();
+    assert!(callback_url.as_str().starts_with(&CONFIGURATION.app_url));
+    let callback_uri = format!(
+        "{}?{}",
+        callback_url.path(),
+        callback_url.query().unwrap_or_default()
+    );
+    return (callback_uri, binding);
+}
+
+/// Goes through start, the provider's login form and callback, signing in as `subject`.
+async fn sign_in<S, B>(app: &S, subject: &str, email: &str) -> Value
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let (callback_uri, binding) = authorize(app, subject, email).await;
+    let request = test::TestRequest::get().uri(&callback_uri).cookie(binding).to_request();
+    let response = test::call_service(app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let cleared = response
+        .response()
+        .cookies()
+        .any(|cookie| cookie.name() == "oidc_binding" && cookie.value().is_empty());
+    assert!(cleared, "binding cookie is cleared on callback");
+    return test::read_body_json(response).await;
+}
+
+fn user_id(container: &Container, body: &Value) -> i32 {
+    let token = body["token"].as_str().expect("token is issued on login");
+    return *container.services.auth_service.decode_claims(token).unwrap().user_id;
+}
+
+#[actix_web::test]
+#[ignore = "requires database and the mock OIDC issuer"]
+async fn login_creates_and_reuses_user() {
+    if !is_configured() {
+        return;
+    }
+    let (container, _, app) = init().await;
+    let subject = Uuid::new_v4().simple().to_string();
+    let email = format!("oidc_{}@example.com", subject);
+
+    let first = sign_in(&app, &subject, &email).await;
+    let second = sign_in(&app, &subject, &email).await;
+
+    assert_eq!(user_id(&container, &first), user_id(&container, &second));
+    let user = container.services.user_service.find_by_id(user_id(&container, &first).into());
+    assert_eq!(*user.unwrap().email, *email);
+}
+
+#[actix_web::test]
+#[ignore = "requires database and the mock OIDC issuer"]
+async fn login_after_account_deletion_creates_new_user() {
+    if !is_configured() {
+        return;
+    }
+    let (container, _, app) = init().await;
+    let subject = Uuid::new_v4().simple().to_string();
+    let email = format!("oidc_{}@example.com", subject);
+    let first = sign_in(&app, &subject, &email).await;
+    let request = test::TestRequest
+        ::delete()
+        .uri("/api/v1/user")
+        .insert_header(("Authorization", format!("Bearer {}", first["token"].as_str().unwrap())))
+        .to_request();
+    assert!(test::call_service(&app, request).await.status().is_success());
+
+    let second = sign_in(&app, &subject, &email).await;
+
+    assert_ne!(user_id(&container, &first), user_id(&container, &second));
+}
+
+#[actix_web::test]
+#[ignore = "requires database and the mock OIDC issuer"]
+async fn callback_is_rejected_in_another_browser() {
+    if !is_configured() {
+        return;
+    }
+    let (_, _, app) = init().await;
+    let subject = Uuid::new_v4().simple().to_string();
+    let email = format!("oidc_{}@example.com", subject);
+
+    let (callback_uri, _) = authorize(&app, &subject, &email).await;
+    let request = test::TestRequest::get().uri(&callback_uri).to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::BAD_REQUEST);
+
+    // The victim's browser has a binding of its own login, which does not match the state.
+    let (callback_uri, _) = authorize(&app, &subject, &email).await;
+    let (_, victim_binding) = authorize(&app, &subject, &email).await;
+    let request = test::TestRequest::get().uri(&callback_uri).cookie(victim_binding).to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::UNAUTHORIZED);
+}
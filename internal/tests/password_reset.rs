@@ -0,0 +1,129 @@
+//! Integration tests of `/auth/password/forgot` and `/auth/password/reset`.
+mod common;
+
+use actix_web::{ body::MessageBody, dev::{ Service, ServiceResponse }, http::StatusCode, test };
+use common::{ connection, init, register, PASSWORD };
+use diesel::RunQueryDsl;
+use internal::notifications::{ memory_notifier::MemoryNotifier, Notification };
+use serde_json::json;
+
+const NEW_PASSWORD: &str = "staple-Battery-horse-4";
+
+/// Requests a reset of the user's password and returns the token sent to them.
+async fn forgot_password<S, B>(app: &S, notifier: &MemoryNotifier, email: &str) -> String
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/password/forgot")
+        .set_json(json!({ "email": email }))
+        .to_request();
+    assert_eq!(test::call_service(app, request).await.status(), StatusCode::OK);
+    match notifier.last_for(email) {
+        Some(Notification::PasswordReset { token, .. }) => {
+            return token.to_string();
+        }
+        notification => panic!("expected a password reset, got {:?}", notification),
+    }
+}
+
+async fn reset_password<S, B>(app: &S, token: &str, password: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/password/reset")
+        .set_json(json!({ "token": token, "password": password }))
+        .to_request();
+    return test::call_service(app, request).await.status();
+}
+
+async fn login<S, B>(app: &S, email: &str, password: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/login")
+        .set_json(json!({ "email": email, "password": password }))
+        .to_request();
+    return test::call_service(app, request).await.status();
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn reset_changes_password_and_revokes_sessions() {
+    let (_, notifier, app) = init().await;
+    let (email, access_token) = register(&app).await;
+    let token = forgot_password(&app, &notifier, &email).await;
+
+    assert_eq!(reset_password(&app, &token, NEW_PASSWORD).await, StatusCode::OK);
+
+    let request = test::TestRequest
+        ::get()
+        .uri("/api/v1/auth/sessions")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(login(&app, &email, PASSWORD).await, StatusCode::BAD_REQUEST);
+    assert_eq!(login(&app, &email, NEW_PASSWORD).await, StatusCode::OK);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn used_token_is_rejected() {
+    let (_, notifier, app) = init().await;
+    let (email, _) = register(&app).await;
+    let token = forgot_password(&app, &notifier, &email).await;
+    assert_eq!(reset_password(&app, &token, NEW_PASSWORD).await, StatusCode::OK);
+
+    let status = reset_password(&app, &token, "another-Staple-horse-5").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(login(&app, &email, NEW_PASSWORD).await, StatusCode::OK);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn expired_token_is_rejected() {
+    let (_, notifier, app) = init().await;
+    let (email, _) = register(&app).await;
+    let token = forgot_password(&app, &notifier, &email).await;
+    diesel
+        ::sql_query(
+            "UPDATE password_resets SET expires_at = NOW() - INTERVAL '1 minute'
+             WHERE user_id = (SELECT id FROM users WHERE email = $1)"
+        )
+        .bind::<diesel::sql_types::Text, _>(&email)
+        .execute(&mut connection())
+        .unwrap();
+
+    let status = reset_password(&app, &token, NEW_PASSWORD).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(login(&app, &email, PASSWORD).await, StatusCode::OK);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn reset_is_sent_in_user_locale() {
+    let (_, notifier, app) = init().await;
+    let (email, access_token) = register(&app).await;
+    let request = test::TestRequest
+        ::put()
+        .uri("/api/v1/user")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .set_json(json!({ "locale": "uk" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), StatusCode::OK);
+
+    forgot_password(&app, &notifier, &email).await;
+
+    let notification = notifier.last_for(&email).unwrap();
+    assert_eq!(notification.locale(), Some("uk"));
+}
@@ -0,0 +1,99 @@
+//! Integration tests of the login with two-factor authentication.
+mod common;
+
+use actix_web::{ body::MessageBody, dev::{ Service, ServiceResponse }, http::StatusCode, test };
+use common::{ init, register, PASSWORD };
+use serde_json::{ json, Value };
+use totp_rs::{ Algorithm, Secret, TOTP };
+
+/// Enables two-factor authentication of the user and returns the recovery codes.
+async fn enable_two_factor<S, B>(app: &S, access_token: &str) -> Vec<String>
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/2fa/enroll")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .to_request();
+    let enrollment: Value = test::call_and_read_body_json(app, request).await;
+    let secret = Secret::Encoded(enrollment["secret"].as_str().unwrap().to_owned());
+    let totp = TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        30,
+        secret.to_bytes().unwrap(),
+        None,
+        "tester".to_owned()
+    ).unwrap();
+
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/2fa/confirm")
+        .insert_header(("Authorization", format!("Bearer {}", access_token)))
+        .set_json(json!({ "code": totp.generate_current().unwrap() }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(app, request).await;
+    return body["recovery_codes"]
+        .as_array()
+        .expect("recovery codes are returned on confirmation")
+        .iter()
+        .map(|code| code.as_str().unwrap().to_owned())
+        .collect();
+}
+
+async fn login_challenge<S, B>(app: &S, email: &str) -> String
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/login")
+        .set_json(json!({ "email": email, "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(app, request).await;
+    return body["challenge_token"].as_str().expect("challenge is issued").to_owned();
+}
+
+async fn verify<S, B>(app: &S, challenge_token: &str, code: &str) -> StatusCode
+    where
+        S: Service<actix_http::Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody
+{
+    let request = test::TestRequest
+        ::post()
+        .uri("/api/v1/auth/2fa/verify")
+        .set_json(json!({ "challenge_token": challenge_token, "code": code }))
+        .to_request();
+    return test::call_service(app, request).await.status();
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn challenge_token_is_single_use() {
+    let (_, _, app) = init().await;
+    let (email, access_token) = register(&app).await;
+    let recovery_codes = enable_two_factor(&app, &access_token).await;
+    let challenge_token = login_challenge(&app, &email).await;
+
+    assert_eq!(verify(&app, &challenge_token, &recovery_codes[0]).await, StatusCode::OK);
+    assert_eq!(verify(&app, &challenge_token, &recovery_codes[1]).await, StatusCode::UNAUTHORIZED);
+
+    let challenge_token = login_challenge(&app, &email).await;
+    assert_eq!(verify(&app, &challenge_token, &recovery_codes[1]).await, StatusCode::OK);
+}
+
+#[actix_web::test]
+#[ignore = "requires database"]
+async fn challenge_survives_invalid_code() {
+    let (_, _, app) = init().await;
+    let (email, access_token) = register(&app).await;
+    let recovery_codes = enable_two_factor(&app, &access_token).await;
+    let challenge_token = login_challenge(&app, &email).await;
+
+    assert_eq!(verify(&app, &challenge_token, "000000").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(verify(&app, &challenge_token, &recovery_codes[0]).await, StatusCode::OK);
+}